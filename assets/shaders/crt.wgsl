@@ -0,0 +1,34 @@
+#import bevy_sprite::mesh2d_view_bindings
+
+@group(1) @binding(0)
+var screen_texture: texture_2d<f32>;
+@group(1) @binding(1)
+var screen_sampler: sampler;
+
+// How far the corners bend in, how far apart the colour channels drift at the edges,
+// and how dark the gaps between scanlines get.
+let CURVATURE: f32 = 0.06;
+let FRINGE: f32 = 0.003;
+let SCANLINE_DEPTH: f32 = 0.2;
+
+@fragment
+fn fragment(
+    @builtin(position) position: vec4<f32>,
+    #import bevy_sprite::mesh2d_vertex_output
+) -> @location(0) vec4<f32> {
+    var centred = position.xy / vec2<f32>(view.width, view.height) * 2.0 - 1.0;
+    centred = centred * (1.0 + centred.yx * centred.yx * CURVATURE);
+    if (abs(centred.x) > 1.0 || abs(centred.y) > 1.0) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+    let screen_uv = centred * 0.5 + 0.5;
+
+    let fringe = centred * FRINGE;
+    let red = textureSample(screen_texture, screen_sampler, screen_uv + fringe).r;
+    let green = textureSample(screen_texture, screen_sampler, screen_uv).g;
+    let blue = textureSample(screen_texture, screen_sampler, screen_uv - fringe).b;
+
+    let scanline = 1.0 - SCANLINE_DEPTH * (0.5 + 0.5 * sin(screen_uv.y * view.height * 3.14159));
+    let vignette = 1.0 - 0.3 * dot(centred, centred) * 0.5;
+    return vec4<f32>(vec3<f32>(red, green, blue) * scanline * vignette, 1.0);
+}
@@ -0,0 +1,41 @@
+// Times the hottest gameplay systems over synthetic worlds of growing size, see bench_worlds.rs.
+// Run with `cargo bench`. Building each world isn't timed, only running the systems on it.
+use asteroids::bench_worlds::SyntheticWorld;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+const WORLD_SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+fn bench_worlds(c: &mut Criterion, name: &str, build: fn(usize) -> SyntheticWorld) {
+    let mut group = c.benchmark_group(name);
+    // The big worlds take a while to build, a few samples are enough to see a trend.
+    group.sample_size(10);
+    for size in WORLD_SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter_batched(
+                || build(size),
+                |mut world| {
+                    world.run();
+                    // Handed back so dropping it isn't timed.
+                    world
+                },
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn collisions(c: &mut Criterion) {
+    bench_worlds(c, "collisions", SyntheticWorld::near_misses);
+}
+
+fn meteor_splits(c: &mut Criterion) {
+    bench_worlds(c, "meteor_splits", SyntheticWorld::splits);
+}
+
+fn screen_edges(c: &mut Criterion) {
+    bench_worlds(c, "screen_wrap_and_despawn", SyntheticWorld::screen_edges);
+}
+
+criterion_group!(benches, collisions, meteor_splits, screen_edges);
+criterion_main!(benches);
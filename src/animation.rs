@@ -0,0 +1,432 @@
+// Every sprite sheet and the ship are packed into one texture atlas once their images have loaded,
+// along with a blank white cell that pickups and particles are tinted from, so the renderer can
+// draw them all together instead of switching textures for each. Where everything goes in the atlas
+// is worked out up front, so meteors and ships can be given their frames before it's been built,
+// and the headless server, which never loads an image, has them all the same. While assets are
+// being watched for changes, a sheet edited on disk is packed again at whatever size it now is,
+// keeping its frames' indices, and a SheetEditedEvent tells the gameplay side so colliders can be
+// fitted to it, see sprite_reload.rs. Which image each sheet comes from is up to the theme, and
+// switching themes packs the new theme's sheets in the same way.
+use bevy::asset::{AssetServerSettings, HandleId, LoadState};
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::sprite::Rect;
+use rand::random;
+
+use crate::culling::Culled;
+use crate::input::ControlScheme;
+use crate::theme::Theme;
+use crate::Spaceship;
+
+const METEOR_SPIN_FRAMES: usize = 8;
+const METEOR_SPIN_FPS: f32 = 6.0;
+const THRUSTER_FRAMES: usize = 4;
+const THRUSTER_FPS: f32 = 20.0;
+const THRUSTER_SIZE: Vec2 = Vec2::new(32.0, 16.0);
+// Sits just behind the back of the 65 pixel long ship sprite.
+const THRUSTER_OFFSET: f32 = -(65.0 + 32.0) / 2.0;
+const SHIP_SIZE: Vec2 = Vec2::new(220.0, 132.0);
+// Each spin sheet is a row of square frames as big as the meteor image's longest side.
+const METEOR_SHEETS: [(u8, f32); 3] = [(8, 302.0), (4, 222.0), (2, 132.0)];
+// Clear pixels between the sheets so filtering at the edge of a frame doesn't pick up the next.
+const ATLAS_PADDING: f32 = 2.0;
+// Only the middle of the blank cell is used, which stays white however it's filtered.
+const BLANK_SIZE: f32 = 4.0;
+// Pixels any more see-through than this don't count towards a sprite's outline.
+const OPAQUE_ALPHA: u8 = 16;
+
+// Steps through the frames of a sprite sheet, looping back to the first.
+#[derive(Component)]
+pub struct SpriteAnimation {
+    timer: Timer,
+    first: usize,
+    frames: usize,
+}
+
+impl SpriteAnimation {
+    pub fn new(first: usize, frames: usize, fps: f32) -> Self {
+        SpriteAnimation {
+            timer: Timer::from_seconds(1.0 / fps, true),
+            first,
+            frames,
+        }
+    }
+
+    pub fn random_frame(&self) -> usize {
+        self.first + (random::<f32>() * self.frames as f32) as usize % self.frames
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Sheet {
+    Meteor(u8),
+    Ship,
+    Thruster,
+}
+
+// A sheet's image and where its frames start in the atlas.
+struct AtlasSource {
+    sheet: Sheet,
+    path: String,
+    image: Handle<Image>,
+    position: Vec2,
+    size: Vec2,
+    // Its frames' indices in the atlas.
+    first: usize,
+    frames: usize,
+}
+
+impl AtlasSource {
+    fn frame_size(&self) -> Vec2 {
+        Vec2::new(self.size.x / self.frames as f32, self.size.y)
+    }
+}
+
+// Sent once a sheet edited on disk has been packed into the atlas again.
+pub struct SheetEditedEvent {
+    pub sheet: Sheet,
+    // A frame's size in pixels, before and after the edit.
+    pub old_frame: Vec2,
+    pub new_frame: Vec2,
+    // Where the first frame's opaque pixels are, in pixels from its centre with y up, or None when
+    // there are none.
+    pub opaque: Option<Rect>,
+}
+
+pub struct SpriteSheets {
+    atlas: Handle<TextureAtlas>,
+    texture: Handle<Image>,
+    size: Vec2,
+    packed: bool,
+    // Kept after they've been copied into the atlas so a theme can swap some of them out.
+    sources: Vec<AtlasSource>,
+    // Set when the sheets have to be laid out again from their images' sizes before packing.
+    relayout: bool,
+    // Whether assets are being watched for changes.
+    watching: bool,
+    meteors: [(u8, usize); 3],
+    ship: usize,
+    thruster: usize,
+    blank: usize,
+}
+
+impl SpriteSheets {
+    pub fn atlas(&self) -> Handle<TextureAtlas> {
+        self.atlas.clone()
+    }
+
+    // Meteors start on a random frame so a wave of them doesn't spin in lockstep.
+    pub fn meteor_spin(&self, size: u8) -> SpriteAnimation {
+        let first = self
+            .meteors
+            .iter()
+            .find(|(sheet_size, _)| *sheet_size == size)
+            .unwrap_or(&self.meteors[0])
+            .1;
+        SpriteAnimation::new(first, METEOR_SPIN_FRAMES, METEOR_SPIN_FPS * (0.5 + random::<f32>()))
+    }
+
+    pub fn ship(&self) -> usize {
+        self.ship
+    }
+
+    pub fn blank(&self) -> usize {
+        self.blank
+    }
+}
+
+#[derive(Component)]
+struct Thruster;
+
+pub struct SpriteAnimations;
+
+impl Plugin for SpriteAnimations {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<SheetEditedEvent>()
+            .add_startup_system_to_stage(StartupStage::PreStartup, load_sprite_sheets)
+            .add_system(reskin_sprite_sheets)
+            .add_system(build_sprite_atlas.after(reskin_sprite_sheets))
+            .add_system(repack_edited_sheets.after(build_sprite_atlas))
+            .add_system(animate_sprites)
+            .add_system(add_thrusters)
+            .add_system(show_thrusters);
+    }
+}
+
+fn load_sprite_sheets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Option<Res<AssetServerSettings>>,
+    theme: Res<Theme>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    let sheets: Vec<(Sheet, String, Vec2, usize)> = METEOR_SHEETS
+        .iter()
+        .map(|(size, cell)| (Sheet::Meteor(*size), Vec2::splat(*cell), METEOR_SPIN_FRAMES))
+        .chain([(Sheet::Ship, SHIP_SIZE, 1), (Sheet::Thruster, THRUSTER_SIZE, THRUSTER_FRAMES)])
+        .map(|(sheet, cell, frames)| (sheet, theme.sheet(sheet).to_string(), cell, frames))
+        .collect();
+    // One sheet to a row, with the blank cell at the bottom.
+    let width = sheets.iter().map(|(_, _, cell, frames)| cell.x * *frames as f32).fold(BLANK_SIZE, f32::max);
+    let height = sheets.iter().map(|(_, _, cell, _)| cell.y + ATLAS_PADDING).sum::<f32>() + BLANK_SIZE;
+    let size = Vec2::new(width, height);
+    // Filled in by build_sprite_atlas when the sheets have loaded.
+    let texture = Handle::weak(HandleId::random::<Image>());
+    let mut atlas = TextureAtlas::new_empty(texture.clone(), size);
+    let mut sources = Vec::new();
+    let mut firsts = Vec::new();
+    let mut y = 0.0;
+    for (sheet, path, cell, frames) in sheets {
+        let first = atlas.len();
+        firsts.push(first);
+        for frame in 0..frames {
+            let min = Vec2::new(cell.x * frame as f32, y);
+            atlas.add_texture(Rect { min, max: min + cell });
+        }
+        sources.push(AtlasSource {
+            sheet,
+            image: asset_server.load(&path),
+            path,
+            position: Vec2::new(0.0, y),
+            size: Vec2::new(cell.x * frames as f32, cell.y),
+            first,
+            frames,
+        });
+        y += cell.y + ATLAS_PADDING;
+    }
+    let blank = atlas.add_texture(Rect {
+        min: Vec2::new(1.0, y + 1.0),
+        max: Vec2::new(BLANK_SIZE - 1.0, y + BLANK_SIZE - 1.0),
+    });
+    let meteors = [0, 1, 2].map(|sheet| (METEOR_SHEETS[sheet].0, firsts[sheet]));
+    commands.insert_resource(SpriteSheets {
+        atlas: texture_atlases.add(atlas),
+        texture,
+        size,
+        packed: false,
+        sources,
+        relayout: false,
+        watching: settings.map_or(false, |settings| settings.watch_for_changes),
+        meteors,
+        ship: firsts[3],
+        thruster: firsts[4],
+        blank,
+    });
+}
+
+// Copies every sheet into a fresh atlas texture where the layout says it goes. A sheet that didn't
+// load is left out rather than holding up the rest, see fallback.rs for what's drawn instead.
+fn pack_atlas(sheets: &SpriteSheets, images: &Assets<Image>) -> Image {
+    let width = sheets.size.x as usize;
+    let mut atlas = Image::new_fill(
+        Extent3d { width: sheets.size.x as u32, height: sheets.size.y as u32, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    for source in sheets.sources.iter() {
+        let image = match images.get(&source.image) {
+            Some(image) if image.texture_descriptor.format == TextureFormat::Rgba8UnormSrgb => image,
+            _ => {
+                warn!("Could not pack {} into the sprite atlas, it didn't load as 8 bit RGBA", source.path);
+                continue;
+            }
+        };
+        let image_size = image.size();
+        let row_bytes = source.size.x.min(image_size.x) as usize * 4;
+        for row in 0..source.size.y.min(image_size.y) as usize {
+            let from = row * image_size.x as usize * 4;
+            let to = ((source.position.y as usize + row) * width + source.position.x as usize) * 4;
+            atlas.data[to..to + row_bytes].copy_from_slice(&image.data[from..from + row_bytes]);
+        }
+    }
+    let blank_top = sheets.size.y as usize - BLANK_SIZE as usize;
+    for row in blank_top..sheets.size.y as usize {
+        atlas.data[row * width * 4..(row * width + BLANK_SIZE as usize) * 4].fill(255);
+    }
+    atlas
+}
+
+// Every sheet is laid out again from the size its image is now, one to a row as before, so one that
+// has grown still fits. Their frames keep the same indices.
+fn lay_out_atlas(sheets: &mut SpriteSheets, images: &Assets<Image>, texture_atlases: &mut Assets<TextureAtlas>) {
+    let mut rects = Vec::new();
+    let mut width = BLANK_SIZE;
+    let mut y = 0.0;
+    for source in sheets.sources.iter_mut() {
+        if let Some(image) = images.get(&source.image) {
+            source.size = image.size();
+        }
+        source.position = Vec2::new(0.0, y);
+        let frame = source.frame_size();
+        for index in 0..source.frames {
+            let min = Vec2::new(frame.x * index as f32, y);
+            rects.push((source.first + index, Rect { min, max: min + frame }));
+        }
+        width = width.max(source.size.x);
+        y += source.size.y + ATLAS_PADDING;
+    }
+    let blank_min = Vec2::new(1.0, y + 1.0);
+    rects.push((sheets.blank, Rect { min: blank_min, max: blank_min + Vec2::splat(BLANK_SIZE - 2.0) }));
+    sheets.size = Vec2::new(width, y + BLANK_SIZE);
+    if let Some(atlas) = texture_atlases.get_mut(&sheets.atlas) {
+        atlas.size = sheets.size;
+        for (index, rect) in rects {
+            atlas.textures[index] = rect;
+        }
+    }
+}
+
+// Sheets the new theme has the same images for aren't loaded again.
+fn reskin_sprite_sheets(asset_server: Res<AssetServer>, theme: Res<Theme>, mut sheets: ResMut<SpriteSheets>) {
+    if !theme.is_changed() || theme.is_added() {
+        return;
+    }
+    let mut reskinned = false;
+    for source in sheets.sources.iter_mut() {
+        let path = theme.sheet(source.sheet);
+        if source.path != path {
+            source.path = path.to_string();
+            source.image = asset_server.load(path);
+            reskinned = true;
+        }
+    }
+    if reskinned {
+        sheets.packed = false;
+        sheets.relayout = true;
+    }
+}
+
+// There are no images to build it from on the headless server.
+fn build_sprite_atlas(
+    asset_server: Res<AssetServer>,
+    mut sheets: ResMut<SpriteSheets>,
+    images: Option<ResMut<Assets<Image>>>,
+    texture_atlases: Option<ResMut<Assets<TextureAtlas>>>,
+) {
+    let (mut images, mut texture_atlases) = match (images, texture_atlases) {
+        (Some(images), Some(texture_atlases)) if !sheets.packed => (images, texture_atlases),
+        _ => return,
+    };
+    let loading = |source: &AtlasSource| {
+        matches!(asset_server.get_load_state(&source.image), LoadState::NotLoaded | LoadState::Loading)
+    };
+    if sheets.sources.iter().any(loading) {
+        return;
+    }
+    if sheets.relayout {
+        lay_out_atlas(&mut sheets, &images, &mut texture_atlases);
+        sheets.relayout = false;
+    }
+    let atlas = pack_atlas(&sheets, &images);
+    images.set_untracked(sheets.texture.clone(), atlas);
+    sheets.packed = true;
+}
+
+// The first frame's opaque pixels, see SheetEditedEvent.
+fn opaque_bounds(image: &Image, frame: Vec2) -> Option<Rect> {
+    if image.texture_descriptor.format != TextureFormat::Rgba8UnormSrgb {
+        return None;
+    }
+    let width = image.size().x as usize;
+    let mut bounds: Option<(usize, usize, usize, usize)> = None;
+    for row in 0..frame.y as usize {
+        for column in 0..frame.x as usize {
+            if image.data[(row * width + column) * 4 + 3] <= OPAQUE_ALPHA {
+                continue;
+            }
+            let (left, top, right, bottom) = bounds.unwrap_or((column, row, column, row));
+            bounds = Some((left.min(column), top.min(row), right.max(column), bottom.max(row)));
+        }
+    }
+    let (left, top, right, bottom) = bounds?;
+    let half = frame / 2.0;
+    Some(Rect {
+        min: Vec2::new(left as f32 - half.x, half.y - (bottom + 1) as f32),
+        max: Vec2::new((right + 1) as f32 - half.x, half.y - top as f32),
+    })
+}
+
+fn repack_edited_sheets(
+    mut sheets: ResMut<SpriteSheets>,
+    images: Option<ResMut<Assets<Image>>>,
+    texture_atlases: Option<ResMut<Assets<TextureAtlas>>>,
+    mut asset_events: EventReader<AssetEvent<Image>>,
+    mut edited_event: EventWriter<SheetEditedEvent>,
+) {
+    let (mut images, mut texture_atlases) = match (images, texture_atlases) {
+        (Some(images), Some(texture_atlases)) if sheets.packed && sheets.watching => (images, texture_atlases),
+        _ => return,
+    };
+    let edited: Vec<usize> = asset_events
+        .iter()
+        .filter_map(|ev| match ev {
+            AssetEvent::Modified { handle } => sheets.sources.iter().position(|source| source.image == *handle),
+            _ => None,
+        })
+        .collect();
+    if edited.is_empty() {
+        return;
+    }
+    let old_frames: Vec<Vec2> = sheets.sources.iter().map(AtlasSource::frame_size).collect();
+    lay_out_atlas(&mut sheets, &images, &mut texture_atlases);
+    let atlas = pack_atlas(&sheets, &images);
+    images.set_untracked(sheets.texture.clone(), atlas);
+    for index in edited {
+        let source = &sheets.sources[index];
+        info!("Packed {} into the sprite atlas again", source.path);
+        let new_frame = source.frame_size();
+        edited_event.send(SheetEditedEvent {
+            sheet: source.sheet,
+            old_frame: old_frames[index],
+            new_frame,
+            opaque: images.get(&source.image).and_then(|image| opaque_bounds(image, new_frame)),
+        });
+    }
+}
+
+// Culled sprites pick up where they left off once they're back in view.
+fn animate_sprites(
+    time: Res<Time>,
+    mut query: Query<(&mut SpriteAnimation, &mut TextureAtlasSprite), Without<Culled>>,
+) {
+    for (mut animation, mut sprite) in query.iter_mut() {
+        let steps = animation.timer.tick(time.delta()).times_finished_this_tick() as usize;
+        let frame = sprite.index.saturating_sub(animation.first) + steps;
+        sprite.index = animation.first + frame % animation.frames;
+    }
+}
+
+fn add_thrusters(mut commands: Commands, sheets: Res<SpriteSheets>, query: Query<Entity, Added<Spaceship>>) {
+    for ship in query.iter() {
+        commands.entity(ship).with_children(|parent| {
+            parent
+                .spawn_bundle(SpriteSheetBundle {
+                    sprite: TextureAtlasSprite::new(sheets.thruster),
+                    texture_atlas: sheets.atlas(),
+                    transform: Transform::from_xyz(THRUSTER_OFFSET, 0.0, -0.1),
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                })
+                .insert(SpriteAnimation::new(sheets.thruster, THRUSTER_FRAMES, THRUSTER_FPS))
+                .insert(Thruster);
+        });
+    }
+}
+
+fn show_thrusters(
+    keyboard_input: Res<Input<KeyCode>>,
+    query_ship: Query<&ControlScheme, With<Spaceship>>,
+    mut query_thruster: Query<(&Parent, &mut Visibility), With<Thruster>>,
+) {
+    for (parent, mut visibility) in query_thruster.iter_mut() {
+        let thrusting = query_ship
+            .get(parent.get())
+            .map(|scheme| keyboard_input.pressed(scheme.thrust))
+            .unwrap_or(false);
+        if visibility.is_visible != thrusting {
+            visibility.is_visible = thrusting;
+        }
+    }
+}
@@ -0,0 +1,219 @@
+// Attract mode: once the main menu has been left alone for a while, a one-player game starts with a
+// simple pilot at the controls, and any key, button or touch goes back to the menu. The pilot picks
+// held controls the same way replays and netplay store them and presses them through the ship's
+// control scheme, so it flies exactly as a player would. It turns to face the nearest meteor and
+// fires once it's lined up, or turns away and thrusts if the meteor has got too close. Like a replay
+// the demo isn't a real game, so it's left out of the high scores, the profile, saves and replays.
+use std::f32::consts::{PI, TAU};
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+use crate::game_mode::GameMode;
+use crate::input::{ControlScheme, FIRE, LEFT, RIGHT, THRUST};
+use crate::launch::LaunchOptions;
+use crate::menu::MENU_TEXT;
+use crate::{AppState, Meteor, Parked, Spaceship, StartGameEvent};
+
+const IDLE_SECONDS: f32 = 15.0;
+// Closer than this the pilot stops shooting and gets out of the way.
+const AVOID_DISTANCE: f32 = 180.0;
+// How far off the pilot can be facing before it turns, and before it shoots or thrusts.
+const TURN_TOLERANCE: f32 = 0.05;
+const AIM_TOLERANCE: f32 = 0.15;
+
+// While this exists the game in progress is the attract mode's demo.
+pub struct Demo;
+
+#[derive(Component)]
+struct DemoBanner;
+
+pub struct AttractMode;
+
+impl Plugin for AttractMode {
+    fn build(&self, app: &mut App) {
+        let options = app.world.get_resource::<LaunchOptions>();
+        if options.map_or(false, |options| options.online() || options.bench.is_some()) {
+            return;
+        }
+        app
+            .init_resource::<IdleTime>()
+            .add_startup_system(setup_demo_banner)
+            // In PreUpdate so the demo is already marked by the time anything reads the game starting.
+            .add_system_to_stage(CoreStage::PreUpdate, track_idle_time.after(InputSystem))
+            .add_system_to_stage(CoreStage::PreUpdate, start_demo.after(track_idle_time))
+            .add_system(show_demo_banner)
+            .add_system_to_stage(CoreStage::PreUpdate, end_demo_on_input.after(InputSystem))
+            .add_system_to_stage(CoreStage::PreUpdate, fly_demo_ship.after(end_demo_on_input))
+            .add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(end_demo_on_game_over))
+            .add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(stop_demo));
+    }
+}
+
+fn any_input(
+    keyboard_input: &Input<KeyCode>,
+    mouse_input: &Input<MouseButton>,
+    gamepad_input: &Input<GamepadButton>,
+    touches: &Touches,
+) -> bool {
+    keyboard_input.get_just_pressed().next().is_some()
+        || mouse_input.get_just_pressed().next().is_some()
+        || gamepad_input.get_just_pressed().next().is_some()
+        || touches.iter_just_pressed().next().is_some()
+}
+
+// How long the main menu has gone without a key, button or touch.
+#[derive(Default)]
+struct IdleTime(f32);
+
+// Time only counts while nothing is held, so a key held down on the menu doesn't start the demo.
+fn track_idle_time(
+    time: Res<Time>,
+    state: Res<State<AppState>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    touches: Res<Touches>,
+    mut idle: ResMut<IdleTime>,
+) {
+    let held = keyboard_input.get_pressed().next().is_some()
+        || mouse_input.get_pressed().next().is_some()
+        || gamepad_input.get_pressed().next().is_some()
+        || touches.iter().next().is_some();
+    if *state.current() != AppState::MainMenu || held {
+        idle.0 = 0.0;
+    } else {
+        idle.0 += time.delta_seconds();
+    }
+}
+
+fn start_demo(
+    mut commands: Commands,
+    mut idle: ResMut<IdleTime>,
+    mut mode: ResMut<GameMode>,
+    mut start_game_event: EventWriter<StartGameEvent>,
+) {
+    if idle.0 >= IDLE_SECONDS {
+        idle.0 = 0.0;
+        *mode = GameMode::Classic;
+        start_game_event.send(StartGameEvent { players: 1 });
+        commands.insert_resource(Demo);
+    }
+}
+
+// Runs before the pilot presses anything, so only presses from a person are seen here. The press
+// that ends the demo is used up, so it doesn't also pause the game or click a menu button.
+fn end_demo_on_input(
+    demo: Option<Res<Demo>>,
+    mut state: ResMut<State<AppState>>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut mouse_input: ResMut<Input<MouseButton>>,
+    mut gamepad_input: ResMut<Input<GamepadButton>>,
+    touches: Res<Touches>,
+    query_ship: Query<&ControlScheme, With<Spaceship>>,
+) {
+    if demo.is_none() || *state.current() != AppState::InGame {
+        return;
+    }
+    if !any_input(&keyboard_input, &mouse_input, &gamepad_input, &touches) {
+        return;
+    }
+    for scheme in query_ship.iter() {
+        scheme.press(&mut keyboard_input, 0);
+    }
+    keyboard_input.clear();
+    mouse_input.clear();
+    gamepad_input.clear();
+    if let Err(err) = state.set(AppState::MainMenu) {
+        warn!("Could not leave demo: {:?}", err);
+    }
+}
+
+fn fly_demo_ship(
+    demo: Option<Res<Demo>>,
+    state: Res<State<AppState>>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    query_ship: Query<(&Transform, &ControlScheme), With<Spaceship>>,
+    query_meteor: Query<&Transform, (With<Meteor>, Without<Parked>)>,
+    mut fired: Local<bool>,
+) {
+    if demo.is_none() || *state.current() != AppState::InGame {
+        return;
+    }
+    for (transform, scheme) in query_ship.iter() {
+        let position = transform.translation.truncate();
+        let nearest = query_meteor
+            .iter()
+            .map(|meteor| meteor.translation.truncate() - position)
+            .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+        let offset = match nearest {
+            Some(offset) => offset,
+            None => {
+                scheme.press(&mut keyboard_input, 0);
+                continue;
+            }
+        };
+        let fleeing = offset.length() < AVOID_DISTANCE;
+        let target = if fleeing { -offset } else { offset };
+        let (axis, angle) = transform.rotation.to_axis_angle();
+        let turn = (target.y.atan2(target.x) - axis.z * angle + PI).rem_euclid(TAU) - PI;
+        let mut held = 0;
+        if turn > TURN_TOLERANCE {
+            held |= LEFT;
+        } else if turn < -TURN_TOLERANCE {
+            held |= RIGHT;
+        }
+        if turn.abs() < AIM_TOLERANCE {
+            if fleeing {
+                held |= THRUST;
+            } else if !*fired {
+                // Firing goes by the key being pressed again, so it's let go every other frame.
+                held |= FIRE;
+            }
+        }
+        *fired = held & FIRE != 0;
+        scheme.press(&mut keyboard_input, held);
+    }
+}
+
+fn end_demo_on_game_over(demo: Option<Res<Demo>>, mut state: ResMut<State<AppState>>) {
+    if demo.is_some() {
+        if let Err(err) = state.set(AppState::MainMenu) {
+            warn!("Could not leave demo: {:?}", err);
+        }
+    }
+}
+
+fn stop_demo(mut commands: Commands) {
+    commands.remove_resource::<Demo>();
+}
+
+fn setup_demo_banner(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                "DEMO - PRESS ANY KEY",
+                TextStyle {
+                    font: asset_server.load("BungeeSpice-Regular.ttf"),
+                    font_size: 40.0,
+                    color: MENU_TEXT,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect { bottom: Val::Px(10.0), left: Val::Px(10.0), ..default() },
+                display: Display::None,
+                ..default()
+            }),
+        )
+        .insert(DemoBanner);
+}
+
+fn show_demo_banner(demo: Option<Res<Demo>>, mut query: Query<&mut Style, With<DemoBanner>>) {
+    let display = if demo.is_some() { Display::Flex } else { Display::None };
+    for mut style in query.iter_mut() {
+        if style.display != display {
+            style.display = display;
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+
+use crate::launch::LaunchOptions;
+use crate::settings::{Settings, VolumeChannel};
+
+// Channel markers, each gets its own volume and can be paused or stopped as a group.
+pub struct MusicChannel;
+pub struct SfxChannel;
+pub struct UiChannel;
+
+pub struct GameAudio;
+
+impl Plugin for GameAudio {
+    fn build(&self, app: &mut App) {
+        // Running headless there's nobody to hear anything, so there's no audio output to open. The
+        // channels are still there for the sound systems to ask for sounds that never play.
+        if app.world.get_resource::<LaunchOptions>().map_or(false, |options| options.headless) {
+            app
+                .add_asset::<AudioSource>()
+                .add_asset::<AudioInstance>()
+                .insert_resource(AudioChannel::<MusicChannel>::default())
+                .insert_resource(AudioChannel::<SfxChannel>::default())
+                .insert_resource(AudioChannel::<UiChannel>::default());
+            return;
+        }
+        app
+            .add_plugin(AudioPlugin)
+            .add_audio_channel::<MusicChannel>()
+            .add_audio_channel::<SfxChannel>()
+            .add_audio_channel::<UiChannel>()
+            .add_system(apply_channel_volumes);
+    }
+}
+
+// Sounds come from assets/ unless the procedural_audio feature is on, in which case
+// they're synthesised at startup and the game needs no sound files at all.
+#[cfg(not(feature = "procedural_audio"))]
+pub fn load_sound(
+    asset_server: &AssetServer,
+    _audio_sources: &mut Assets<AudioSource>,
+    path: &str,
+) -> Handle<AudioSource> {
+    asset_server.load(path)
+}
+
+#[cfg(feature = "procedural_audio")]
+pub fn load_sound(
+    asset_server: &AssetServer,
+    audio_sources: &mut Assets<AudioSource>,
+    path: &str,
+) -> Handle<AudioSource> {
+    match crate::synth::sound_for(path) {
+        Some(sound) => audio_sources.add(sound),
+        None => asset_server.load(path),
+    }
+}
+
+fn apply_channel_volumes(
+    settings: Res<Settings>,
+    music: Res<AudioChannel<MusicChannel>>,
+    sfx: Res<AudioChannel<SfxChannel>>,
+    ui: Res<AudioChannel<UiChannel>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    music.set_volume(settings.volume(VolumeChannel::Music) as f64);
+    sfx.set_volume(settings.volume(VolumeChannel::Sfx) as f64);
+    ui.set_volume(settings.volume(VolumeChannel::Sfx) as f64);
+}
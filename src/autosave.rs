@@ -0,0 +1,81 @@
+// The profile and high scores are written whenever they change and a run when it's left from
+// the game, but closing the window or crashing mid-run skips that last part. So everything is
+// flushed again on exit, and a copy of the run is kept up to date for a panic hook to write out.
+use std::panic;
+use std::sync::{Arc, Mutex};
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::window::WindowCloseRequested;
+
+use crate::leaderboard::{HighScores, HIGH_SCORES_FILE};
+use crate::profile::{Profile, PROFILE_FILE};
+use crate::savegame::{write_run, RunSnapshot, SavedRun};
+use crate::storage;
+
+// Copying a whole run every frame would be wasteful, a crash loses at most this much of it.
+const CRASH_SNAPSHOT_INTERVAL: f32 = 1.0;
+
+struct CrashSnapshot {
+    run: Arc<Mutex<Option<SavedRun>>>,
+    timer: Timer,
+}
+
+pub struct Autosave;
+
+impl Plugin for Autosave {
+    fn build(&self, app: &mut App) {
+        let run = Arc::new(Mutex::new(None));
+        install_panic_hook(run.clone());
+        app
+            .insert_resource(CrashSnapshot {
+                run,
+                timer: Timer::from_seconds(CRASH_SNAPSHOT_INTERVAL, true),
+            })
+            .add_system(update_crash_snapshot)
+            // Last so it also sees an AppExit sent earlier in the same frame, which is the final one.
+            .add_system_to_stage(CoreStage::Last, save_on_exit);
+    }
+}
+
+fn install_panic_hook(run: Arc<Mutex<Option<SavedRun>>>) {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        // try_lock, panicking while the snapshot is being updated mustn't deadlock here.
+        if let Ok(run) = run.try_lock() {
+            if let Some(run) = run.as_ref() {
+                write_run(run);
+            }
+        }
+        default_hook(info);
+    }));
+}
+
+fn update_crash_snapshot(time: Res<Time>, snapshot: RunSnapshot, mut crash_snapshot: ResMut<CrashSnapshot>) {
+    if !crash_snapshot.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    if let Ok(mut run) = crash_snapshot.run.lock() {
+        *run = snapshot.capture();
+    }
+}
+
+fn save_on_exit(
+    mut exit_event: EventReader<AppExit>,
+    mut close_event: EventReader<WindowCloseRequested>,
+    snapshot: RunSnapshot,
+    profile: Res<Profile>,
+    high_scores: Res<HighScores>,
+    mut saved: Local<bool>,
+) {
+    let exiting = exit_event.iter().count() + close_event.iter().count() > 0;
+    if !exiting || *saved {
+        return;
+    }
+    *saved = true;
+    if let Some(run) = snapshot.capture() {
+        write_run(&run);
+    }
+    storage::save(PROFILE_FILE, &*profile);
+    storage::save(HIGH_SCORES_FILE, &*high_scores);
+}
@@ -0,0 +1,226 @@
+// A stress test started with --bench METEORS BULLETS. It starts a one-player game flown by the
+// server's autopilot, fills the field with that many meteors, keeps that many bullets flying and
+// writes how long every frame and each stage of it took to a CSV in the bench folder next to the
+// save files. It quits after BENCH_FRAMES frames so runs can be compared. The ship is kept
+// shielded so it lasts the whole run.
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use bevy::app::AppExit;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+
+use crate::bullets::{fire_bullet, BulletPool};
+use crate::definitions::EntityDefinitions;
+use crate::launch::{BenchOptions, LaunchOptions};
+use crate::powerups::{grant_powerup, PowerupKind, Shield};
+use crate::server::{fly_ships, Autopilot};
+use crate::spawning::{BulletSpec, MeteorSpec, SpawnCommands};
+use crate::storage;
+use crate::{arena_size, AppState, Bullet, Game, GameState, Meteor, Parked, Spaceship, StartGameEvent};
+
+const BENCH_FRAMES: u32 = 3600;
+const BENCH_METEOR_SIZES: [u8; 3] = [2, 4, 8];
+const BENCH_SPEED: f32 = 100.0;
+// Keeps the meteors from landing on the ship as the run starts.
+const SAFE_RADIUS: f32 = 150.0;
+
+// Each stage is timed from its start until the next one starts. Physics runs between Update and
+// PostUpdate so it's counted with Update, and the frame is rendered after Last.
+const TIMED_STAGES: [CoreStage; 5] =
+    [CoreStage::First, CoreStage::PreUpdate, CoreStage::Update, CoreStage::PostUpdate, CoreStage::Last];
+const CSV_HEADER: &str =
+    "frame,frame_ms,first_ms,pre_update_ms,update_and_physics_ms,post_update_ms,last_and_render_ms,meteors,bullets";
+
+struct Bench {
+    options: BenchOptions,
+    csv: Option<BufWriter<File>>,
+    // Frames are only counted once the field is full.
+    filled: bool,
+    frames: u32,
+    stage_starts: [Option<Instant>; 5],
+}
+
+pub struct Benchmark;
+
+impl Plugin for Benchmark {
+    fn build(&self, app: &mut App) {
+        let options = match app.world.get_resource::<LaunchOptions>().and_then(|options| options.bench) {
+            Some(options) => options,
+            None => return,
+        };
+        app
+            .insert_resource(Bench {
+                options,
+                csv: create_csv(),
+                filled: false,
+                frames: 0,
+                stage_starts: [None; 5],
+            })
+            .insert_resource(Autopilot(StdRng::from_entropy()))
+            .add_system_to_stage(CoreStage::PreUpdate, fly_ships.after(InputSystem))
+            .add_system_set(SystemSet::on_update(AppState::MainMenu).with_system(start_bench))
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(fill_field)
+                    .with_system(keep_bullets_flying)
+                    .with_system(shield_ship),
+            );
+        for (index, stage) in TIMED_STAGES.into_iter().enumerate() {
+            let timer = move |world: &mut World| time_stage(world, index);
+            app.add_system_to_stage(stage, timer.exclusive_system().at_start());
+        }
+    }
+}
+
+fn create_csv() -> Option<BufWriter<File>> {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or(0);
+    let path = match storage::config_path(&format!("bench/bench_{}.csv", seconds)) {
+        Some(path) => path,
+        None => {
+            warn!("No config directory to write the bench results in");
+            return None;
+        }
+    };
+    let created = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| File::create(&path))
+        .and_then(|file| {
+            let mut csv = BufWriter::new(file);
+            writeln!(csv, "{}", CSV_HEADER)?;
+            Ok(csv)
+        });
+    match created {
+        Ok(csv) => {
+            info!("Writing bench results to {}", path.display());
+            Some(csv)
+        }
+        Err(err) => {
+            warn!("Could not write bench results to {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+fn start_bench(mut state: ResMut<State<AppState>>, mut start_game_event: EventWriter<StartGameEvent>) {
+    start_game_event.send(StartGameEvent { players: 1 });
+    if let Err(err) = state.set(AppState::InGame) {
+        warn!("Could not start the bench: {:?}", err);
+    }
+}
+
+fn fill_field(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    window_descriptor: Res<WindowDescriptor>,
+    query_game: Query<&Game>,
+    mut bench: ResMut<Bench>,
+) {
+    if bench.filled || !matches!(query_game.single().gameState, GameState::InProgress) {
+        return;
+    }
+    let mut rng = thread_rng();
+    let arena = arena_size(&windows, &window_descriptor);
+    for _ in 0..bench.options.meteors {
+        let size = BENCH_METEOR_SIZES[rng.gen_range(0..BENCH_METEOR_SIZES.len())];
+        let spec = MeteorSpec::new(size).at(random_position(&mut rng, arena));
+        commands.spawn_meteor(spec.with_velocity(random_velocity(&mut rng)));
+    }
+    bench.filled = true;
+    info!("Bench running with {} meteors and {} bullets", bench.options.meteors, bench.options.bullets);
+}
+
+// Bullets leaving the screen or hitting something are replaced straight away.
+fn keep_bullets_flying(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    window_descriptor: Res<WindowDescriptor>,
+    bench: Res<Bench>,
+    definitions: Res<EntityDefinitions>,
+    mut pool: ResMut<BulletPool>,
+    query_bullets: Query<(), (With<Bullet>, Without<Parked>)>,
+) {
+    if !bench.filled {
+        return;
+    }
+    let mut rng = thread_rng();
+    let arena = arena_size(&windows, &window_descriptor);
+    let missing = (bench.options.bullets as usize).saturating_sub(query_bullets.iter().count());
+    for _ in 0..missing {
+        let spec = BulletSpec::player(0)
+            .at(random_position(&mut rng, arena))
+            .with_velocity(random_velocity(&mut rng) * 4.0);
+        fire_bullet(&mut commands, &mut pool, &definitions.bullet, spec);
+    }
+}
+
+fn shield_ship(
+    mut commands: Commands,
+    definitions: Res<EntityDefinitions>,
+    query_ship: Query<Entity, (With<Spaceship>, Without<Shield>)>,
+) {
+    for ship in query_ship.iter() {
+        grant_powerup(&mut commands, &definitions.powerup, ship, PowerupKind::Shield);
+    }
+}
+
+fn random_position(rng: &mut impl Rng, arena: Vec2) -> Vec2 {
+    loop {
+        let position = Vec2::new((rng.gen::<f32>() - 0.5) * arena.x, (rng.gen::<f32>() - 0.5) * arena.y);
+        if position.length() > SAFE_RADIUS {
+            return position;
+        }
+    }
+}
+
+fn random_velocity(rng: &mut impl Rng) -> Vec2 {
+    Vec2::new(rng.gen::<f32>() - 0.5, rng.gen::<f32>() - 0.5) * 2.0 * BENCH_SPEED
+}
+
+// Each frame is written out at the start of the next one, so its Last stage also covers rendering.
+fn time_stage(world: &mut World, index: usize) {
+    let now = Instant::now();
+    if index == 0 {
+        write_frame(world, now);
+    }
+    world.resource_mut::<Bench>().stage_starts[index] = Some(now);
+}
+
+fn write_frame(world: &mut World, now: Instant) {
+    let meteors = world.query_filtered::<(), With<Meteor>>().iter(world).count();
+    let bullets = world.query_filtered::<(), (With<Bullet>, Without<Parked>)>().iter(world).count();
+    let mut bench = world.resource_mut::<Bench>();
+    if !bench.filled {
+        return;
+    }
+    let starts: Option<Vec<Instant>> = bench.stage_starts.iter().copied().collect();
+    let starts = match starts {
+        Some(starts) => starts,
+        None => return,
+    };
+    let ms = |from: Instant, to: Instant| to.duration_since(from).as_secs_f64() * 1000.0;
+    let mut row = format!("{},{:.3}", bench.frames, ms(starts[0], now));
+    for (index, start) in starts.iter().enumerate() {
+        let end = starts.get(index + 1).copied().unwrap_or(now);
+        row.push_str(&format!(",{:.3}", ms(*start, end)));
+    }
+    if let Some(csv) = bench.csv.as_mut() {
+        if let Err(err) = writeln!(csv, "{},{},{}", row, meteors, bullets) {
+            warn!("Stopped writing bench results: {}", err);
+            bench.csv = None;
+        }
+    }
+    bench.frames += 1;
+    if bench.frames < BENCH_FRAMES {
+        return;
+    }
+    if let Some(mut csv) = bench.csv.take() {
+        csv.flush().ok();
+    }
+    info!("Bench finished after {} frames", bench.frames);
+    world.send_event(AppExit);
+}
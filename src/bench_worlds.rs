@@ -0,0 +1,110 @@
+// Synthetic worlds for the criterion benches in benches/, which can only reach what the crate
+// makes public. Each one is the headless game with a lot of one situation set up, and runs only
+// the systems being measured on it.
+use bevy::ecs::system::{BoxedSystem, CommandQueue};
+use bevy::prelude::*;
+
+use crate::bullets::{fire_bullet, park_bullets, spend_offscreen_bullets, BulletPool};
+use crate::definitions::EntityDefinitions;
+use crate::launch::LaunchOptions;
+use crate::server::headless_app;
+use crate::spawning::{BulletSpec, MeteorSpec, SpawnCommands};
+use crate::{screen_wrap, spaceship_collision};
+
+const BENCH_SEED: u64 = 0;
+// Room for each meteor to itself, so none start out touching another.
+const SMALL_METEOR_SPACING: f32 = 40.0;
+const LARGE_METEOR_SPACING: f32 = 140.0;
+const EDGE_ARENA: Vec2 = Vec2::new(1000.0, 1000.0);
+
+pub struct SyntheticWorld {
+    app: App,
+    systems: Vec<BoxedSystem>,
+}
+
+impl SyntheticWorld {
+    // Small meteors with bullets between them that miss, for the cost of checking every pair.
+    pub fn near_misses(entities: usize) -> SyntheticWorld {
+        let (positions, arena) = grid(entities / 2, SMALL_METEOR_SPACING);
+        let meteors: Vec<(u8, Vec2)> = positions.iter().map(|position| (2, *position)).collect();
+        let bullets: Vec<Vec2> = positions.iter().map(|position| *position + SMALL_METEOR_SPACING / 2.0).collect();
+        SyntheticWorld::new(arena, &meteors, &bullets, true)
+            .with_system(spaceship_collision)
+    }
+
+    // A bullet in every large meteor, so each one is destroyed and splits in two as its commands apply.
+    pub fn splits(entities: usize) -> SyntheticWorld {
+        let (positions, arena) = grid(entities / 2, LARGE_METEOR_SPACING);
+        let meteors: Vec<(u8, Vec2)> = positions.iter().map(|position| (8, *position)).collect();
+        SyntheticWorld::new(arena, &meteors, &positions, true)
+            .with_system(spaceship_collision)
+            .with_system(park_bullets)
+    }
+
+    // Meteors just past the right edge to wrap and bullets just past the left one to be spent.
+    pub fn screen_edges(entities: usize) -> SyntheticWorld {
+        let count = entities / 2;
+        let rows: Vec<f32> = (0..count)
+            .map(|row| (row as f32 / count.max(1) as f32 - 0.5) * EDGE_ARENA.y)
+            .collect();
+        let meteors: Vec<(u8, Vec2)> = rows.iter().map(|y| (2, Vec2::new(EDGE_ARENA.x / 2.0 + 50.0, *y))).collect();
+        let bullets: Vec<Vec2> = rows.iter().map(|y| Vec2::new(-EDGE_ARENA.x / 2.0 - 50.0, *y)).collect();
+        SyntheticWorld::new(EDGE_ARENA, &meteors, &bullets, false)
+            .with_system(screen_wrap)
+            .with_system(spend_offscreen_bullets)
+            .with_system(park_bullets)
+    }
+
+    // Nothing runs in the game itself until the world is handed to `run`. Settling steps the physics
+    // once more so it knows which colliders touch.
+    fn new(arena: Vec2, meteors: &[(u8, Vec2)], bullets: &[Vec2], settle: bool) -> SyntheticWorld {
+        let options = LaunchOptions { window_size: Some(arena), ..default() };
+        let mut app = headless_app(options, BENCH_SEED);
+        app.update();
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &app.world);
+        for (size, position) in meteors {
+            commands.spawn_meteor(MeteorSpec::new(*size).at(*position));
+        }
+        queue.apply(&mut app.world);
+        // Lets the meteors settle into the world, they'd only be wrapped on the frame after.
+        app.update();
+        let bullet = app.world.resource::<EntityDefinitions>().bullet.clone();
+        let mut commands = Commands::new(&mut queue, &app.world);
+        // Nothing has been parked yet, so a pool of its own just spawns every bullet.
+        let mut pool = BulletPool::default();
+        for position in bullets {
+            fire_bullet(&mut commands, &mut pool, &bullet, BulletSpec::player(0).at(*position));
+        }
+        queue.apply(&mut app.world);
+        if settle {
+            app.update();
+        }
+        SyntheticWorld { app, systems: Vec::new() }
+    }
+
+    fn with_system<Params>(mut self, system: impl IntoSystem<(), (), Params>) -> SyntheticWorld {
+        let mut system: BoxedSystem = Box::new(IntoSystem::into_system(system));
+        system.initialize(&mut self.app.world);
+        self.systems.push(system);
+        self
+    }
+
+    // Runs the systems once each in order, applying their commands like the end of a stage would.
+    pub fn run(&mut self) {
+        for system in self.systems.iter_mut() {
+            system.run((), &mut self.app.world);
+            system.apply_buffers(&mut self.app.world);
+        }
+    }
+}
+
+// Positions for `count` things in a square centred on the origin, and an arena that fits them.
+fn grid(count: usize, spacing: f32) -> (Vec<Vec2>, Vec2) {
+    let columns = (count as f32).sqrt().ceil().max(1.0) as usize;
+    let offset = (columns as f32 - 1.0) / 2.0;
+    let positions = (0..count)
+        .map(|index| Vec2::new((index % columns) as f32 - offset, (index / columns) as f32 - offset) * spacing)
+        .collect();
+    (positions, Vec2::splat((columns + 1) as f32 * spacing))
+}
@@ -0,0 +1,4 @@
+// Runs games with no window or audio, see server.rs.
+fn main() {
+    asteroids::run_server();
+}
@@ -0,0 +1,108 @@
+// Bullets are recycled rather than despawned, since they're fired and spent constantly. Whatever
+// ends a bullet inserts Parked instead of despawning it, and it's then moved far outside the arena,
+// stopped and taken out of collisions until fire_bullet hands it out again. Online games roll
+// entities back and forth, which a pool can't keep track of, so there spent bullets are despawned.
+// Bullets leaving the arena are spent, unless the mode has them bounce off its edges a few times.
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::definitions::BulletDefinition;
+use crate::game_mode::GameMode;
+use crate::launch::LaunchOptions;
+use crate::palette::Tint;
+use crate::particles::{Trail, BULLET_TRAIL};
+use crate::spawning::{BulletBundle, BulletSpec};
+use crate::{arena_size, AppState, Bullet, DestroyCommands, Parked, PARKED_POSITION, PAR_BATCH_SIZE};
+
+// How many times a bullet has bounced off the edges so far.
+#[derive(Component, Reflect, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct Ricochets(pub u8);
+
+#[derive(Default)]
+pub struct BulletPool {
+    parked: Vec<Entity>,
+    enabled: bool,
+}
+
+pub struct Bullets;
+
+impl Plugin for Bullets {
+    fn build(&self, app: &mut App) {
+        let online = app.world.get_resource::<LaunchOptions>().map_or(false, |options| options.online());
+        app
+            .insert_resource(BulletPool { parked: Vec::new(), enabled: !online })
+            .add_system(park_bullets)
+            .add_system_set(SystemSet::on_update(AppState::InGame).with_system(spend_offscreen_bullets));
+    }
+}
+
+pub fn fire_bullet(commands: &mut Commands, pool: &mut BulletPool, definition: &BulletDefinition, spec: BulletSpec) {
+    let bullet_bundle = BulletBundle::new(spec, definition);
+    if let Some(bullet) = pool.parked.pop() {
+        commands.entity(bullet).remove::<Parked>().insert_bundle(bullet_bundle);
+        return;
+    }
+    commands
+        .spawn_bundle(SpriteBundle::default())
+        .insert(Tint::Bullet)
+        // Fast and small enough to pass clean through a meteor between two steps without it.
+        .insert(Ccd::enabled())
+        .insert_bundle(bullet_bundle)
+        .insert(Trail::new(BULLET_TRAIL));
+}
+
+pub fn park_bullets(
+    mut commands: Commands,
+    mut pool: ResMut<BulletPool>,
+    mut query_spent: Query<(Entity, &mut Transform, &mut Velocity), (With<Bullet>, Added<Parked>)>,
+) {
+    for (bullet, mut transform, mut velocity) in query_spent.iter_mut() {
+        if !pool.enabled {
+            commands.destroy(bullet);
+            continue;
+        }
+        transform.translation = PARKED_POSITION;
+        *velocity = Velocity::zero();
+        commands.entity(bullet).insert(CollisionGroups::new(0, 0));
+        pool.parked.push(bullet);
+    }
+}
+
+// A bounce flips the velocity across whichever edges were crossed and puts the bullet back on them.
+pub fn spend_offscreen_bullets(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    window_descriptor: Res<WindowDescriptor>,
+    mode: Res<GameMode>,
+    mut query_bullets: Query<
+        (Entity, &mut Transform, &mut Velocity, Option<&mut Ricochets>),
+        (With<Bullet>, Without<Parked>),
+    >,
+) {
+    let arena = arena_size(&windows, &window_descriptor);
+    let max_bounces = mode.rules().bullet_bounces;
+    let spent = Mutex::new(Vec::new());
+    query_bullets.par_for_each_mut(PAR_BATCH_SIZE, |(bullet, mut transform, mut velocity, ricochets)| {
+        let position = transform.translation.truncate();
+        let crossed = position.abs().cmpgt(arena / 2.0);
+        if !crossed.any() {
+            return;
+        }
+        match ricochets {
+            Some(mut ricochets) if ricochets.0 < max_bounces => {
+                ricochets.0 += 1;
+                let flip = Vec2::select(crossed, Vec2::NEG_ONE, Vec2::ONE);
+                velocity.linvel *= flip;
+                let clamped = position.clamp(-arena / 2.0, arena / 2.0);
+                transform.translation = clamped.extend(transform.translation.z);
+            }
+            _ => spent.lock().unwrap().push(bullet),
+        }
+    });
+    for bullet in spent.into_inner().unwrap() {
+        commands.entity(bullet).insert(Parked);
+    }
+}
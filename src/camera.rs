@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+use rand::random;
+
+use crate::settings::Settings;
+use crate::{Game, PlayerId, Spaceship};
+
+const MAX_SHAKE_OFFSET: f32 = 20.0;
+const MAX_SHAKE_ROTATION: f32 = 0.05;
+const TRAUMA_DECAY: f32 = 1.5;
+
+pub const SHIP_HIT_TRAUMA: f32 = 0.6;
+pub const METEOR_TRAUMA_PER_SIZE: f32 = 0.05;
+
+pub struct CameraShakeEvent {
+    pub trauma: f32,
+}
+
+// Trauma is added by events and decays over time, the shake itself scales with trauma squared
+// so small hits barely move the camera while big explosions throw it around.
+#[derive(Component, Default)]
+pub struct CameraShake {
+    trauma: f32,
+    offset: Vec2,
+    rotation: f32,
+}
+
+// Cameras follow their player's ship in split-screen and sit at the origin otherwise.
+#[derive(Component)]
+pub struct CameraFollow(pub u8);
+
+pub struct ScreenShake;
+
+impl Plugin for ScreenShake {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<CameraShakeEvent>()
+            .add_system(add_trauma)
+            .add_system(follow_ship.before(shake_camera))
+            .add_system(shake_camera.after(add_trauma));
+    }
+}
+
+fn add_trauma(
+    mut shake_event: EventReader<CameraShakeEvent>,
+    mut query: Query<&mut CameraShake>,
+) {
+    for ev in shake_event.iter() {
+        for mut shake in query.iter_mut() {
+            shake.trauma = (shake.trauma + ev.trauma).min(1.0);
+        }
+    }
+}
+
+fn follow_ship(
+    query_game: Query<&Game>,
+    query_ship: Query<(&Transform, &PlayerId), (With<Spaceship>, Without<CameraFollow>)>,
+    mut query_camera: Query<(&mut Transform, &CameraShake, &CameraFollow)>,
+) {
+    let game = query_game.single();
+    for (mut transform, shake, follow) in query_camera.iter_mut() {
+        let mut target = Vec2::ZERO;
+        if game.players > 1 {
+            target = Vec2::new(
+                transform.translation.x - shake.offset.x,
+                transform.translation.y - shake.offset.y,
+            );
+            for (ship_transform, player) in query_ship.iter() {
+                if player.0 == follow.0 {
+                    target = ship_transform.translation.truncate();
+                }
+            }
+        }
+        // Keep the current shake offset applied so shake_camera can remove it as usual.
+        transform.translation.x = target.x + shake.offset.x;
+        transform.translation.y = target.y + shake.offset.y;
+    }
+}
+
+fn shake_camera(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut query: Query<(&mut Transform, &mut CameraShake)>,
+) {
+    for (mut transform, mut shake) in query.iter_mut() {
+        // Undo last frame's shake so anything else moving the camera isn't fought against.
+        transform.translation.x -= shake.offset.x;
+        transform.translation.y -= shake.offset.y;
+        transform.rotate_z(-shake.rotation);
+
+        shake.trauma = (shake.trauma - TRAUMA_DECAY * time.delta_seconds()).max(0.0);
+
+        let strength = if settings.reduced_motion { 0.0 } else { settings.screen_shake };
+        let amount = shake.trauma * shake.trauma * strength;
+        shake.offset = Vec2::new(random::<f32>() * 2.0 - 1.0, random::<f32>() * 2.0 - 1.0)
+            * MAX_SHAKE_OFFSET
+            * amount;
+        shake.rotation = (random::<f32>() * 2.0 - 1.0) * MAX_SHAKE_ROTATION * amount;
+
+        transform.translation.x += shake.offset.x;
+        transform.translation.y += shake.offset.y;
+        transform.rotate_z(shake.rotation);
+    }
+}
@@ -0,0 +1,303 @@
+// A small extra camera follows the first player's view into a low resolution image. While in game
+// it renders a few times a second, and each image is copied back from the GPU into a ring buffer
+// holding the last few seconds, which F9 saves as a GIF next to the other save files.
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::num::NonZeroU32;
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::extract_resource::{ExtractResource, ExtractResourcePlugin};
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{
+    Buffer, BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Extent3d, ImageCopyBuffer, ImageDataLayout,
+    Maintain, MapMode, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+};
+use bevy::render::renderer::{RenderDevice, RenderQueue};
+use bevy::render::{RenderApp, RenderStage};
+
+use crate::camera::CameraFollow;
+use crate::{storage, AppState};
+
+// 256 pixels of 4 bytes is a whole number of the 256 byte rows GPU copies need, so no padding.
+const CLIP_WIDTH: u32 = 256;
+const CLIP_HEIGHT: u32 = 144;
+const CLIP_FPS: u32 = 15;
+const CLIP_SECONDS: u32 = 10;
+const CLIP_FRAMES: usize = (CLIP_FPS * CLIP_SECONDS) as usize;
+const SAVE_CLIP_KEY: KeyCode = KeyCode::F9;
+
+// What the render world needs to know to copy this frame back.
+#[derive(Clone)]
+struct ClipTarget {
+    image: Handle<Image>,
+    capture: bool,
+}
+
+impl ExtractResource for ClipTarget {
+    type Source = ClipTarget;
+
+    fn extract_resource(source: &ClipTarget) -> Self {
+        source.clone()
+    }
+}
+
+// Frames are RGBA, oldest first.
+struct ClipBuffer {
+    frames: VecDeque<Vec<u8>>,
+    timer: Timer,
+    receiver: Mutex<Receiver<Vec<u8>>>,
+}
+
+struct FrameSender(Mutex<Sender<Vec<u8>>>);
+
+// Copies still on their way back from the GPU, each with whether it could be read once it's arrived.
+#[derive(Default)]
+struct PendingReadbacks(Vec<(Buffer, Arc<Mutex<Option<bool>>>)>);
+
+#[derive(Component)]
+struct ClipCamera;
+
+pub struct ClipCapture;
+
+impl Plugin for ClipCapture {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = channel();
+        app
+            .insert_resource(ClipBuffer {
+                frames: VecDeque::with_capacity(CLIP_FRAMES),
+                timer: Timer::from_seconds(1.0 / CLIP_FPS as f32, true),
+                receiver: Mutex::new(receiver),
+            })
+            .add_plugin(ExtractResourcePlugin::<ClipTarget>::default())
+            .add_startup_system(setup_clip_camera)
+            .add_system(follow_game_camera)
+            .add_system(schedule_capture)
+            .add_system(collect_frames)
+            .add_system(save_clip);
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .insert_resource(FrameSender(Mutex::new(sender)))
+                .init_resource::<PendingReadbacks>()
+                // Cleanup runs after the frame has been rendered and submitted.
+                .add_system_to_stage(RenderStage::Cleanup, collect_readbacks)
+                .add_system_to_stage(RenderStage::Cleanup, read_back_frame.after(collect_readbacks));
+        }
+    }
+}
+
+fn setup_clip_camera(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let size = Extent3d { width: CLIP_WIDTH, height: CLIP_HEIGHT, depth_or_array_layers: 1 };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+    let image = images.add(image);
+
+    commands
+        .spawn_bundle(Camera2dBundle {
+            // Only turned on for the frames that get captured.
+            camera: Camera {
+                priority: -1,
+                target: RenderTarget::Image(image.clone()),
+                is_active: false,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(UiCameraConfig { show_ui: false })
+        .insert(ClipCamera);
+    commands.insert_resource(ClipTarget { image, capture: false });
+}
+
+// Shows the same part of the world as player one's camera, scaled down to fit the clip.
+fn follow_game_camera(
+    windows: Res<Windows>,
+    query_game_camera: Query<(&Transform, &CameraFollow), Without<ClipCamera>>,
+    mut query_clip_camera: Query<(&mut Transform, &mut OrthographicProjection), With<ClipCamera>>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let followed = query_game_camera.iter().find(|(_, follow)| follow.0 == 0);
+    for (mut transform, mut projection) in query_clip_camera.iter_mut() {
+        if let Some((game_transform, _)) = followed {
+            *transform = *game_transform;
+        }
+        let scale = window.width() / CLIP_WIDTH as f32;
+        if projection.scale != scale {
+            projection.scale = scale;
+        }
+    }
+}
+
+fn schedule_capture(
+    time: Res<Time>,
+    state: Res<State<AppState>>,
+    mut buffer: ResMut<ClipBuffer>,
+    mut target: ResMut<ClipTarget>,
+    mut query_camera: Query<&mut Camera, With<ClipCamera>>,
+) {
+    let capture = *state.current() == AppState::InGame && buffer.timer.tick(time.delta()).just_finished();
+    if target.capture != capture {
+        target.capture = capture;
+    }
+    for mut camera in query_camera.iter_mut() {
+        if camera.is_active != capture {
+            camera.is_active = capture;
+        }
+    }
+}
+
+// Hands on whichever copies the GPU has finished since last frame, without waiting for the rest.
+fn collect_readbacks(
+    render_device: Res<RenderDevice>,
+    mut pending: ResMut<PendingReadbacks>,
+    sender: Res<FrameSender>,
+) {
+    if pending.0.is_empty() {
+        return;
+    }
+    render_device.wgpu_device().poll(Maintain::Poll);
+    let mut waiting = Vec::new();
+    for (buffer, mapped) in std::mem::take(&mut pending.0) {
+        let state = mapped.lock().map(|state| *state).unwrap_or(Some(false));
+        match state {
+            Some(true) => {}
+            // Failed copies are dropped, their frame is just missing from the clip.
+            Some(false) => continue,
+            None => {
+                waiting.push((buffer, mapped));
+                continue;
+            }
+        }
+        let mut frame = buffer.slice(..).get_mapped_range().to_vec();
+        buffer.unmap();
+        // The image is BGRA, GIFs want RGBA.
+        for pixel in frame.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        if let Ok(sender) = sender.0.lock() {
+            sender.send(frame).ok();
+        }
+    }
+    pending.0 = waiting;
+}
+
+fn read_back_frame(
+    target: Option<Res<ClipTarget>>,
+    gpu_images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut pending: ResMut<PendingReadbacks>,
+) {
+    let gpu_image = match target.as_ref().filter(|target| target.capture) {
+        Some(target) => match gpu_images.get(&target.image) {
+            Some(gpu_image) => gpu_image,
+            None => return,
+        },
+        None => return,
+    };
+    let bytes_per_row = CLIP_WIDTH * 4;
+    let buffer = render_device.create_buffer(&BufferDescriptor {
+        label: None,
+        size: (bytes_per_row * CLIP_HEIGHT) as u64,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = render_device.create_command_encoder(&CommandEncoderDescriptor::default());
+    encoder.copy_texture_to_buffer(
+        gpu_image.texture.as_image_copy(),
+        ImageCopyBuffer {
+            buffer: &buffer,
+            layout: ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(bytes_per_row),
+                rows_per_image: None,
+            },
+        },
+        Extent3d { width: CLIP_WIDTH, height: CLIP_HEIGHT, depth_or_array_layers: 1 },
+    );
+    render_queue.submit([encoder.finish()]);
+
+    // Mapped once the copy is done, which collect_readbacks checks for on a later frame.
+    let mapped = Arc::new(Mutex::new(None));
+    let on_mapped = mapped.clone();
+    render_device.map_buffer(&buffer.slice(..), MapMode::Read, move |result| {
+        if let Err(err) = &result {
+            warn!("Could not read back a clip frame: {}", err);
+        }
+        if let Ok(mut mapped) = on_mapped.lock() {
+            *mapped = Some(result.is_ok());
+        }
+    });
+    pending.0.push((buffer, mapped));
+}
+
+fn collect_frames(mut buffer: ResMut<ClipBuffer>) {
+    let frames: Vec<Vec<u8>> = match buffer.receiver.lock() {
+        Ok(receiver) => receiver.try_iter().collect(),
+        Err(_) => return,
+    };
+    for frame in frames {
+        if buffer.frames.len() == CLIP_FRAMES {
+            buffer.frames.pop_front();
+        }
+        buffer.frames.push_back(frame);
+    }
+}
+
+// Encoding takes a moment, so it happens on its own thread and the game carries on.
+fn save_clip(keyboard_input: Res<Input<KeyCode>>, buffer: Res<ClipBuffer>) {
+    if !keyboard_input.just_pressed(SAVE_CLIP_KEY) || buffer.frames.is_empty() {
+        return;
+    }
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or(0);
+    let path = match storage::config_path(&format!("clips/clip_{}.gif", seconds)) {
+        Some(path) => path,
+        None => {
+            warn!("No config directory to save clips in");
+            return;
+        }
+    };
+    let frames: Vec<Vec<u8>> = buffer.frames.iter().cloned().collect();
+    thread::spawn(move || match write_gif(&path, frames) {
+        Ok(()) => info!("Saved clip to {}", path.display()),
+        Err(err) => warn!("Could not save clip to {}: {}", path.display(), err),
+    });
+}
+
+fn write_gif(path: &Path, frames: Vec<Vec<u8>>) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    let file = File::create(path).map_err(|err| err.to_string())?;
+    let mut encoder = gif::Encoder::new(file, CLIP_WIDTH as u16, CLIP_HEIGHT as u16, &[])
+        .map_err(|err| err.to_string())?;
+    encoder.set_repeat(gif::Repeat::Infinite).map_err(|err| err.to_string())?;
+    for mut pixels in frames {
+        let mut frame = gif::Frame::from_rgba_speed(CLIP_WIDTH as u16, CLIP_HEIGHT as u16, &mut pixels, 10);
+        // In hundredths of a second.
+        frame.delay = (100 / CLIP_FPS) as u16;
+        encoder.write_frame(&frame).map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+
+const CONFIG_PATH: &str = "config.ron";
+
+// Tuning values read from assets/config.ron. The defaults are used until the file has loaded,
+// or if it's missing, and any field left out of the file keeps its default.
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "2f0c8e4a-7d1b-4b6e-9c3a-5e8f1a2b3c4d"]
+#[serde(default)]
+pub struct GameConfig {
+    pub bullet_speed: f32,
+    pub asteroid_base: f32,
+    pub thrust_impulse: f32,
+    // Radians turned per frame while a turn key is held.
+    pub rotation_rate: f32,
+    pub initial_lives: u8,
+    // An extra life every this many points, 0 for none.
+    pub extra_life_score: u32,
+    pub bullet_colour: Color,
+    pub button_colour: Color,
+    pub button_hovered_colour: Color,
+    pub button_pressed_colour: Color,
+    // Serves live stats for browser-source overlays on this port, see stream_overlay.rs.
+    pub stream_overlay_port: Option<u16>,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            bullet_speed: 200.0,
+            asteroid_base: 16.25,
+            thrust_impulse: 1.0,
+            rotation_rate: 0.1,
+            initial_lives: 3,
+            extra_life_score: 50,
+            bullet_colour: Color::rgb(0.7, 0.5, 0.5),
+            button_colour: Color::rgb(0.15, 0.15, 0.15),
+            button_hovered_colour: Color::rgb(0.25, 0.25, 0.25),
+            button_pressed_colour: Color::rgb(0.35, 0.75, 0.35),
+            stream_overlay_port: None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct GameConfigLoader;
+
+impl AssetLoader for GameConfigLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let config: GameConfig = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(config));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+// Keeps the asset alive so the asset server goes on watching the file for changes.
+struct GameConfigHandle(Handle<GameConfig>);
+
+pub struct Config;
+
+impl Plugin for Config {
+    fn build(&self, app: &mut App) {
+        app
+            .add_asset::<GameConfig>()
+            .init_asset_loader::<GameConfigLoader>()
+            .init_resource::<GameConfig>()
+            .add_startup_system(load_config)
+            .add_system(apply_config);
+    }
+}
+
+fn load_config(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(GameConfigHandle(asset_server.load(CONFIG_PATH)));
+}
+
+// Copies the asset into the GameConfig resource whenever it's loaded or edited on disk.
+fn apply_config(
+    handle: Res<GameConfigHandle>,
+    configs: Res<Assets<GameConfig>>,
+    mut asset_events: EventReader<AssetEvent<GameConfig>>,
+    mut config: ResMut<GameConfig>,
+) {
+    for ev in asset_events.iter() {
+        match ev {
+            AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed }
+                if *changed == handle.0 =>
+            {
+                if let Some(loaded) = configs.get(changed) {
+                    info!("Loaded {}", CONFIG_PATH);
+                    *config = loaded.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+}
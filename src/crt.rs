@@ -0,0 +1,162 @@
+// With the CRT filter on, the game cameras render into an image instead of the window and a
+// separate camera draws that image onto a fullscreen quad through the CRT shader.
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::render::camera::RenderTarget;
+use bevy::render::render_resource::{
+    AsBindGroup, Extent3d, ShaderRef, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+};
+use bevy::render::view::RenderLayers;
+use bevy::sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle, Mesh2dHandle};
+use bevy::window::{WindowId, WindowResized};
+
+use crate::camera::CameraFollow;
+use crate::settings::Settings;
+
+// Nothing else is drawn on this layer, so the CRT camera only sees its quad.
+const CRT_LAYER: u8 = 1;
+
+#[derive(AsBindGroup, TypeUuid, Clone)]
+#[uuid = "6b1a3f2e-4c5d-4e8f-9a0b-1c2d3e4f5a6b"]
+struct CrtMaterial {
+    #[texture(0)]
+    #[sampler(1)]
+    screen: Handle<Image>,
+}
+
+impl Material2d for CrtMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/crt.wgsl".into()
+    }
+}
+
+struct CrtScreen {
+    image: Handle<Image>,
+}
+
+#[derive(Component)]
+struct CrtCamera;
+
+#[derive(Component)]
+struct CrtQuad;
+
+pub struct CrtFilter;
+
+impl Plugin for CrtFilter {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugin(Material2dPlugin::<CrtMaterial>::default())
+            .add_startup_system(setup_crt)
+            .add_system(resize_crt_screen)
+            .add_system(apply_crt_setting);
+    }
+}
+
+fn screen_image(window: &Window) -> Image {
+    let size = Extent3d {
+        width: window.physical_width().max(1),
+        height: window.physical_height().max(1),
+        depth_or_array_layers: 1,
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+    image
+}
+
+fn screen_quad(window: &Window) -> Mesh {
+    Mesh::from(shape::Quad::new(Vec2::new(window.width(), window.height())))
+}
+
+fn setup_crt(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<CrtMaterial>>,
+) {
+    // Running headless there's no screen, so no CrtScreen either.
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let image = images.add(screen_image(window));
+
+    commands
+        .spawn_bundle(MaterialMesh2dBundle {
+            mesh: meshes.add(screen_quad(window)).into(),
+            material: materials.add(CrtMaterial { screen: image.clone() }),
+            ..default()
+        })
+        .insert(RenderLayers::layer(CRT_LAYER))
+        .insert(CrtQuad);
+    // Draws after the game cameras have filled in the image.
+    commands
+        .spawn_bundle(Camera2dBundle {
+            camera: Camera {
+                priority: 10,
+                is_active: false,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(RenderLayers::layer(CRT_LAYER))
+        .insert(UiCameraConfig { show_ui: false })
+        .insert(CrtCamera);
+    commands.insert_resource(CrtScreen { image });
+}
+
+fn resize_crt_screen(
+    windows: Res<Windows>,
+    screen: Option<Res<CrtScreen>>,
+    mut resized_event: EventReader<WindowResized>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    query_quad: Query<&Mesh2dHandle, With<CrtQuad>>,
+) {
+    let (screen, window) = match (screen, windows.get_primary()) {
+        (Some(screen), Some(window)) if resized_event.iter().last().is_some() => (screen, window),
+        _ => return,
+    };
+    if let Some(image) = images.get_mut(&screen.image) {
+        *image = screen_image(window);
+    }
+    for quad in query_quad.iter() {
+        if let Some(mesh) = meshes.get_mut(&quad.0) {
+            *mesh = screen_quad(window);
+        }
+    }
+}
+
+fn apply_crt_setting(
+    settings: Res<Settings>,
+    screen: Option<Res<CrtScreen>>,
+    mut query_crt_camera: Query<&mut Camera, With<CrtCamera>>,
+    mut query_game_cameras: Query<&mut Camera, (With<CameraFollow>, Without<CrtCamera>)>,
+) {
+    let screen = match screen {
+        Some(screen) if settings.is_changed() => screen,
+        _ => return,
+    };
+    for mut camera in query_crt_camera.iter_mut() {
+        camera.is_active = settings.crt;
+    }
+    let target = if settings.crt {
+        RenderTarget::Image(screen.image.clone())
+    } else {
+        RenderTarget::Window(WindowId::primary())
+    };
+    for mut camera in query_game_cameras.iter_mut() {
+        camera.target = target.clone();
+    }
+}
@@ -0,0 +1,81 @@
+// When the cameras only see part of the arena, like in split screen or while spectating, meteors and
+// bullets well outside every camera's view aren't drawn, and get Culled so animation and trails can
+// skip them too. Physics still runs for them as usual. With one camera on the whole arena nothing
+// is ever far enough out to be culled.
+use bevy::prelude::*;
+use bevy::render::view::VisibilitySystems;
+
+use crate::camera::CameraFollow;
+use crate::{despawn_dead, Bullet, Dead, Meteor, Parked};
+
+// Room around each view for the biggest meteor to still be drawn while only partly in sight.
+const CULL_MARGIN: f32 = 400.0;
+
+#[derive(Component)]
+pub struct Culled;
+
+pub struct Culling;
+
+impl Plugin for Culling {
+    fn build(&self, app: &mut App) {
+        // Before despawn_dead, so nothing is left to insert Culled on once it's gone.
+        app.add_system_to_stage(
+            CoreStage::PostUpdate,
+            cull_offscreen.after(VisibilitySystems::CheckVisibility).before(despawn_dead),
+        );
+    }
+}
+
+// Bevy has just worked out what's visible, which for sprites is everything that isn't hidden.
+fn cull_offscreen(
+    mut commands: Commands,
+    query_camera: Query<(&GlobalTransform, &Camera, &OrthographicProjection), With<CameraFollow>>,
+    mut visibilities: ParamSet<(
+        Query<
+            (Entity, &GlobalTransform, &mut ComputedVisibility, Option<&Children>, Option<&Culled>),
+            (Or<(With<Meteor>, With<Bullet>)>, Without<Parked>, Without<Dead>),
+        >,
+        Query<&mut ComputedVisibility>,
+    )>,
+) {
+    let views: Vec<(Vec2, Vec2)> = query_camera
+        .iter()
+        .filter(|(_, camera, _)| camera.is_active)
+        .filter_map(|(transform, camera, projection)| {
+            let half_size = camera.logical_viewport_size()? * projection.scale / 2.0 + CULL_MARGIN;
+            Some((transform.translation().truncate(), half_size))
+        })
+        .collect();
+    // Nothing's watching on the headless server, so there's nothing to cull for.
+    if views.is_empty() {
+        return;
+    }
+    let mut hidden_children = Vec::new();
+    for (entity, transform, mut visibility, children, culled) in visibilities.p0().iter_mut() {
+        let position = transform.translation().truncate();
+        let in_view = views.iter().any(|(centre, half_size)| {
+            let offset = (position - *centre).abs();
+            offset.x <= half_size.x && offset.y <= half_size.y
+        });
+        match (in_view, culled.is_some()) {
+            (true, true) => {
+                commands.entity(entity).remove::<Culled>();
+            }
+            (false, false) => {
+                commands.entity(entity).insert(Culled);
+            }
+            _ => {}
+        }
+        if !in_view {
+            visibility.is_visible = false;
+            // Vector outlines are drawn as children.
+            hidden_children.extend(children.into_iter().flatten().copied());
+        }
+    }
+    let mut query_children = visibilities.p1();
+    for child in hidden_children {
+        if let Ok(mut visibility) = query_children.get_mut(child) {
+            visibility.is_visible = false;
+        }
+    }
+}
@@ -0,0 +1,170 @@
+// The custom game screen, opened from the mode select screen. The player picks the starting lives,
+// how many meteors the first wave brings, how fast meteors fly and how the ships handle, and the
+// start buttons play a GameMode::Custom made from exactly those rules. The last rules picked are kept
+// for next time.
+use bevy::prelude::*;
+
+use crate::game_mode::{CustomRules, GameMode, HandlingModel};
+use crate::menu::{spawn_menu_button, spawn_menu_root, spawn_menu_title, MenuButton, MENU_TEXT};
+use crate::{storage, AppState, StartButton};
+
+const CUSTOM_GAME_FILE: &str = "custom_game.ron";
+const MAX_LIVES: u8 = 9;
+const MAX_METEORS: u8 = 10;
+const METEOR_SPEED_STEP: u8 = 25;
+const METEOR_SPEED_MIN: u8 = 50;
+const METEOR_SPEED_MAX: u8 = 200;
+const HANDLING_MODELS: [HandlingModel; 3] = [HandlingModel::Settings, HandlingModel::Nimble, HandlingModel::Heavy];
+
+// Each rule is one row on the screen with a value and -/+ buttons, like the settings.
+#[derive(Clone, Copy)]
+enum CustomRule {
+    Lives,
+    Meteors,
+    MeteorSpeed,
+    Handling,
+}
+
+const CUSTOM_RULE_ROWS: [CustomRule; 4] =
+    [CustomRule::Lives, CustomRule::Meteors, CustomRule::MeteorSpeed, CustomRule::Handling];
+
+impl CustomRule {
+    fn label(&self, rules: &CustomRules) -> String {
+        match self {
+            CustomRule::Lives => format!("Lives: {}", rules.lives),
+            CustomRule::Meteors => format!("First Wave: {} Meteors", rules.meteors),
+            CustomRule::MeteorSpeed => format!("Meteor Speed: {}%", rules.meteor_speed),
+            CustomRule::Handling => format!("Handling: {}", rules.handling.label()),
+        }
+    }
+
+    fn adjust(&self, rules: &mut CustomRules, steps: i32) {
+        match self {
+            CustomRule::Lives => rules.lives = (rules.lives as i32 + steps).clamp(1, MAX_LIVES as i32) as u8,
+            CustomRule::Meteors => rules.meteors = (rules.meteors as i32 + steps).clamp(1, MAX_METEORS as i32) as u8,
+            CustomRule::MeteorSpeed => {
+                rules.meteor_speed = (rules.meteor_speed as i32 + METEOR_SPEED_STEP as i32 * steps)
+                    .clamp(METEOR_SPEED_MIN as i32, METEOR_SPEED_MAX as i32) as u8;
+            }
+            CustomRule::Handling => {
+                let index = HANDLING_MODELS.iter().position(|model| *model == rules.handling).unwrap_or(0) as i32;
+                rules.handling = HANDLING_MODELS[(index + steps).clamp(0, HANDLING_MODELS.len() as i32 - 1) as usize];
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct CustomRuleAdjust {
+    rule: CustomRule,
+    steps: i32,
+}
+
+#[derive(Component)]
+struct CustomRuleValue(CustomRule);
+
+// The screen's start buttons, which play whatever the rules are when they're clicked.
+#[derive(Component)]
+struct CustomStartButton;
+
+#[derive(Component)]
+struct CustomGameScreen;
+
+pub struct CustomGames;
+
+impl Plugin for CustomGames {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(storage::load::<CustomRules>(CUSTOM_GAME_FILE).unwrap_or_default())
+            .add_system(save_custom_rules)
+            .add_system_set(SystemSet::on_enter(AppState::CustomGame).with_system(setup_custom_game))
+            .add_system_set(
+                SystemSet::on_update(AppState::CustomGame)
+                    .with_system(adjust_custom_rule)
+                    .with_system(update_custom_rules.after(adjust_custom_rule)),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::CustomGame).with_system(despawn_custom_game));
+    }
+}
+
+fn setup_custom_game(mut commands: Commands, asset_server: Res<AssetServer>, rules: Res<CustomRules>) {
+    let text_style = TextStyle {
+        font: asset_server.load("BungeeSpice-Regular.ttf"),
+        font_size: 30.0,
+        color: MENU_TEXT,
+    };
+    let mode = Some(GameMode::Custom(*rules));
+    spawn_menu_root(&mut commands)
+        .insert(CustomGameScreen)
+        .with_children(|parent| {
+            spawn_menu_title(parent, &asset_server, "Custom Game");
+            for rule in CUSTOM_RULE_ROWS {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        color: Color::NONE.into(),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        spawn_menu_button(row, &asset_server, "-").insert(CustomRuleAdjust { rule, steps: -1 });
+                        row.spawn_bundle(
+                            TextBundle::from_section(rule.label(&rules), text_style.clone()).with_style(Style {
+                                margin: UiRect::all(Val::Px(10.0)),
+                                ..default()
+                            }),
+                        )
+                        .insert(CustomRuleValue(rule));
+                        spawn_menu_button(row, &asset_server, "+").insert(CustomRuleAdjust { rule, steps: 1 });
+                    });
+            }
+            spawn_menu_button(parent, &asset_server, "Start")
+                .insert(StartButton { players: 1, mode })
+                .insert(CustomStartButton);
+            spawn_menu_button(parent, &asset_server, "Two Players")
+                .insert(StartButton { players: 2, mode })
+                .insert(CustomStartButton);
+            spawn_menu_button(parent, &asset_server, "Back").insert(MenuButton::Back);
+        });
+}
+
+fn adjust_custom_rule(
+    query: Query<(&Interaction, &CustomRuleAdjust), Changed<Interaction>>,
+    mut rules: ResMut<CustomRules>,
+) {
+    for (interaction, adjust) in query.iter() {
+        if *interaction == Interaction::Clicked {
+            adjust.rule.adjust(&mut rules, adjust.steps);
+        }
+    }
+}
+
+fn update_custom_rules(
+    rules: Res<CustomRules>,
+    mut query_text: Query<(&mut Text, &CustomRuleValue)>,
+    mut query_start: Query<&mut StartButton, With<CustomStartButton>>,
+) {
+    if !rules.is_changed() {
+        return;
+    }
+    for (mut text, value) in query_text.iter_mut() {
+        text.sections[0].value = value.0.label(&rules);
+    }
+    for mut start_button in query_start.iter_mut() {
+        start_button.mode = Some(GameMode::Custom(*rules));
+    }
+}
+
+fn despawn_custom_game(mut commands: Commands, query: Query<Entity, With<CustomGameScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn save_custom_rules(rules: Res<CustomRules>) {
+    if rules.is_changed() && !rules.is_added() {
+        storage::save(CUSTOM_GAME_FILE, &*rules);
+    }
+}
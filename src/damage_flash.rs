@@ -0,0 +1,121 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+use crate::palette::Tint;
+use crate::settings::Settings;
+use crate::ShipDestroyedEvent;
+
+const FLASH_DURATION: f32 = 0.15;
+const FLASH_ALPHA: f32 = 0.6;
+const VIGNETTE_DURATION: f32 = 1.2;
+const VIGNETTE_PULSES: f32 = 2.0;
+const VIGNETTE_TEXTURE_SIZE: u32 = 128;
+
+#[derive(Component)]
+struct ScreenFlash(Timer);
+
+#[derive(Component)]
+struct DamageVignette(Timer);
+
+pub struct DamageFlash;
+
+impl Plugin for DamageFlash {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(setup_damage_overlay)
+            .add_system(trigger_damage_flash)
+            .add_system(update_screen_flash.after(trigger_damage_flash))
+            .add_system(update_damage_vignette.after(trigger_damage_flash));
+    }
+}
+
+fn finished_timer(duration: f32) -> Timer {
+    let mut timer = Timer::from_seconds(duration, false);
+    timer.tick(timer.duration());
+    timer
+}
+
+// Clear in the middle and opaque towards the edges, tinted red by the overlay node.
+fn vignette_image() -> Image {
+    let size = VIGNETTE_TEXTURE_SIZE;
+    let centre = (size as f32 - 1.0) / 2.0;
+    let mut data = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let distance = Vec2::new(x as f32 - centre, y as f32 - centre).length() / centre;
+            let alpha = ((distance - 0.5) / 0.5).clamp(0.0, 1.0).powi(2);
+            data.extend_from_slice(&[255, 255, 255, (alpha * 255.0) as u8]);
+        }
+    }
+    Image::new(
+        Extent3d { width: size, height: size, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn setup_damage_overlay(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let full_screen = Style {
+        size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+        position_type: PositionType::Absolute,
+        ..default()
+    };
+    commands
+        .spawn_bundle(ImageBundle {
+            style: full_screen.clone(),
+            image: UiImage(images.add(vignette_image())),
+            color: Color::NONE.into(),
+            focus_policy: bevy::ui::FocusPolicy::Pass,
+            ..default()
+        })
+        .insert(DamageVignette(finished_timer(VIGNETTE_DURATION)))
+        .insert(Tint::Danger);
+    commands
+        .spawn_bundle(NodeBundle {
+            style: full_screen,
+            color: Color::NONE.into(),
+            focus_policy: bevy::ui::FocusPolicy::Pass,
+            ..default()
+        })
+        .insert(ScreenFlash(finished_timer(FLASH_DURATION)));
+}
+
+// Players who turned on reduced motion get neither the flash nor the pulsing.
+fn trigger_damage_flash(
+    settings: Res<Settings>,
+    mut destroyed_event: EventReader<ShipDestroyedEvent>,
+    mut query_flash: Query<&mut ScreenFlash>,
+    mut query_vignette: Query<&mut DamageVignette>,
+) {
+    if destroyed_event.iter().last().is_none() || settings.reduced_motion {
+        return;
+    }
+    for mut flash in query_flash.iter_mut() {
+        flash.0.reset();
+    }
+    for mut vignette in query_vignette.iter_mut() {
+        vignette.0.reset();
+    }
+}
+
+fn update_screen_flash(time: Res<Time>, mut query: Query<(&mut ScreenFlash, &mut UiColor)>) {
+    for (mut flash, mut colour) in query.iter_mut() {
+        flash.0.tick(time.delta());
+        let mut tint = Color::WHITE;
+        tint.set_a(FLASH_ALPHA * (1.0 - flash.0.percent()));
+        *colour = tint.into();
+    }
+}
+
+fn update_damage_vignette(time: Res<Time>, mut query: Query<(&mut DamageVignette, &mut UiColor)>) {
+    for (mut vignette, mut colour) in query.iter_mut() {
+        vignette.0.tick(time.delta());
+        let progress = vignette.0.percent();
+        // Pulses a couple of times while fading out overall.
+        let pulse = (progress * VIGNETTE_PULSES * PI).sin().abs();
+        colour.0.set_a(pulse * (1.0 - progress));
+    }
+}
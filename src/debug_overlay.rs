@@ -0,0 +1,111 @@
+// F3 toggles a panel with frame timing and how much is alive at the moment, for checking what a
+// slow frame had going on.
+use bevy::diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+
+use crate::particles::Particle;
+use crate::ui::set_text;
+use crate::{Bullet, Game, GameState, Meteor, Parked};
+
+const TOGGLE_KEY: KeyCode = KeyCode::F3;
+const DEBUG_TEXT_COLOUR: Color = Color::rgb(1.0, 1.0, 0.0);
+
+// On the panel and its text both, as visibility isn't inherited in this version of Bevy.
+#[derive(Component)]
+struct DebugOverlayNode;
+
+#[derive(Component)]
+struct DebugOverlayText;
+
+pub struct DebugOverlay;
+
+impl Plugin for DebugOverlay {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugin(FrameTimeDiagnosticsPlugin)
+            .add_startup_system(setup_debug_overlay)
+            .add_system(toggle_debug_overlay)
+            .add_system(update_debug_overlay.after(toggle_debug_overlay));
+    }
+}
+
+fn setup_debug_overlay(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect { right: Val::Px(10.0), bottom: Val::Px(10.0), ..default() },
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(DebugOverlayNode)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: asset_server.load("BungeeSpice-Regular.ttf"),
+                            font_size: 18.0,
+                            color: DEBUG_TEXT_COLOUR,
+                        },
+                    ),
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                })
+                .insert(DebugOverlayNode)
+                .insert(DebugOverlayText);
+        });
+}
+
+fn toggle_debug_overlay(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut query_overlay: Query<&mut Visibility, With<DebugOverlayNode>>,
+) {
+    if !keyboard_input.just_pressed(TOGGLE_KEY) {
+        return;
+    }
+    for mut visibility in query_overlay.iter_mut() {
+        visibility.is_visible = !visibility.is_visible;
+    }
+}
+
+fn game_state_name(state: &GameState) -> &'static str {
+    match state {
+        GameState::Loading => "Loading",
+        GameState::InProgress => "In Progress",
+        GameState::Ended => "Ended",
+        GameState::Waiting => "Waiting",
+    }
+}
+
+fn update_debug_overlay(
+    diagnostics: Res<Diagnostics>,
+    query_game: Query<&Game>,
+    query_meteors: Query<(), With<Meteor>>,
+    query_bullets: Query<(), (With<Bullet>, Without<Parked>)>,
+    query_particles: Query<&Visibility, (With<Particle>, Without<DebugOverlayText>)>,
+    mut query_text: Query<(&mut Text, &Visibility), With<DebugOverlayText>>,
+) {
+    let average = |diagnostic| diagnostics.get(diagnostic).and_then(|diagnostic| diagnostic.average());
+    let fps = average(FrameTimeDiagnosticsPlugin::FPS).unwrap_or(0.0);
+    let frame_time = average(FrameTimeDiagnosticsPlugin::FRAME_TIME).unwrap_or(0.0);
+    let value = format!(
+        "FPS: {:.0}\nFrame: {:.2} ms\nMeteors: {}\nBullets: {}\nParticles: {}\nGame: {}",
+        fps,
+        frame_time * 1000.0,
+        query_meteors.iter().count(),
+        query_bullets.iter().count(),
+        query_particles.iter().filter(|visibility| visibility.is_visible).count(),
+        game_state_name(&query_game.single().gameState)
+    );
+    for (mut text, visibility) in query_text.iter_mut() {
+        if visibility.is_visible {
+            set_text(&mut text, &value);
+        }
+    }
+}
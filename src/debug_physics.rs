@@ -0,0 +1,22 @@
+// Collider wireframes for working on the physics, only built in with the debug-physics feature.
+// They start hidden and F4 shows or hides them.
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::{DebugRenderContext, RapierDebugRenderPlugin};
+
+const TOGGLE_KEY: KeyCode = KeyCode::F4;
+
+pub struct PhysicsDebug;
+
+impl Plugin for PhysicsDebug {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugin(RapierDebugRenderPlugin { enabled: false, ..default() })
+            .add_system(toggle_physics_debug);
+    }
+}
+
+fn toggle_physics_debug(keyboard_input: Res<Input<KeyCode>>, mut debug_render: ResMut<DebugRenderContext>) {
+    if keyboard_input.just_pressed(TOGGLE_KEY) {
+        debug_render.enabled = !debug_render.enabled;
+    }
+}
@@ -0,0 +1,196 @@
+// What the ship, meteors, bullets and pickups are made of, read from assets/game.entities.ron and
+// hot-reloaded the same way as config.ron, so new kinds of thing can be tried out without a rebuild.
+// The spawn systems build everything from the EntityDefinitions resource rather than constants of
+// their own. Tuning like speeds and the meteor base size stays in config.ron. Which image each sprite
+// is cut from is fixed when the texture atlas is laid out at startup, see animation.rs, so only their
+// sizes are defined here.
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy_rapier2d::prelude::Collider;
+use serde::Deserialize;
+
+const DEFINITIONS_PATH: &str = "game.entities.ron";
+
+// A collider's shape, centred on whatever it's attached to.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum ColliderShape {
+    Ball { radius: f32 },
+    // Lies along the x axis, which is the way the ship sprite points.
+    Capsule { half_length: f32, radius: f32 },
+    Cuboid { half_extents: Vec2 },
+}
+
+impl ColliderShape {
+    pub fn collider(&self) -> Collider {
+        match *self {
+            ColliderShape::Ball { radius } => Collider::ball(radius),
+            ColliderShape::Capsule { half_length, radius } => Collider::capsule_x(half_length, radius),
+            ColliderShape::Cuboid { half_extents } => Collider::cuboid(half_extents.x, half_extents.y),
+        }
+    }
+}
+
+// Somewhere on the ship that bullets are fired from.
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+#[serde(default)]
+pub struct MountDefinition {
+    // From the ship's centre, with x along its nose.
+    pub offset: Vec2,
+    // Radians anticlockwise from straight ahead that its bullets fly off at.
+    pub angle: f32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ShipDefinition {
+    pub size: Vec2,
+    pub collider: ColliderShape,
+    pub restitution: f32,
+    // Every mount fires a bullet each time the ship shoots.
+    pub mounts: Vec<MountDefinition>,
+}
+
+impl Default for ShipDefinition {
+    fn default() -> Self {
+        ShipDefinition {
+            size: Vec2::new(65.0, 33.0),
+            collider: ColliderShape::Capsule { half_length: 16.5, radius: 16.5 },
+            restitution: 0.7,
+            mounts: vec![MountDefinition { offset: Vec2::new(32.5, 0.0), angle: 0.0 }],
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct MeteorDefinition {
+    // Scored for each meteor shot, whatever its size, before any powerups or trick shots.
+    pub points: u32,
+    // Meteors this size or smaller are destroyed outright instead of splitting in two.
+    pub smallest_size: u8,
+    // Radians either side of the meteor's heading that its halves fly off at.
+    pub split_angle: f32,
+    // How wide the collider is as a fraction of the sprite.
+    pub hitbox: f32,
+}
+
+impl Default for MeteorDefinition {
+    fn default() -> Self {
+        MeteorDefinition { points: 1, smallest_size: 2, split_angle: 0.7, hitbox: 1.0 }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct BulletDefinition {
+    pub radius: f32,
+}
+
+impl Default for BulletDefinition {
+    fn default() -> Self {
+        BulletDefinition { radius: 2.5 }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PowerupDefinition {
+    // The chance of a destroyed meteor leaving a pickup behind.
+    pub drop_chance: f32,
+    pub size: f32,
+    // How close a ship has to fly to a pickup to collect it.
+    pub pickup_radius: f32,
+    // Seconds a pickup floats around before it's gone.
+    pub lifetime: f32,
+    // Seconds an effect lasts once it's collected.
+    pub effect_duration: f32,
+}
+
+impl Default for PowerupDefinition {
+    fn default() -> Self {
+        PowerupDefinition {
+            drop_chance: 0.1,
+            size: 14.0,
+            pickup_radius: 30.0,
+            lifetime: 10.0,
+            effect_duration: 8.0,
+        }
+    }
+}
+
+// The defaults are used until the file has loaded, or if it's missing, and anything left out of the
+// file keeps its default.
+#[derive(Deserialize, TypeUuid, Clone, Debug, Default)]
+#[uuid = "8b3e5d71-2c4f-4a9e-b6d0-3f7a1c9e5b24"]
+#[serde(default)]
+pub struct EntityDefinitions {
+    pub ship: ShipDefinition,
+    pub meteor: MeteorDefinition,
+    pub bullet: BulletDefinition,
+    pub powerup: PowerupDefinition,
+}
+
+#[derive(Default)]
+struct EntityDefinitionsLoader;
+
+impl AssetLoader for EntityDefinitionsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let definitions: EntityDefinitions = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(definitions));
+            Ok(())
+        })
+    }
+
+    // Plain .ron files are config.ron's, the asset server tries the longest extension first.
+    fn extensions(&self) -> &[&str] {
+        &["entities.ron"]
+    }
+}
+
+// Keeps the asset alive so the asset server goes on watching the file for changes.
+struct EntityDefinitionsHandle(Handle<EntityDefinitions>);
+
+pub struct Definitions;
+
+impl Plugin for Definitions {
+    fn build(&self, app: &mut App) {
+        app
+            .add_asset::<EntityDefinitions>()
+            .init_asset_loader::<EntityDefinitionsLoader>()
+            .init_resource::<EntityDefinitions>()
+            .add_startup_system(load_definitions)
+            .add_system(apply_definitions);
+    }
+}
+
+fn load_definitions(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(EntityDefinitionsHandle(asset_server.load(DEFINITIONS_PATH)));
+}
+
+// Things already out keep what they were spawned with, the new definitions apply to the next ones.
+fn apply_definitions(
+    handle: Res<EntityDefinitionsHandle>,
+    assets: Res<Assets<EntityDefinitions>>,
+    mut asset_events: EventReader<AssetEvent<EntityDefinitions>>,
+    mut definitions: ResMut<EntityDefinitions>,
+) {
+    for ev in asset_events.iter() {
+        match ev {
+            AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed }
+                if *changed == handle.0 =>
+            {
+                if let Some(loaded) = assets.get(changed) {
+                    info!("Loaded {}", DEFINITIONS_PATH);
+                    *definitions = loaded.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,50 @@
+// With a seed set, every frame advances the simulation by the same fixed step and all gameplay
+// randomness comes from that seed, so the same inputs always play out the same way.
+// The core gameplay systems are ordered in main whether or not this is on.
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::{RapierConfiguration, TimestepMode};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::launch::LaunchOptions;
+use crate::replay::GameRng;
+
+pub const FIXED_DELTA: f32 = 1.0 / 60.0;
+
+#[derive(Clone, Copy)]
+pub struct Deterministic {
+    pub seed: u64,
+}
+
+// Turned on by inserting Deterministic before this plugin, e.g. from a test, or by launching
+// with --seed. Has to be added after the physics plugin so there's a RapierConfiguration to change.
+pub struct DeterministicSimulation;
+
+impl Plugin for DeterministicSimulation {
+    fn build(&self, app: &mut App) {
+        let seed = app.world.get_resource::<LaunchOptions>().and_then(|options| options.seed);
+        let deterministic = app.world.get_resource::<Deterministic>().copied();
+        let deterministic = match deterministic.or(seed.map(|seed| Deterministic { seed })) {
+            Some(deterministic) => deterministic,
+            None => return,
+        };
+        app.world.insert_resource(deterministic);
+        info!("Running deterministically with seed {}", deterministic.seed);
+        app.world.insert_resource(GameRng(StdRng::seed_from_u64(deterministic.seed)));
+        if let Some(mut rapier_config) = app.world.get_resource_mut::<RapierConfiguration>() {
+            rapier_config.timestep_mode = TimestepMode::Fixed { dt: FIXED_DELTA, substeps: 1 };
+        }
+    }
+}
+
+// Timers and movement see exactly one fixed step per frame however long the frame really took.
+pub fn fix_time_step(deterministic: Option<Res<Deterministic>>, mut time: ResMut<Time>) {
+    if deterministic.is_none() {
+        return;
+    }
+    if let Some(last_update) = time.last_update() {
+        time.update_with_instant(last_update + Duration::from_secs_f32(FIXED_DELTA));
+    }
+}
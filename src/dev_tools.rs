@@ -0,0 +1,91 @@
+// The egui world inspector and some cheats, only built in with the dev-tools feature. The inspector
+// lists every entity with its components, including the Rapier ones and the Game, and lets their
+// values be changed live. The hotkeys set up situations that would take a while to play into:
+// F5 drops a meteor under the cursor, F6 gives every ship every powerup, and F7 clears the field
+// so the next wave comes in.
+use bevy::prelude::*;
+use bevy_inspector_egui::{RegisterInspectable, WorldInspectorPlugin};
+use bevy_inspector_egui_rapier::InspectableRapierPlugin;
+
+use crate::camera::CameraFollow;
+use crate::definitions::EntityDefinitions;
+use crate::powerups::{grant_powerup, POWERUP_KINDS};
+use crate::spawning::{MeteorSpec, SpawnCommands};
+use crate::{AppState, Game, Meteor, Parked, Spaceship};
+
+const SPAWN_METEOR_KEY: KeyCode = KeyCode::F5;
+const GRANT_POWERUPS_KEY: KeyCode = KeyCode::F6;
+const SKIP_WAVE_KEY: KeyCode = KeyCode::F7;
+const CHEAT_METEOR_SIZE: u8 = 8;
+
+pub struct DevTools;
+
+impl Plugin for DevTools {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugin(WorldInspectorPlugin::new())
+            .add_plugin(InspectableRapierPlugin)
+            .register_inspectable::<Game>()
+            .register_inspectable::<Meteor>()
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(spawn_meteor_at_cursor)
+                    .with_system(grant_all_powerups)
+                    .with_system(skip_wave),
+            );
+    }
+}
+
+// Worked out from player one's camera, so it's only under the cursor without split screen.
+fn spawn_meteor_at_cursor(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    query_camera: Query<(&GlobalTransform, &OrthographicProjection, &CameraFollow)>,
+) {
+    if !keyboard_input.just_pressed(SPAWN_METEOR_KEY) {
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let camera = query_camera.iter().find(|(_, _, follow)| follow.0 == 0);
+    let (cursor, (camera_transform, projection, _)) = match (window.cursor_position(), camera) {
+        (Some(cursor), Some(camera)) => (cursor, camera),
+        _ => return,
+    };
+    let from_centre = cursor - Vec2::new(window.width(), window.height()) / 2.0;
+    let position = camera_transform.translation().truncate() + from_centre * projection.scale;
+    commands.spawn_meteor(MeteorSpec::new(CHEAT_METEOR_SIZE).at(position));
+}
+
+fn grant_all_powerups(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    definitions: Res<EntityDefinitions>,
+    query_ship: Query<Entity, With<Spaceship>>,
+) {
+    if !keyboard_input.just_pressed(GRANT_POWERUPS_KEY) {
+        return;
+    }
+    for ship in query_ship.iter() {
+        for kind in POWERUP_KINDS {
+            grant_powerup(&mut commands, &definitions.powerup, ship, kind);
+        }
+    }
+}
+
+// The wave counts as cleared, and the next one turns up after the usual breather.
+fn skip_wave(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    query_meteor: Query<Entity, With<Meteor>>,
+) {
+    if !keyboard_input.just_pressed(SKIP_WAVE_KEY) {
+        return;
+    }
+    for meteor in query_meteor.iter() {
+        commands.entity(meteor).insert(Parked);
+    }
+}
@@ -0,0 +1,274 @@
+// Two ships face off across a sparse meteor field and can shoot each other down. Losing a ship,
+// to a bullet or a meteor, hands the round to the other player, and whoever wins most of
+// DUEL_ROUNDS takes the match. Lives and waves don't apply, rounds replace both.
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::RapierContext;
+use rand::Rng;
+
+use crate::camera::{CameraShakeEvent, SHIP_HIT_TRAUMA};
+use crate::game_mode::GameMode;
+use crate::powerups::Shield;
+use crate::replay::GameRng;
+use crate::spawning::{MeteorSpec, ShipSpec, SpawnCommands};
+use crate::ui::set_text;
+use crate::{
+    arena_size, AppState, Bullet, DestroyCommands, Game, GameState, GameplayEntity, Parked, PlayerId,
+    ShipDestroyedEvent, Spaceship, StartGameEvent,
+};
+
+const DUEL_ROUNDS: u8 = 5;
+const ROUND_BREAK: f32 = 2.0;
+const DUEL_METEORS: usize = 4;
+const DUEL_METEOR_SIZE: u8 = 4;
+const DUEL_METEOR_SPEED: f32 = 40.0;
+// Ships start on opposite sides facing each other.
+const DUEL_SPAWNS: [(Vec2, f32); 2] = [(Vec2::new(-300.0, 0.0), 0.0), (Vec2::new(300.0, 0.0), PI)];
+const SPAWN_CLEARANCE: f32 = 150.0;
+const DUEL_TEXT_COLOUR: Color = Color::rgb(1.0, 0.8, 0.0);
+
+#[derive(PartialEq, Eq)]
+enum RoundPhase {
+    Fighting,
+    // Waiting to start the next round, or to end the match once someone has won it.
+    Break,
+}
+
+struct DuelMatch {
+    wins: [u8; 2],
+    round: u32,
+    phase: RoundPhase,
+    timer: Timer,
+    // Who took the last round, None for a draw.
+    last_winner: Option<u8>,
+}
+
+impl Default for DuelMatch {
+    fn default() -> Self {
+        DuelMatch {
+            wins: [0; 2],
+            round: 0,
+            phase: RoundPhase::Break,
+            timer: Timer::from_seconds(ROUND_BREAK, false),
+            last_winner: None,
+        }
+    }
+}
+
+impl DuelMatch {
+    fn champion(&self) -> Option<u8> {
+        let needed = DUEL_ROUNDS / 2 + 1;
+        (0..2).find(|player| self.wins[*player as usize] >= needed)
+    }
+}
+
+#[derive(Component)]
+struct DuelScoreUI;
+
+#[derive(Component)]
+struct DuelBanner;
+
+pub struct Duels;
+
+impl Plugin for Duels {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<DuelMatch>()
+            .add_startup_system(setup_duel_ui)
+            .add_system(reset_duel)
+            .add_system(update_duel_ui)
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(bullet_hits_ship)
+                    .with_system(run_rounds.after(bullet_hits_ship)),
+            );
+    }
+}
+
+fn reset_duel(mut game_event: EventReader<StartGameEvent>, mut duel: ResMut<DuelMatch>) {
+    if game_event.iter().last().is_some() {
+        *duel = DuelMatch::default();
+    }
+}
+
+fn in_duel(mode: &GameMode, game: &Game) -> bool {
+    *mode == GameMode::Duel && matches!(game.gameState, GameState::InProgress)
+}
+
+fn bullet_hits_ship(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    rapier_context: Res<RapierContext>,
+    query_game: Query<&Game>,
+    query_ship: Query<(Entity, &PlayerId, &Transform, Option<&Shield>), With<Spaceship>>,
+    query_bullets: Query<(Entity, &PlayerId), (With<Bullet>, Without<Parked>)>,
+    mut ship_destroyed_event: EventWriter<ShipDestroyedEvent>,
+    mut shake_event: EventWriter<CameraShakeEvent>,
+) {
+    if !in_duel(&mode, query_game.single()) {
+        return;
+    }
+    for (entity_ship, player, transform, shield) in query_ship.iter() {
+        if shield.is_some() {
+            continue;
+        }
+        let hit_by = query_bullets.iter().find(|(entity_bullet, owner)| {
+            *owner != player && rapier_context.intersection_pair(entity_ship, *entity_bullet) == Some(true)
+        });
+        if let Some((entity_bullet, _)) = hit_by {
+            commands.destroy(entity_ship);
+            commands.entity(entity_bullet).insert(Parked);
+            shake_event.send(CameraShakeEvent { trauma: SHIP_HIT_TRAUMA });
+            ship_destroyed_event.send(ShipDestroyedEvent {
+                player: player.0,
+                position: transform.translation.truncate(),
+            });
+        }
+    }
+}
+
+fn run_rounds(
+    mut commands: Commands,
+    time: Res<Time>,
+    mode: Res<GameMode>,
+    windows: Res<Windows>,
+    window_descriptor: Res<WindowDescriptor>,
+    mut duel: ResMut<DuelMatch>,
+    mut rng: ResMut<GameRng>,
+    mut query_game: Query<&mut Game>,
+    gameplay_query: Query<Entity, GameplayEntity>,
+    mut ship_destroyed_event: EventReader<ShipDestroyedEvent>,
+) {
+    let mut destroyed: Vec<u8> = ship_destroyed_event.iter().map(|ev| ev.player).collect();
+    // A ship hit by a bullet and a meteor in the same frame is destroyed twice.
+    destroyed.sort_unstable();
+    destroyed.dedup();
+    let mut game = query_game.single_mut();
+    if !in_duel(&mode, &game) {
+        return;
+    }
+
+    match duel.phase {
+        RoundPhase::Fighting => {
+            if destroyed.is_empty() {
+                return;
+            }
+            // Both going down in the same frame is a draw and nobody scores.
+            duel.last_winner = match destroyed.as_slice() {
+                [loser] => Some(1 - loser),
+                _ => None,
+            };
+            if let Some(winner) = duel.last_winner {
+                duel.wins[winner as usize] += 1;
+                game.player_scores[winner as usize] = u32::from(duel.wins[winner as usize]);
+            }
+            duel.phase = RoundPhase::Break;
+            duel.timer.reset();
+        }
+        RoundPhase::Break => {
+            if !duel.timer.tick(time.delta()).just_finished() {
+                return;
+            }
+            if duel.champion().is_some() {
+                game.gameState = GameState::Ended;
+                return;
+            }
+            for entity in gameplay_query.iter() {
+                commands.destroy(entity);
+            }
+            for (player, (position, orientation)) in DUEL_SPAWNS.iter().enumerate() {
+                commands.spawn_ship(ShipSpec::player(player as u8).at(*position).facing(*orientation));
+            }
+            let arena = arena_size(&windows, &window_descriptor);
+            for _ in 0..DUEL_METEORS {
+                let velocity = Vec2::from_angle(rng.0.gen_range(0.0..2.0 * PI)) * DUEL_METEOR_SPEED;
+                let spec = MeteorSpec::new(DUEL_METEOR_SIZE).with_velocity(velocity);
+                commands.spawn_meteor(spec.at(meteor_position(&mut rng.0, arena)));
+            }
+            duel.round += 1;
+            duel.last_winner = None;
+            duel.phase = RoundPhase::Fighting;
+        }
+    }
+}
+
+// Anywhere not right on top of a ship's starting point.
+fn meteor_position(rng: &mut impl Rng, arena: Vec2) -> Vec2 {
+    loop {
+        let position = Vec2::new(
+            (rng.gen::<f32>() - 0.5) * arena.x,
+            (rng.gen::<f32>() - 0.5) * arena.y,
+        );
+        if DUEL_SPAWNS.iter().all(|(spawn, _)| spawn.distance(position) > SPAWN_CLEARANCE) {
+            return position;
+        }
+    }
+}
+
+fn setup_duel_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("BungeeSpice-Regular.ttf");
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(
+                    TextBundle::from_section(
+                        "",
+                        TextStyle { font: font.clone(), font_size: 30.0, color: DUEL_TEXT_COLOUR },
+                    )
+                    .with_style(Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect { top: Val::Px(10.0), ..default() },
+                        ..default()
+                    }),
+                )
+                .insert(DuelScoreUI);
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "",
+                    TextStyle { font, font_size: 60.0, color: DUEL_TEXT_COLOUR },
+                ))
+                .insert(DuelBanner);
+        });
+}
+
+fn update_duel_ui(
+    mode: Res<GameMode>,
+    duel: Res<DuelMatch>,
+    query_game: Query<&Game>,
+    mut query_score: Query<&mut Text, (With<DuelScoreUI>, Without<DuelBanner>)>,
+    mut query_banner: Query<&mut Text, (With<DuelBanner>, Without<DuelScoreUI>)>,
+) {
+    let active = in_duel(&mode, query_game.single());
+    let score = if active {
+        format!("P1 {} - {} P2   Best of {}", duel.wins[0], duel.wins[1], DUEL_ROUNDS)
+    } else {
+        String::new()
+    };
+    let banner = match (active, &duel.phase) {
+        (false, _) | (true, RoundPhase::Fighting) => String::new(),
+        (true, RoundPhase::Break) => match (duel.champion(), duel.last_winner) {
+            (Some(champion), _) => format!("P{} WINS THE DUEL", champion + 1),
+            (None, _) if duel.round == 0 => "ROUND 1".to_string(),
+            (None, Some(winner)) => format!("P{} TAKES ROUND {}", winner + 1, duel.round),
+            (None, None) => format!("ROUND {} IS A DRAW", duel.round),
+        },
+    };
+    for mut text in query_score.iter_mut() {
+        set_text(&mut text, &score);
+    }
+    for mut text in query_banner.iter_mut() {
+        set_text(&mut text, &banner);
+    }
+}
@@ -0,0 +1,117 @@
+// Keeps the game playable without its art, e.g. from a checkout that doesn't have the asset pack.
+// If the ship's or any meteor's sheet fails to load, ships or meteors are drawn with the line
+// outlines from vector.rs instead of as empty sprites, whatever the render style setting says. If
+// the font fails, a sans serif font the system has is put in its place, so every bit of text that
+// asked for the game's font gets that instead. A toast says what's missing either way, see toast.rs.
+// Browsers have no system fonts to fall back on, so the text stays blank there.
+use bevy::asset::{HandleId, LoadState};
+use bevy::prelude::*;
+
+use crate::animation::Sheet;
+use crate::spawning::{LARGE_METEOR, MEDIUM_METEOR, SMALL_METEOR};
+use crate::theme::Theme;
+use crate::toast::ToastEvent;
+
+const GAME_FONT: &str = "BungeeSpice-Regular.ttf";
+// Tried in order. DejaVu and Liberation are on most Linux desktops, Arial is on Windows and macOS.
+#[cfg(not(target_arch = "wasm32"))]
+const SYSTEM_FONTS: [&str; 7] = [
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu-sans-fonts/DejaVuSans.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "/Library/Fonts/Arial.ttf",
+];
+
+// What's being drawn as outlines because its sheet didn't load.
+#[derive(Default, PartialEq, Eq)]
+pub struct MissingArt {
+    pub ship: bool,
+    pub meteors: bool,
+}
+
+pub struct Fallbacks;
+
+impl Plugin for Fallbacks {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<MissingArt>()
+            .add_system(check_missing_art)
+            .add_system(fall_back_font);
+    }
+}
+
+// Kept up to date as themes are switched, since one theme's sheets may load where another's didn't.
+// The headless server never loads an image, so nothing is missing there.
+fn check_missing_art(
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    images: Option<Res<Assets<Image>>>,
+    mut missing: ResMut<MissingArt>,
+    mut toast_event: EventWriter<ToastEvent>,
+) {
+    if images.is_none() {
+        return;
+    }
+    let failed = |sheet: Sheet| asset_server.get_load_state(theme.sheet(sheet)) == LoadState::Failed;
+    let meteors: Vec<Sheet> = [LARGE_METEOR, MEDIUM_METEOR, SMALL_METEOR]
+        .into_iter()
+        .map(Sheet::Meteor)
+        .filter(|sheet| failed(*sheet))
+        .collect();
+    let now = MissingArt { ship: failed(Sheet::Ship), meteors: !meteors.is_empty() };
+    if now == *missing {
+        return;
+    }
+    let mut newly_missing = Vec::new();
+    if now.ship && !missing.ship {
+        newly_missing.push((theme.sheet(Sheet::Ship), "ships"));
+    }
+    if now.meteors && !missing.meteors {
+        newly_missing.extend(meteors.into_iter().map(|sheet| (theme.sheet(sheet), "meteors")));
+    }
+    for (path, drawn) in newly_missing {
+        warn!("Could not load {}, drawing {} as outlines", path, drawn);
+        toast_event.send(ToastEvent(format!("{} is missing, {} are drawn as outlines", path, drawn)));
+    }
+    *missing = now;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn system_font() -> Option<(&'static str, Font)> {
+    SYSTEM_FONTS.iter().find_map(|path| {
+        let bytes = std::fs::read(path).ok()?;
+        Font::try_from_bytes(bytes).ok().map(|font| (*path, font))
+    })
+}
+
+#[cfg(target_arch = "wasm32")]
+fn system_font() -> Option<(&'static str, Font)> {
+    None
+}
+
+fn fall_back_font(
+    asset_server: Res<AssetServer>,
+    fonts: Option<ResMut<Assets<Font>>>,
+    mut toast_event: EventWriter<ToastEvent>,
+    mut done: Local<bool>,
+) {
+    let mut fonts = match fonts {
+        Some(fonts) if !*done => fonts,
+        _ => return,
+    };
+    if asset_server.get_load_state(GAME_FONT) != LoadState::Failed {
+        return;
+    }
+    *done = true;
+    match system_font() {
+        Some((path, font)) => {
+            warn!("Could not load {}, using {} instead", GAME_FONT, path);
+            fonts.set_untracked(HandleId::from(GAME_FONT), font);
+            toast_event.send(ToastEvent(format!("{} is missing, using a system font", GAME_FONT)));
+        }
+        None => warn!("Could not load {} and there's no system font to use instead", GAME_FONT),
+    }
+}
@@ -0,0 +1,48 @@
+// Applies the vsync and frame cap video settings. Vsync is the window's present mode, the cap is a
+// sleep at the end of each frame until it's taken as long as the cap allows. Benchmarks run uncapped
+// so the sleep doesn't end up in their timings, and browsers pace frames themselves and can't sleep.
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy::window::PresentMode;
+
+use crate::launch::LaunchOptions;
+use crate::settings::{Settings, FPS_CAP_UNLIMITED};
+
+pub struct FrameLimiter;
+
+impl Plugin for FrameLimiter {
+    fn build(&self, app: &mut App) {
+        let bench = app.world.get_resource::<LaunchOptions>().map_or(false, |options| options.bench.is_some());
+        app.add_system(apply_vsync);
+        if !bench && cfg!(not(target_arch = "wasm32")) {
+            app.add_system_to_stage(CoreStage::Last, pace_frames);
+        }
+    }
+}
+
+fn apply_vsync(settings: Res<Settings>, mut windows: ResMut<Windows>) {
+    if !settings.is_changed() {
+        return;
+    }
+    let present_mode = if settings.vsync { PresentMode::Fifo } else { PresentMode::Immediate };
+    if let Some(window) = windows.get_primary_mut() {
+        if window.present_mode() != present_mode {
+            window.set_present_mode(present_mode);
+        }
+    }
+}
+
+// Sleeping is only as precise as the OS makes it, which is close enough to keep frames even.
+fn pace_frames(settings: Res<Settings>, mut last_frame: Local<Option<Instant>>) {
+    if settings.fps_cap != FPS_CAP_UNLIMITED {
+        let frame_time = Duration::from_secs_f64(1.0 / settings.fps_cap as f64);
+        if let Some(elapsed) = last_frame.map(|last_frame| last_frame.elapsed()) {
+            if elapsed < frame_time {
+                thread::sleep(frame_time - elapsed);
+            }
+        }
+    }
+    *last_frame = Some(Instant::now());
+}
@@ -0,0 +1,248 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::GameConfig;
+use crate::input::Handling;
+
+// Which rules a run is played by. Picked with --mode at launch or on the mode select screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum GameMode {
+    Classic,
+    // Waves keep coming on a timer whether or not the last one was cleared.
+    Survival,
+    // Score as much as possible before the clock runs out, see time_limit.rs.
+    TimeAttack,
+    // Two ships shoot it out over best-of rounds, see duel.rs.
+    Duel,
+    // One life, no shields and faster meteors.
+    Hardcore,
+    // Slow meteors and calm music, and ships respawn forever so the game never ends.
+    Zen,
+    // Bullets bounce off the edges, and only hits after a bounce score.
+    Ricochet,
+    // One or two players protect a station in the middle of the arena, see station.rs.
+    Defense,
+    // Fly through a course of rings against the clock and a ghost of the best run, see race.rs.
+    Race,
+    // Rules put together on the custom game screen, see custom_game.rs.
+    Custom(CustomRules),
+}
+
+impl Default for GameMode {
+    fn default() -> Self {
+        GameMode::Classic
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum HandlingModel {
+    // However the player has set turn speed and thrust in the settings.
+    Settings,
+    Nimble,
+    Heavy,
+}
+
+impl HandlingModel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            HandlingModel::Settings => "Your Settings",
+            HandlingModel::Nimble => "Nimble",
+            HandlingModel::Heavy => "Heavy",
+        }
+    }
+
+    fn handling(&self) -> Option<Handling> {
+        match self {
+            HandlingModel::Settings => None,
+            HandlingModel::Nimble => Some(Handling { rotation: 1.5, thrust: 1.5 }),
+            HandlingModel::Heavy => Some(Handling { rotation: 0.7, thrust: 0.6 }),
+        }
+    }
+}
+
+// Whole numbers only, so a custom mode can be compared and ordered like any other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustomRules {
+    pub lives: u8,
+    // Meteors in the first wave, each wave after brings one more.
+    pub meteors: u8,
+    // How fast meteors fly, as a percentage of the usual speed.
+    pub meteor_speed: u8,
+    pub handling: HandlingModel,
+}
+
+impl Default for CustomRules {
+    fn default() -> Self {
+        CustomRules { lives: 3, meteors: 1, meteor_speed: 100, handling: HandlingModel::Settings }
+    }
+}
+
+// Which leaderboard a mode's scores go on, if any.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ScoreBoard {
+    // The one board most modes share.
+    Shared,
+    Own,
+    Unranked,
+}
+
+// What a mode changes about a run's rules, so the systems involved ask the mode's rules rather than
+// checking for particular modes.
+pub struct ModeRules {
+    // Lives each player starts with, or None for config.ron's initial_lives.
+    pub lives: Option<u8>,
+    // Whether losing every life ends the game. Without it ships respawn without costing a life.
+    pub game_over: bool,
+    // Whether scoring config.ron's extra_life_score earns a life, see scoring.rs.
+    pub extra_lives: bool,
+    // Whether shield pickups drop.
+    pub shields: bool,
+    // How long the run lasts in seconds, None for as long as the lives do.
+    pub time_limit: Option<f32>,
+    // Multiplier on how fast each wave's meteors fly.
+    pub meteor_speed: f32,
+    // Meteors in the first wave, each wave after brings one more.
+    pub first_wave: u32,
+    // Whether meteors come in waves. Modes without them spawn their own, see duel.rs and race.rs.
+    pub waves: bool,
+    // How many times a bullet bounces off the arena's edges before leaving it is the end of it.
+    pub bullet_bounces: u8,
+    // Hits only score after the bullet has bounced, a point for each bounce.
+    pub trick_shots: bool,
+    // A station in the middle of the arena that waves are aimed at and that has to survive.
+    pub station: bool,
+    // A course of checkpoint rings to fly through, finishing it ends the run.
+    pub checkpoints: bool,
+    pub leaderboard: ScoreBoard,
+    // The menu's calm music carries on in game rather than the gameplay music and its stems.
+    pub ambient_music: bool,
+    // How ships turn and thrust, None for however the player has it in the settings.
+    pub handling: Option<Handling>,
+}
+
+const STANDARD_RULES: ModeRules = ModeRules {
+    lives: None,
+    game_over: true,
+    extra_lives: true,
+    shields: true,
+    time_limit: None,
+    meteor_speed: 1.0,
+    first_wave: 1,
+    waves: true,
+    bullet_bounces: 0,
+    trick_shots: false,
+    station: false,
+    checkpoints: false,
+    leaderboard: ScoreBoard::Shared,
+    ambient_music: false,
+    handling: None,
+};
+
+impl GameMode {
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        match name.to_lowercase().as_str() {
+            "classic" => Some(GameMode::Classic),
+            "survival" => Some(GameMode::Survival),
+            "time-attack" => Some(GameMode::TimeAttack),
+            "duel" => Some(GameMode::Duel),
+            "hardcore" => Some(GameMode::Hardcore),
+            "zen" => Some(GameMode::Zen),
+            "ricochet" => Some(GameMode::Ricochet),
+            "defense" => Some(GameMode::Defense),
+            "race" => Some(GameMode::Race),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "Classic",
+            GameMode::Survival => "Survival",
+            GameMode::TimeAttack => "Time Attack",
+            GameMode::Duel => "Duel",
+            GameMode::Hardcore => "Hardcore",
+            GameMode::Zen => "Zen",
+            GameMode::Ricochet => "Ricochet",
+            GameMode::Defense => "Station Defense",
+            GameMode::Race => "Checkpoint Race",
+            GameMode::Custom(_) => "Custom",
+        }
+    }
+
+    // A line for the mode select screen.
+    pub fn description(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "Clear wave after wave",
+            GameMode::Survival => "Waves keep coming, cleared or not",
+            GameMode::TimeAttack => "Score all you can in two minutes",
+            GameMode::Duel => "Two ships shoot it out",
+            GameMode::Hardcore => "One life, no shields, faster meteors",
+            GameMode::Zen => "Slow meteors and no game over",
+            GameMode::Ricochet => "Only shots off the edges score",
+            GameMode::Defense => "Keep the meteors off the station",
+            GameMode::Race => "Fly the rings against your best time",
+            GameMode::Custom(_) => "Your own rules",
+        }
+    }
+
+    pub fn rules(&self) -> ModeRules {
+        match self {
+            GameMode::Classic | GameMode::Survival => STANDARD_RULES,
+            GameMode::TimeAttack => ModeRules {
+                time_limit: Some(120.0),
+                leaderboard: ScoreBoard::Own,
+                ..STANDARD_RULES
+            },
+            // Duels are scored in rounds won, which don't belong on the leaderboard.
+            GameMode::Duel => ModeRules {
+                waves: false,
+                extra_lives: false,
+                leaderboard: ScoreBoard::Unranked,
+                ..STANDARD_RULES
+            },
+            GameMode::Hardcore => ModeRules {
+                lives: Some(1),
+                extra_lives: false,
+                shields: false,
+                meteor_speed: 1.5,
+                leaderboard: ScoreBoard::Own,
+                ..STANDARD_RULES
+            },
+            GameMode::Zen => ModeRules {
+                game_over: false,
+                extra_lives: false,
+                meteor_speed: 0.6,
+                leaderboard: ScoreBoard::Unranked,
+                ambient_music: true,
+                ..STANDARD_RULES
+            },
+            GameMode::Ricochet => ModeRules {
+                bullet_bounces: 3,
+                trick_shots: true,
+                leaderboard: ScoreBoard::Own,
+                ..STANDARD_RULES
+            },
+            GameMode::Defense => ModeRules { station: true, leaderboard: ScoreBoard::Own, ..STANDARD_RULES },
+            // Races are timed rather than scored, the ghost keeps the best time.
+            GameMode::Race => ModeRules {
+                waves: false,
+                checkpoints: true,
+                leaderboard: ScoreBoard::Unranked,
+                ..STANDARD_RULES
+            },
+            // Anything goes, so there's no fair board to put the score on.
+            GameMode::Custom(custom) => ModeRules {
+                lives: Some(custom.lives),
+                meteor_speed: custom.meteor_speed as f32 / 100.0,
+                first_wave: custom.meteors as u32,
+                leaderboard: ScoreBoard::Unranked,
+                handling: custom.handling.handling(),
+                ..STANDARD_RULES
+            },
+        }
+    }
+
+    pub fn initial_lives(&self, config: &GameConfig) -> u8 {
+        self.rules().lives.unwrap_or(config.initial_lives)
+    }
+}
@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+
+use crate::game_mode::GameMode;
+use crate::launch::LaunchOptions;
+use crate::leaderboard::HighScores;
+use crate::menu::{spawn_menu_button, spawn_menu_root, spawn_menu_title, MENU_TEXT};
+use crate::race::Race;
+use crate::{AppState, Game, RunStats, StartGameEvent};
+
+#[derive(Component, Clone, Copy)]
+enum GameOverButton {
+    Restart,
+    MainMenu,
+    Lobby,
+}
+
+#[derive(Component)]
+struct GameOverScreenRoot;
+
+pub struct GameOverScreen;
+
+impl Plugin for GameOverScreen {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(setup_game_over))
+            .add_system_set(SystemSet::on_update(AppState::GameOver).with_system(game_over_button_action))
+            .add_system_set(SystemSet::on_exit(AppState::GameOver).with_system(despawn_game_over));
+    }
+}
+
+fn setup_game_over(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query_game: Query<&Game>,
+    high_scores: Res<HighScores>,
+    mode: Res<GameMode>,
+    run_stats: Res<RunStats>,
+    race: Res<Race>,
+    options: Option<Res<LaunchOptions>>,
+) {
+    let game = query_game.single();
+    let text_style = TextStyle {
+        font: asset_server.load("BungeeSpice-Regular.ttf"),
+        font_size: 30.0,
+        color: MENU_TEXT,
+    };
+    let mut lines = vec![
+        format!("Final Score: {}", game.score),
+        format!("Best Score: {}", high_scores.best(*mode).max(game.score)),
+        format!("Waves Cleared: {}", run_stats.waves_cleared),
+        format!("Accuracy: {:.0}%", run_stats.accuracy() * 100.0),
+    ];
+    if mode.rules().checkpoints {
+        lines.extend(race.summary());
+    }
+    spawn_menu_root(&mut commands)
+        .insert(GameOverScreenRoot)
+        .with_children(|parent| {
+            spawn_menu_title(parent, &asset_server, "Game Over");
+            for line in lines {
+                parent.spawn_bundle(TextBundle::from_section(line, text_style.clone()));
+            }
+            // Online matches end with their session, so the next one is set up from the lobby.
+            if options.map_or(false, |options| options.online()) {
+                spawn_menu_button(parent, &asset_server, "Lobby").insert(GameOverButton::Lobby);
+            } else {
+                spawn_menu_button(parent, &asset_server, "Restart").insert(GameOverButton::Restart);
+                spawn_menu_button(parent, &asset_server, "Main Menu").insert(GameOverButton::MainMenu);
+            }
+        });
+}
+
+fn game_over_button_action(
+    query: Query<(&Interaction, &GameOverButton), Changed<Interaction>>,
+    query_game: Query<&Game>,
+    mut state: ResMut<State<AppState>>,
+    mut start_game_event: EventWriter<StartGameEvent>,
+) {
+    for (interaction, button) in query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let result = match button {
+            GameOverButton::Restart => {
+                start_game_event.send(StartGameEvent { players: query_game.single().players });
+                state.set(AppState::InGame)
+            }
+            GameOverButton::MainMenu => state.set(AppState::MainMenu),
+            GameOverButton::Lobby => state.set(AppState::Lobby),
+        };
+        if let Err(err) = result {
+            warn!("Game over transition failed: {:?}", err);
+        }
+    }
+}
+
+fn despawn_game_over(mut commands: Commands, query: Query<Entity, With<GameOverScreenRoot>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
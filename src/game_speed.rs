@@ -0,0 +1,42 @@
+// The game speed setting slows the whole simulation down for players who find the default pace too
+// fast. Like replay playback it stretches Time straight after it's been updated, so physics, timers
+// and everything else going by the frame's length slow down together. It only applies in game, so
+// menus and the pause screen run at full speed. Turning and thrust are per frame rather than per
+// second and stay as quick as they were, which only helps. Replays record the slowed frames and play
+// back at full speed, and online and seeded games always run at full speed. Ordered with the other
+// systems that change Time in main.
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+use crate::deterministic::Deterministic;
+use crate::replay::Playback;
+use crate::settings::Settings;
+use crate::AppState;
+
+// Time's own idea of the frame's length is off after it's been stretched, so the real one is kept here.
+// Online games are always deterministic, so they're left alone along with the seeded ones.
+pub fn slow_time(
+    state: Res<State<AppState>>,
+    settings: Res<Settings>,
+    deterministic: Option<Res<Deterministic>>,
+    playback: Option<Res<Playback>>,
+    mut time: ResMut<Time>,
+    mut last_frame: Local<Option<Instant>>,
+) {
+    let now = match time.last_update() {
+        Some(now) => now,
+        None => return,
+    };
+    let real_delta = last_frame.map(|last_frame| now.duration_since(last_frame));
+    *last_frame = Some(now);
+    if *state.current() != AppState::InGame
+        || settings.game_speed >= 1.0
+        || deterministic.is_some()
+        || playback.is_some()
+    {
+        return;
+    }
+    if let Some(real_delta) = real_delta {
+        time.update_with_instant(now + real_delta.mul_f32(settings.game_speed));
+    }
+}
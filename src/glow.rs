@@ -0,0 +1,144 @@
+// Bevy 0.8 has no HDR camera or bloom pass, so bright things get a soft halo sprite drawn
+// behind them instead. It's cheap enough for most machines and can be turned off in settings.
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+use crate::palette::Tint;
+use crate::settings::Settings;
+use crate::{Bullet, Lifetime, MeteorDestroyedEvent, ShipDestroyedEvent};
+
+const GLOW_TEXTURE_SIZE: u32 = 64;
+const GLOW_Z: f32 = 4.0;
+const BULLET_GLOW_SIZE: f32 = 20.0;
+const BULLET_GLOW_ALPHA: f32 = 0.6;
+const CORE_COLOUR: Color = Color::rgb(1.0, 0.8, 0.5);
+const CORE_DURATION: f32 = 0.3;
+const CORE_SIZE_PER_METEOR_SIZE: f32 = 12.0;
+const SHIP_CORE_SIZE: f32 = 160.0;
+
+struct GlowTexture(Handle<Image>);
+
+// Marks every halo so they can all be hidden when bloom is switched off.
+#[derive(Component)]
+struct Glow;
+
+// The bright flash in the middle of an explosion, which grows and fades over its lifetime.
+#[derive(Component)]
+struct ExplosionCore {
+    size: f32,
+}
+
+pub struct Bloom;
+
+impl Plugin for Bloom {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(create_glow_texture)
+            .add_system(add_bullet_glow)
+            .add_system(spawn_explosion_cores)
+            .add_system(update_explosion_cores)
+            .add_system(apply_bloom_setting);
+    }
+}
+
+// A white disc that fades out towards its edge, tinted per sprite.
+fn create_glow_texture(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let size = GLOW_TEXTURE_SIZE;
+    let centre = (size as f32 - 1.0) / 2.0;
+    let mut data = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let distance = Vec2::new(x as f32 - centre, y as f32 - centre).length() / centre;
+            let alpha = (1.0 - distance).max(0.0).powi(2);
+            data.extend_from_slice(&[255, 255, 255, (alpha * 255.0) as u8]);
+        }
+    }
+    let image = Image::new(
+        Extent3d { width: size, height: size, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    commands.insert_resource(GlowTexture(images.add(image)));
+}
+
+fn glow_visibility(settings: &Settings) -> Visibility {
+    Visibility { is_visible: settings.bloom }
+}
+
+fn add_bullet_glow(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    texture: Res<GlowTexture>,
+    query: Query<Entity, Added<Bullet>>,
+) {
+    for bullet in query.iter() {
+        commands.entity(bullet).with_children(|parent| {
+            parent
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgba(1.0, 1.0, 1.0, BULLET_GLOW_ALPHA),
+                        custom_size: Some(Vec2::splat(BULLET_GLOW_SIZE)),
+                        ..default()
+                    },
+                    texture: texture.0.clone(),
+                    transform: Transform::from_xyz(0.0, 0.0, -0.1),
+                    visibility: glow_visibility(&settings),
+                    ..default()
+                })
+                .insert(Glow)
+                .insert(Tint::Bullet);
+        });
+    }
+}
+
+fn spawn_explosion_cores(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    texture: Res<GlowTexture>,
+    mut meteor_event: EventReader<MeteorDestroyedEvent>,
+    mut ship_event: EventReader<ShipDestroyedEvent>,
+) {
+    let explosions = meteor_event
+        .iter()
+        .map(|ev| (ev.position, ev.size as f32 * CORE_SIZE_PER_METEOR_SIZE))
+        .chain(ship_event.iter().map(|ev| (ev.position, SHIP_CORE_SIZE)));
+    // The flash of an explosion is left out with reduced motion.
+    if settings.reduced_motion {
+        return;
+    }
+    for (position, size) in explosions {
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: CORE_COLOUR,
+                    custom_size: Some(Vec2::splat(size)),
+                    ..default()
+                },
+                texture: texture.0.clone(),
+                transform: Transform::from_translation(position.extend(GLOW_Z)),
+                visibility: glow_visibility(&settings),
+                ..default()
+            })
+            .insert(Glow)
+            .insert(ExplosionCore { size })
+            .insert(Lifetime(Timer::from_seconds(CORE_DURATION, false)));
+    }
+}
+
+fn update_explosion_cores(mut query: Query<(&ExplosionCore, &Lifetime, &mut Sprite)>) {
+    for (core, lifetime, mut sprite) in query.iter_mut() {
+        let progress = lifetime.0.percent();
+        sprite.custom_size = Some(Vec2::splat(core.size * (0.5 + progress)));
+        sprite.color.set_a(1.0 - progress);
+    }
+}
+
+fn apply_bloom_setting(settings: Res<Settings>, mut query: Query<&mut Visibility, With<Glow>>) {
+    if !settings.is_changed() {
+        return;
+    }
+    for mut visibility in query.iter_mut() {
+        *visibility = glow_visibility(&settings);
+    }
+}
@@ -0,0 +1,128 @@
+// Per-profile choices for how much of the HUD is shown, so a player who finds it distracting can
+// dim or hide parts of it, and swap the score and lives between their corners. Kept in the
+// profile rather than the settings as it's a matter of taste rather than of the machine.
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::profile::Profile;
+
+// A dimmed element is drawn at this fraction of its usual opacity.
+const DIMMED_OPACITY: f32 = 0.35;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HudVisibility {
+    Shown,
+    Dimmed,
+    Hidden,
+}
+
+impl Default for HudVisibility {
+    fn default() -> Self {
+        HudVisibility::Shown
+    }
+}
+
+pub const HUD_VISIBILITIES: [HudVisibility; 3] = [HudVisibility::Shown, HudVisibility::Dimmed, HudVisibility::Hidden];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HudPart {
+    Score,
+    Lives,
+    Radar,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HudLayout {
+    pub score: HudVisibility,
+    pub lives: HudVisibility,
+    pub radar: HudVisibility,
+    // Lives on the left and score on the right instead of the other way round.
+    pub swap_corners: bool,
+}
+
+impl HudLayout {
+    pub fn visibility(&self, part: HudPart) -> HudVisibility {
+        match part {
+            HudPart::Score => self.score,
+            HudPart::Lives => self.lives,
+            HudPart::Radar => self.radar,
+        }
+    }
+
+    pub fn visibility_mut(&mut self, part: HudPart) -> &mut HudVisibility {
+        match part {
+            HudPart::Score => &mut self.score,
+            HudPart::Lives => &mut self.lives,
+            HudPart::Radar => &mut self.radar,
+        }
+    }
+}
+
+// Marks a node as part of the HUD, with the opacity it has when it's shown in full.
+#[derive(Component)]
+pub struct HudElement {
+    pub part: HudPart,
+    pub opacity: f32,
+}
+
+// The row holding the score and lives, which is reversed to swap them over.
+#[derive(Component)]
+pub struct HudCorners;
+
+pub struct HudOptions;
+
+impl Plugin for HudOptions {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(apply_hud_visibility)
+            .add_system(apply_hud_corners);
+    }
+}
+
+// Only the alpha is touched, so the colours stay whatever the palette made them.
+fn apply_hud_visibility(
+    profile: Res<Profile>,
+    mut query: Query<(
+        &HudElement,
+        ChangeTrackers<HudElement>,
+        &mut Style,
+        Option<&mut Text>,
+        Option<&mut UiColor>,
+    )>,
+) {
+    for (element, tracker, mut style, text, colour) in query.iter_mut() {
+        if !profile.is_changed() && !tracker.is_added() {
+            continue;
+        }
+        let visibility = profile.hud.visibility(element.part);
+        let display = if visibility == HudVisibility::Hidden { Display::None } else { Display::Flex };
+        if style.display != display {
+            style.display = display;
+        }
+        let opacity = match visibility {
+            HudVisibility::Dimmed => element.opacity * DIMMED_OPACITY,
+            _ => element.opacity,
+        };
+        if let Some(mut text) = text {
+            for section in text.sections.iter_mut() {
+                section.style.color.set_a(opacity);
+            }
+        }
+        if let Some(mut colour) = colour {
+            colour.0.set_a(opacity);
+        }
+    }
+}
+
+fn apply_hud_corners(profile: Res<Profile>, mut query: Query<&mut Style, With<HudCorners>>) {
+    if !profile.is_changed() {
+        return;
+    }
+    let direction = if profile.hud.swap_corners { FlexDirection::RowReverse } else { FlexDirection::Row };
+    for mut style in query.iter_mut() {
+        if style.flex_direction != direction {
+            style.flex_direction = direction;
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::launch::LaunchOptions;
+use crate::settings::Settings;
+
+// How far a stick has to be pushed to count as a key held down.
+const STICK_THRESHOLD: f32 = 0.5;
+
+#[derive(Component, Clone, Copy)]
+pub struct ControlScheme {
+    pub thrust: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub fire: KeyCode,
+}
+
+// How quickly ships turn and how hard they thrust, as multipliers on config.ron's rotation_rate and
+// thrust_impulse. Picked when a game starts, see replay.rs.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Handling {
+    pub rotation: f32,
+    pub thrust: f32,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Handling { rotation: 1.0, thrust: 1.0 }
+    }
+}
+
+pub const ARROWS: ControlScheme = ControlScheme {
+    thrust: KeyCode::Up,
+    left: KeyCode::Left,
+    right: KeyCode::Right,
+    fire: KeyCode::Space,
+};
+
+pub const WASD: ControlScheme = ControlScheme {
+    thrust: KeyCode::W,
+    left: KeyCode::A,
+    right: KeyCode::D,
+    fire: KeyCode::LControl,
+};
+
+const WASD_J: ControlScheme = ControlScheme {
+    thrust: KeyCode::W,
+    left: KeyCode::A,
+    right: KeyCode::D,
+    fire: KeyCode::J,
+};
+
+// WASD + J the other way round, steering with the right hand and firing with the left.
+const LEFT_HANDED: ControlScheme = ControlScheme {
+    thrust: KeyCode::I,
+    left: KeyCode::J,
+    right: KeyCode::L,
+    fire: KeyCode::F,
+};
+
+// Player one's layout, picked in the settings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControlPreset {
+    Arrows,
+    WasdJ,
+    LeftHanded,
+    // Any connected gamepad presses the arrow layout's keys, see press_gamepad_keys.
+    Gamepad,
+}
+
+pub const CONTROL_PRESETS: [ControlPreset; 4] =
+    [ControlPreset::Arrows, ControlPreset::WasdJ, ControlPreset::LeftHanded, ControlPreset::Gamepad];
+
+impl ControlPreset {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ControlPreset::Arrows => "Arrows + Space",
+            ControlPreset::WasdJ => "WASD + J",
+            ControlPreset::LeftHanded => "Left-Handed",
+            ControlPreset::Gamepad => "Gamepad",
+        }
+    }
+
+    // Player two keeps WASD unless player one has taken it, then gets the arrows instead.
+    fn schemes(&self) -> [ControlScheme; 2] {
+        match self {
+            ControlPreset::Arrows | ControlPreset::Gamepad => [ARROWS, WASD],
+            ControlPreset::WasdJ => [WASD_J, ARROWS],
+            ControlPreset::LeftHanded => [LEFT_HANDED, WASD],
+        }
+    }
+}
+
+// The layouts ships are spawned with and replays are recorded and played back with. Online games
+// and the headless server always use the defaults from ControlScheme::for_player, as every peer
+// has to press the same keys for the same controls.
+pub struct ControlSchemes([ControlScheme; 2]);
+
+impl Default for ControlSchemes {
+    fn default() -> Self {
+        ControlSchemes([ControlScheme::for_player(0), ControlScheme::for_player(1)])
+    }
+}
+
+impl ControlSchemes {
+    pub fn for_player(&self, player: u8) -> ControlScheme {
+        self.0[(player as usize).min(1)]
+    }
+}
+
+pub struct ControlPresets;
+
+impl Plugin for ControlPresets {
+    fn build(&self, app: &mut App) {
+        if app.world.get_resource::<LaunchOptions>().map_or(false, |options| options.online()) {
+            return;
+        }
+        app
+            .add_system(apply_control_preset)
+            .add_system_to_stage(CoreStage::PreUpdate, press_gamepad_keys.after(InputSystem));
+    }
+}
+
+// Held controls are packed into these bits when they're stored or sent, by replays and netplay, and when
+// the attract mode's pilot picks them.
+pub const THRUST: u8 = 1;
+pub const LEFT: u8 = 1 << 1;
+pub const RIGHT: u8 = 1 << 2;
+pub const FIRE: u8 = 1 << 3;
+
+impl ControlScheme {
+    pub fn for_player(player: u8) -> ControlScheme {
+        match player {
+            0 => ARROWS,
+            _ => WASD,
+        }
+    }
+
+    pub fn bits(&self) -> [(KeyCode, u8); 4] {
+        [(self.thrust, THRUST), (self.left, LEFT), (self.right, RIGHT), (self.fire, FIRE)]
+    }
+
+    pub fn held(&self, keyboard_input: &Input<KeyCode>) -> u8 {
+        self.bits()
+            .iter()
+            .filter(|(key, _)| keyboard_input.pressed(*key))
+            .fold(0, |held, (_, bit)| held | bit)
+    }
+
+    // Presses and releases the scheme's keys so they look held exactly as in `held`.
+    pub fn press(&self, keyboard_input: &mut Input<KeyCode>, held: u8) {
+        for (key, bit) in self.bits() {
+            if held & bit != 0 {
+                keyboard_input.press(key);
+            } else {
+                keyboard_input.release(key);
+            }
+        }
+    }
+}
+
+fn apply_control_preset(settings: Res<Settings>, mut schemes: ResMut<ControlSchemes>) {
+    if settings.is_changed() {
+        schemes.0 = settings.control_preset.schemes();
+    }
+}
+
+// Like touch.rs, keys are only released here if a gamepad pressed them.
+fn press_gamepad_keys(
+    settings: Res<Settings>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut held: Local<Vec<KeyCode>>,
+) {
+    let mut pressed = Vec::new();
+    if settings.control_preset == ControlPreset::Gamepad {
+        let scheme = ARROWS;
+        for gamepad in gamepads.iter() {
+            let button = |button_type| gamepad_input.pressed(GamepadButton(*gamepad, button_type));
+            let stick_x = axes.get(GamepadAxis(*gamepad, GamepadAxisType::LeftStickX)).unwrap_or(0.0);
+            let controls = [
+                (scheme.thrust, button(GamepadButtonType::RightTrigger2) || button(GamepadButtonType::DPadUp)),
+                (scheme.left, button(GamepadButtonType::DPadLeft) || stick_x < -STICK_THRESHOLD),
+                (scheme.right, button(GamepadButtonType::DPadRight) || stick_x > STICK_THRESHOLD),
+                (scheme.fire, button(GamepadButtonType::South)),
+            ];
+            for (key, down) in controls {
+                if down && !pressed.contains(&key) {
+                    pressed.push(key);
+                }
+            }
+        }
+    }
+    for key in held.iter().filter(|key| !pressed.contains(key)) {
+        keyboard_input.release(*key);
+    }
+    for key in pressed.iter() {
+        keyboard_input.press(*key);
+    }
+    *held = pressed;
+}
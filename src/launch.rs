@@ -0,0 +1,327 @@
+// Options given on the command line, e.g. `asteroids --seed 42 --windowed 800x600 --mute`.
+// They're parsed before the app exists and inserted as a resource for the plugins to read.
+use std::env;
+use std::net::SocketAddr;
+
+use bevy::app::ScheduleRunnerPlugin;
+use bevy::prelude::*;
+use bevy::window::{WindowMode, WindowPlugin};
+
+use crate::game_mode::GameMode;
+
+const USAGE: &str = "Usage: asteroids [--seed N] [--fullscreen | --windowed WxH] \
+                     [--mode classic|survival|time-attack|duel|hardcore|zen|ricochet|defense|race] \
+                     [--mute] [--headless] \
+                     [--netplay LOCAL_PORT REMOTE_ADDRESS PLAYER [--spectator ADDRESS] | --lobby \
+                     | --spectate LOCAL_PORT HOST_ADDRESS] [--twitch CHANNEL] [--script FILE] \
+                     [--bench METEORS BULLETS]";
+
+// An online game against one other copy of the game, see netplay.rs.
+#[derive(Clone, Copy)]
+pub struct NetplayOptions {
+    pub local_port: u16,
+    pub remote: SocketAddr,
+    // Which of the two players is on this machine, 0 or 1.
+    pub local_player: usize,
+    // Someone watching with --spectate, this machine sends them both players' inputs.
+    pub spectator: Option<SocketAddr>,
+}
+
+// Watching an online game, with the inputs sent by the player the spectator was given to.
+#[derive(Clone, Copy)]
+pub struct SpectateOptions {
+    pub local_port: u16,
+    pub host: SocketAddr,
+}
+
+// How crowded the --bench stress test makes the game, see bench.rs.
+#[derive(Clone, Copy)]
+pub struct BenchOptions {
+    pub meteors: u32,
+    pub bullets: u32,
+}
+
+#[derive(Clone, Default)]
+pub struct LaunchOptions {
+    // Runs the simulation deterministically from this seed, see deterministic.rs.
+    pub seed: Option<u64>,
+    pub fullscreen: bool,
+    pub window_size: Option<Vec2>,
+    pub mode: GameMode,
+    pub mute: bool,
+    // No window, the game loop just runs as fast as it can, for scripted runs and tests.
+    pub headless: bool,
+    pub netplay: Option<NetplayOptions>,
+    // Starts in the online lobby to pick who to play instead of the main menu.
+    pub lobby: bool,
+    pub spectate: Option<SpectateOptions>,
+    // The Twitch channel whose chat can send things into the game, see twitch.rs.
+    pub twitch: Option<String>,
+    // A script in assets/scripts that changes the rules of the game, see scripting.rs.
+    pub script: Option<String>,
+    pub bench: Option<BenchOptions>,
+}
+
+impl LaunchOptions {
+    pub fn from_args() -> LaunchOptions {
+        LaunchOptions::parse(env::args().skip(1))
+    }
+
+    // Logging isn't set up yet, so problems go straight to stderr. Bad arguments are skipped
+    // rather than refusing to start.
+    fn parse(mut args: impl Iterator<Item = String>) -> LaunchOptions {
+        let mut options = LaunchOptions::default();
+        let mut spectator = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => match args.next().and_then(|seed| seed.parse().ok()) {
+                    Some(seed) => options.seed = Some(seed),
+                    None => eprintln!("--seed needs a number"),
+                },
+                "--fullscreen" => {
+                    options.fullscreen = true;
+                    options.window_size = None;
+                }
+                "--windowed" => match args.next().as_deref().and_then(parse_window_size) {
+                    Some(size) => {
+                        options.window_size = Some(size);
+                        options.fullscreen = false;
+                    }
+                    None => eprintln!("--windowed needs a size like 1280x720"),
+                },
+                "--mode" => match args.next().as_deref().and_then(GameMode::from_name) {
+                    Some(mode) => options.mode = mode,
+                    None => eprintln!(
+                        "--mode needs one of classic, survival, time-attack, duel, hardcore, zen, ricochet, \
+                         defense or race"
+                    ),
+                },
+                "--mute" => options.mute = true,
+                "--headless" => options.headless = true,
+                "--netplay" => match parse_netplay(args.next(), args.next(), args.next()) {
+                    Some(netplay) if cfg!(feature = "netplay") => options.netplay = Some(netplay),
+                    Some(_) => eprintln!("--netplay needs the game built with the netplay feature"),
+                    None => eprintln!("--netplay needs a local port, the other player's address and 1 or 2"),
+                },
+                "--lobby" if cfg!(feature = "netplay") => options.lobby = true,
+                "--lobby" => eprintln!("--lobby needs the game built with the netplay feature"),
+                "--spectator" => match args.next().and_then(|address| address.parse().ok()) {
+                    Some(address) => spectator = Some(address),
+                    None => eprintln!("--spectator needs the spectator's address"),
+                },
+                "--spectate" => match parse_spectate(args.next(), args.next()) {
+                    Some(spectate) if cfg!(feature = "netplay") => options.spectate = Some(spectate),
+                    Some(_) => eprintln!("--spectate needs the game built with the netplay feature"),
+                    None => eprintln!("--spectate needs a local port and the address of the player hosting"),
+                },
+                "--twitch" => match args.next() {
+                    Some(channel) if cfg!(feature = "twitch") => options.twitch = Some(channel.to_lowercase()),
+                    Some(_) => eprintln!("--twitch needs the game built with the twitch feature"),
+                    None => eprintln!("--twitch needs a channel name"),
+                },
+                "--script" => match args.next() {
+                    Some(script) if cfg!(feature = "scripting") => options.script = Some(script),
+                    Some(_) => eprintln!("--script needs the game built with the scripting feature"),
+                    None => eprintln!("--script needs the name of a file in assets/scripts"),
+                },
+                "--bench" => match parse_bench(args.next(), args.next()) {
+                    Some(bench) => options.bench = Some(bench),
+                    None => eprintln!("--bench needs how many meteors and how many bullets to spawn"),
+                },
+                _ => eprintln!("Ignoring unknown argument {}\n{}", arg, USAGE),
+            }
+        }
+        match (options.netplay.as_mut(), spectator) {
+            (Some(netplay), Some(_)) => netplay.spectator = spectator,
+            (None, Some(_)) => eprintln!("--spectator only works with --netplay"),
+            _ => {}
+        }
+        options
+    }
+
+    // Online games step the game in the rollback schedule, see netplay.rs.
+    pub fn online(&self) -> bool {
+        self.netplay.is_some() || self.lobby || self.spectate.is_some()
+    }
+
+    pub fn window_descriptor(&self) -> WindowDescriptor {
+        let mut descriptor = WindowDescriptor::default();
+        if self.fullscreen {
+            descriptor.mode = WindowMode::BorderlessFullscreen;
+        }
+        if let Some(size) = self.window_size {
+            descriptor.width = size.x;
+            descriptor.height = size.y;
+        }
+        // In the browser the game draws to the page's canvas and resizes with whatever holds it.
+        if cfg!(target_arch = "wasm32") {
+            descriptor.canvas = Some("#bevy".to_string());
+            descriptor.fit_canvas_to_parent = true;
+        }
+        descriptor
+    }
+}
+
+fn parse_window_size(size: &str) -> Option<Vec2> {
+    let (width, height) = size.split_once('x')?;
+    let size = Vec2::new(width.parse().ok()?, height.parse().ok()?);
+    (size.x > 0.0 && size.y > 0.0).then_some(size)
+}
+
+// Players are numbered from 1 on the command line.
+fn parse_netplay(port: Option<String>, remote: Option<String>, player: Option<String>) -> Option<NetplayOptions> {
+    let local_player = match player?.as_str() {
+        "1" => 0,
+        "2" => 1,
+        _ => return None,
+    };
+    Some(NetplayOptions {
+        local_port: port?.parse().ok()?,
+        remote: remote?.parse().ok()?,
+        local_player,
+        spectator: None,
+    })
+}
+
+fn parse_spectate(port: Option<String>, host: Option<String>) -> Option<SpectateOptions> {
+    Some(SpectateOptions {
+        local_port: port?.parse().ok()?,
+        host: host?.parse().ok()?,
+    })
+}
+
+fn parse_bench(meteors: Option<String>, bullets: Option<String>) -> Option<BenchOptions> {
+    Some(BenchOptions {
+        meteors: meteors?.parse().ok()?,
+        bullets: bullets?.parse().ok()?,
+    })
+}
+
+// Still provides the Windows resource, but with nothing in it the app mustn't quit over that.
+pub const HEADLESS_WINDOWS: WindowPlugin = WindowPlugin {
+    add_primary_window: false,
+    exit_on_all_closed: false,
+    close_when_requested: false,
+};
+
+// Without a window there's no winit event loop to drive the app, so this loops it instead.
+// Rendering needs the Windows resource, so this has to be added before DefaultPlugins.
+pub struct LaunchRunner;
+
+impl Plugin for LaunchRunner {
+    fn build(&self, app: &mut App) {
+        if app.world.get_resource::<LaunchOptions>().map_or(false, |options| options.headless) {
+            app.add_plugin(HEADLESS_WINDOWS).add_plugin(ScheduleRunnerPlugin);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> LaunchOptions {
+        LaunchOptions::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_leaves_the_defaults() {
+        let options = parse(&[]);
+        assert_eq!(options.seed, None);
+        assert!(!options.fullscreen);
+        assert_eq!(options.window_size, None);
+        assert_eq!(options.mode, GameMode::default());
+        assert!(!options.mute);
+        assert!(!options.headless);
+        assert!(!options.online());
+        assert!(options.bench.is_none());
+    }
+
+    #[test]
+    fn seed_takes_a_number() {
+        assert_eq!(parse(&["--seed", "42"]).seed, Some(42));
+        assert_eq!(parse(&["--seed", "forty-two"]).seed, None);
+        assert_eq!(parse(&["--seed"]).seed, None);
+    }
+
+    #[test]
+    fn the_last_of_fullscreen_and_windowed_wins() {
+        let options = parse(&["--fullscreen", "--windowed", "800x600"]);
+        assert!(!options.fullscreen);
+        assert_eq!(options.window_size, Some(Vec2::new(800.0, 600.0)));
+
+        let options = parse(&["--windowed", "800x600", "--fullscreen"]);
+        assert!(options.fullscreen);
+        assert_eq!(options.window_size, None);
+    }
+
+    #[test]
+    fn windowed_needs_a_size_above_zero() {
+        assert_eq!(parse(&["--windowed", "800"]).window_size, None);
+        assert_eq!(parse(&["--windowed", "0x600"]).window_size, None);
+        assert_eq!(parse(&["--windowed", "widexhigh"]).window_size, None);
+        assert_eq!(parse(&["--windowed"]).window_size, None);
+    }
+
+    #[test]
+    fn mode_takes_a_mode_name() {
+        assert_eq!(parse(&["--mode", "time-attack"]).mode, GameMode::TimeAttack);
+        assert_eq!(parse(&["--mode", "Race"]).mode, GameMode::Race);
+        assert_eq!(parse(&["--mode", "tetris"]).mode, GameMode::default());
+        assert_eq!(parse(&["--mode"]).mode, GameMode::default());
+    }
+
+    #[test]
+    fn switches_turn_themselves_on() {
+        let options = parse(&["--mute", "--headless"]);
+        assert!(options.mute);
+        assert!(options.headless);
+    }
+
+    #[test]
+    fn netplay_takes_a_port_an_address_and_a_player() {
+        let options = parse(&["--netplay", "7000", "127.0.0.1:7001", "2", "--spectator", "127.0.0.1:7002"]);
+        match options.netplay {
+            Some(netplay) => {
+                assert_eq!(netplay.local_port, 7000);
+                assert_eq!(netplay.remote, "127.0.0.1:7001".parse().unwrap());
+                assert_eq!(netplay.local_player, 1);
+                assert_eq!(netplay.spectator, Some("127.0.0.1:7002".parse().unwrap()));
+            }
+            None => assert!(!cfg!(feature = "netplay")),
+        }
+        assert!(parse(&["--netplay", "7000", "127.0.0.1:7001", "3"]).netplay.is_none());
+        assert!(parse(&["--netplay", "port", "127.0.0.1:7001", "1"]).netplay.is_none());
+        assert!(parse(&["--netplay", "7000"]).netplay.is_none());
+    }
+
+    #[test]
+    fn spectator_only_counts_with_netplay() {
+        let options = parse(&["--spectator", "127.0.0.1:7002"]);
+        assert!(options.netplay.is_none());
+        assert!(!options.online());
+    }
+
+    #[test]
+    fn spectate_takes_a_port_and_the_host() {
+        let options = parse(&["--spectate", "7002", "127.0.0.1:7000"]);
+        assert_eq!(options.spectate.is_some(), cfg!(feature = "netplay"));
+        assert!(parse(&["--spectate", "7002"]).spectate.is_none());
+    }
+
+    #[test]
+    fn bench_takes_two_counts() {
+        let bench = parse(&["--bench", "500", "200"]).bench.unwrap();
+        assert_eq!((bench.meteors, bench.bullets), (500, 200));
+        assert!(parse(&["--bench", "500"]).bench.is_none());
+        assert!(parse(&["--bench", "many", "200"]).bench.is_none());
+    }
+
+    #[test]
+    fn unknown_arguments_are_skipped() {
+        let options = parse(&["--turbo", "--seed", "7", "extra"]);
+        assert_eq!(options.seed, Some(7));
+        assert!(!options.fullscreen);
+        assert!(!options.headless);
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::attract::Demo;
+use crate::game_mode::{GameMode, ScoreBoard};
+use crate::menu::{spawn_menu_button, spawn_menu_root, spawn_menu_title, MenuButton, MENU_TEXT};
+use crate::palette::Tint;
+use crate::replay::Playback;
+use crate::{storage, AppState, Game};
+
+const MAX_HIGH_SCORES: usize = 10;
+pub const HIGH_SCORES_FILE: &str = "high_scores.ron";
+
+// Most modes share one board, the ones with their own rules get one each, see ModeRules. Modes
+// that aren't ranked, like duels, have no scores at all.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighScores {
+    pub scores: Vec<u32>,
+    pub boards: BTreeMap<GameMode, Vec<u32>>,
+}
+
+impl HighScores {
+    fn board(&self, mode: GameMode) -> &[u32] {
+        match mode.rules().leaderboard {
+            ScoreBoard::Shared => &self.scores,
+            ScoreBoard::Own => self.boards.get(&mode).map(Vec::as_slice).unwrap_or(&[]),
+            ScoreBoard::Unranked => &[],
+        }
+    }
+
+    pub fn add(&mut self, mode: GameMode, score: u32) {
+        let scores = match mode.rules().leaderboard {
+            ScoreBoard::Shared => &mut self.scores,
+            ScoreBoard::Own => self.boards.entry(mode).or_default(),
+            ScoreBoard::Unranked => return,
+        };
+        scores.push(score);
+        scores.sort_unstable_by(|a, b| b.cmp(a));
+        scores.truncate(MAX_HIGH_SCORES);
+    }
+
+    pub fn best(&self, mode: GameMode) -> u32 {
+        self.board(mode).first().copied().unwrap_or(0)
+    }
+}
+
+#[derive(Component)]
+struct LeaderboardScreen;
+
+pub struct Leaderboard;
+
+impl Plugin for Leaderboard {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(storage::load::<HighScores>(HIGH_SCORES_FILE).unwrap_or_default())
+            .add_system(save_high_scores)
+            .add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(record_high_score))
+            .add_system_set(SystemSet::on_enter(AppState::Leaderboard).with_system(setup_leaderboard))
+            .add_system_set(SystemSet::on_exit(AppState::Leaderboard).with_system(despawn_leaderboard));
+    }
+}
+
+fn record_high_score(
+    query_game: Query<&Game>,
+    mode: Res<GameMode>,
+    playback: Option<Res<Playback>>,
+    demo: Option<Res<Demo>>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    if playback.is_none() && demo.is_none() {
+        high_scores.add(*mode, query_game.single().score);
+    }
+}
+
+fn save_high_scores(high_scores: Res<HighScores>) {
+    if high_scores.is_changed() && !high_scores.is_added() {
+        storage::save(HIGH_SCORES_FILE, &*high_scores);
+    }
+}
+
+fn setup_leaderboard(mut commands: Commands, asset_server: Res<AssetServer>, high_scores: Res<HighScores>) {
+    let text_style = TextStyle {
+        font: asset_server.load("BungeeSpice-Regular.ttf"),
+        font_size: 30.0,
+        color: MENU_TEXT,
+    };
+    spawn_menu_root(&mut commands)
+        .insert(LeaderboardScreen)
+        .with_children(|parent| {
+            spawn_menu_title(parent, &asset_server, "Leaderboard");
+            parent
+                .spawn_bundle(NodeBundle { color: Color::NONE.into(), ..default() })
+                .with_children(|parent| {
+                    let heading = (!high_scores.boards.is_empty()).then(|| "Classic & Survival");
+                    spawn_board(parent, heading, &high_scores.scores, &text_style);
+                    for (mode, scores) in high_scores.boards.iter() {
+                        spawn_board(parent, Some(mode.label()), scores, &text_style);
+                    }
+                });
+            spawn_menu_button(parent, &asset_server, "Back").insert(MenuButton::Back);
+        });
+}
+
+// The shared board only gets a heading once there are others beside it.
+fn spawn_board(parent: &mut ChildBuilder, heading: Option<&str>, scores: &[u32], text_style: &TextStyle) {
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                margin: UiRect { left: Val::Px(30.0), right: Val::Px(30.0), ..default() },
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            if let Some(heading) = heading {
+                parent.spawn_bundle(TextBundle::from_section(heading, text_style.clone())).insert(Tint::Accent);
+            }
+            if scores.is_empty() {
+                parent.spawn_bundle(TextBundle::from_section("No scores yet", text_style.clone()));
+            }
+            for (rank, score) in scores.iter().enumerate() {
+                parent.spawn_bundle(TextBundle::from_section(
+                    format!("{}. {}", rank + 1, score),
+                    text_style.clone(),
+                ));
+            }
+        });
+}
+
+fn despawn_leaderboard(mut commands: Commands, query: Query<Entity, With<LeaderboardScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
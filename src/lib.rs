@@ -0,0 +1,1547 @@
+use std::sync::Mutex;
+
+use bevy::ecs::system::Command;
+use bevy::hierarchy::despawn_with_children_recursive;
+use bevy::render::settings::WgpuSettings;
+use bevy::{asset::AssetServerSettings, prelude::*, window::WindowPlugin, winit::WinitPlugin};
+use bevy_kira_audio::prelude::{AudioChannel, AudioControl};
+use bevy_rapier2d::prelude::*;
+
+mod animation;
+mod attract;
+mod audio;
+mod autosave;
+mod bench;
+#[doc(hidden)]
+pub mod bench_worlds;
+mod bullets;
+mod camera;
+#[cfg(not(target_arch = "wasm32"))]
+mod clips;
+mod config;
+mod crt;
+mod culling;
+mod custom_game;
+mod damage_flash;
+mod debug_overlay;
+#[cfg(feature = "debug-physics")]
+mod debug_physics;
+mod definitions;
+mod deterministic;
+#[cfg(feature = "dev-tools")]
+mod dev_tools;
+mod duel;
+mod fallback;
+mod frame_limiter;
+mod game_mode;
+mod game_over;
+mod game_speed;
+mod glow;
+mod hud;
+mod input;
+mod launch;
+mod leaderboard;
+mod lighting;
+#[cfg(feature = "netplay")]
+mod lobby;
+mod menu;
+mod meteor_pool;
+#[cfg(not(target_arch = "wasm32"))]
+mod mods;
+mod music;
+mod navigation;
+#[cfg(feature = "netplay")]
+mod netplay;
+mod palette;
+mod particles;
+mod pause;
+mod physics_preset;
+mod power_saving;
+mod powerups;
+mod preload;
+mod profile;
+mod race;
+mod radar;
+mod replay;
+mod savegame;
+mod scoring;
+#[cfg(feature = "scripting")]
+mod scripting;
+mod server;
+mod settings;
+mod sound_effects;
+pub mod spawning;
+#[cfg(feature = "netplay")]
+mod spectator;
+mod split_screen;
+mod sprite_reload;
+mod starfield;
+mod station;
+#[cfg(feature = "steam")]
+mod steam;
+mod stingers;
+mod storage;
+#[cfg(not(target_arch = "wasm32"))]
+mod stream_overlay;
+#[cfg(feature = "procedural_audio")]
+mod synth;
+mod theme;
+mod threat_indicator;
+mod time_limit;
+mod toast;
+mod touch;
+mod transition;
+mod tutorial;
+#[cfg(feature = "twitch")]
+mod twitch;
+mod ui;
+mod vector;
+mod visual_cues;
+mod wave;
+mod wave_script;
+
+use animation::SpriteAnimations;
+use attract::AttractMode;
+use audio::{GameAudio, UiChannel};
+use autosave::Autosave;
+use bench::Benchmark;
+use bullets::{fire_bullet, BulletPool, Bullets, Ricochets};
+use camera::{CameraFollow, CameraShake, CameraShakeEvent, ScreenShake, METEOR_TRAUMA_PER_SIZE, SHIP_HIT_TRAUMA};
+#[cfg(not(target_arch = "wasm32"))]
+use clips::ClipCapture;
+use config::{Config, GameConfig};
+use crt::CrtFilter;
+use culling::Culling;
+use custom_game::CustomGames;
+use damage_flash::DamageFlash;
+use debug_overlay::DebugOverlay;
+#[cfg(feature = "debug-physics")]
+use debug_physics::PhysicsDebug;
+use definitions::{Definitions, EntityDefinitions};
+use deterministic::{fix_time_step, DeterministicSimulation};
+#[cfg(feature = "dev-tools")]
+use dev_tools::DevTools;
+use duel::Duels;
+use fallback::Fallbacks;
+use frame_limiter::FrameLimiter;
+use game_mode::GameMode;
+use game_over::GameOverScreen;
+use game_speed::slow_time;
+use glow::Bloom;
+use hud::{HudCorners, HudElement, HudOptions, HudPart};
+use input::{ControlPresets, ControlScheme, ControlSchemes, Handling};
+use launch::{LaunchOptions, LaunchRunner};
+use leaderboard::Leaderboard;
+use lighting::{Lighting, PointLight2d};
+#[cfg(feature = "netplay")]
+use lobby::Lobby;
+use menu::{MainMenu, ModeChoice};
+use meteor_pool::MeteorPooling;
+#[cfg(not(target_arch = "wasm32"))]
+use mods::Mods;
+use music::BackgroundMusic;
+use navigation::MenuNavigation;
+use palette::{Palettes, Tint};
+use particles::Particles;
+use pause::Pause;
+use physics_preset::PhysicsPresets;
+use power_saving::PowerSaving;
+use powerups::{Powerup, Powerups, RapidFire, Shield};
+use preload::{AssetCache, Preloading};
+use profile::PlayerProfile;
+use race::{Checkpoint, RaceGhost, Races};
+use radar::Radar;
+use replay::{drive_replay_time, Replays};
+use savegame::SaveGame;
+use scoring::{score_points, ScoreEvent, ScorePopups, ScoreSource};
+#[cfg(feature = "scripting")]
+use scripting::GameScripts;
+pub use server::run_server;
+use settings::{GameSettings, Settings};
+use sound_effects::{SoundEffects, UiSounds};
+use spawning::{BulletSpec, MeteorSpec, ShipSpec, SpawnCommands};
+use split_screen::SplitScreen;
+use sprite_reload::SpriteReloading;
+use starfield::Starfield;
+use station::{RepairPickup, Station, StationDefense};
+#[cfg(feature = "steam")]
+use steam::SteamStats;
+use stingers::Stingers;
+#[cfg(not(target_arch = "wasm32"))]
+use stream_overlay::StreamOverlay;
+use theme::Themes;
+use threat_indicator::ThreatIndicators;
+use time_limit::TimeLimits;
+use toast::Toasts;
+use touch::TouchControls;
+use transition::Transitions;
+use tutorial::Tutorial;
+#[cfg(feature = "twitch")]
+use twitch::TwitchChat;
+use vector::VectorGraphics;
+use visual_cues::VisualCues;
+use wave::Waves;
+use wave_script::ScriptedWaves;
+
+pub struct GameEvents;
+
+impl Plugin for GameEvents {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<StartGameEvent>()
+            .add_event::<MeteorDestroyedEvent>()
+            .add_event::<ShipDestroyedEvent>()
+            .add_event::<ScoreEvent>();
+    }
+}
+
+pub struct SetupScreen;
+
+impl Plugin for SetupScreen {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(setup_graphics)
+            .add_system(screen_wrap);
+    }
+}
+
+// Starting, spawning into and ending a run, everything a game needs besides CoreGameplay
+// whether or not anyone is watching. The server binary runs it without the rest.
+pub struct Simulation;
+
+impl Plugin for Simulation {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<RunStats>()
+            .init_resource::<Handling>()
+            .init_resource::<ControlSchemes>()
+            .add_startup_system(setup_game)
+            // Each of these stretches or replaces the frame's length once Time has measured it, in
+            // this order so a replay being watched with --seed still gets its recorded lengths.
+            .add_system_to_stage(CoreStage::First, fix_time_step)
+            .add_system_to_stage(CoreStage::First, slow_time.after(fix_time_step))
+            .add_system_to_stage(CoreStage::First, drive_replay_time.after(slow_time))
+            .add_system(update_game_state)
+            .add_system(expire_lifetimes)
+            // After everything that destroys anything, so each is gone before the next frame. The
+            // PostUpdate systems that insert onto gameplay entities are ordered before it.
+            .add_system_to_stage(CoreStage::PostUpdate, despawn_dead)
+            .add_system_set(SystemSet::on_update(AppState::InGame).with_system(end_game))
+            .add_system_set(SystemSet::on_exit(AppState::InGame).with_system(cleanup_game));
+    }
+}
+
+const PIXELS_PER_METER: f32 = 100.0;
+
+// Physics and the systems that step a game along. Netplay runs both in its rollback schedule
+// instead, so they can be rewound and replayed when a late input arrives.
+pub struct CoreGameplay;
+
+impl Plugin for CoreGameplay {
+    fn build(&self, app: &mut App) {
+        #[cfg(feature = "netplay")]
+        {
+            let options = app.world.get_resource::<LaunchOptions>().cloned().unwrap_or_default();
+            if options.online() {
+                netplay::add_rollback_gameplay(app, &options, gameplay_systems());
+                return;
+            }
+        }
+        app
+            .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(PIXELS_PER_METER))
+            .add_system_set(gameplay_systems());
+    }
+}
+
+// Explicitly ordered so every run steps the game the same way, which replays and netplay rely on.
+fn gameplay_systems() -> SystemSet {
+    SystemSet::on_update(AppState::InGame)
+        .with_system(setup_physics)
+        .with_system(controls.after(setup_physics))
+        .with_system(cool_weapons.after(controls))
+        .with_system(spawn_bullet.after(cool_weapons))
+        .with_system(spaceship_collision.after(spawn_bullet))
+        .with_system(score_points.after(spaceship_collision))
+        .with_system(lose_lives.after(score_points))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum AppState {
+    MainMenu,
+    // Picking a mode to play, on top of the main menu.
+    ModeSelect,
+    // Putting together a custom mode, on top of the mode select screen.
+    CustomGame,
+    // Setting up an online game, see lobby.rs.
+    Lobby,
+    Settings,
+    Leaderboard,
+    InGame,
+    Paused,
+    GameOver
+}
+
+pub fn run() {
+    let options = LaunchOptions::from_args();
+    let headless = options.headless;
+    let first_state = if options.lobby { AppState::Lobby } else { AppState::MainMenu };
+    let mut app = App::new();
+    // Nothing is drawn without a window, so there's no need for a graphics device either.
+    if headless {
+        app.insert_resource(WgpuSettings { backends: None, ..default() });
+    }
+    app
+        .insert_resource(options.window_descriptor())
+        .insert_resource(options.mode)
+        .insert_resource(options)
+        .add_state(first_state)
+        .add_plugin(GameEvents)
+        .add_plugin(Simulation)
+        .add_plugin(Bullets)
+        .add_plugin(MeteorPooling)
+        .add_plugin(Preloading)
+        .add_plugin(MainMenu)
+        .add_plugin(CustomGames)
+        .add_plugin(AttractMode)
+        .add_plugin(MenuNavigation)
+        .add_plugin(ControlPresets)
+        .add_plugin(TouchControls)
+        .add_plugin(Transitions)
+        .add_plugin(PowerSaving)
+        .add_plugin(FrameLimiter)
+        .add_plugin(BackgroundMusic)
+        .add_plugin(SoundEffects)
+        .add_plugin(Stingers)
+        .add_plugin(VisualCues)
+        .add_plugin(Toasts)
+        .add_plugin(Tutorial)
+        .add_plugin(Leaderboard)
+        .add_plugin(PlayerProfile)
+        .add_plugin(GameSettings)
+        .add_plugin(Palettes)
+        .add_plugin(SetupScreen)
+        .add_plugin(ScreenShake)
+        .add_plugin(Starfield)
+        .add_plugin(SpriteAnimations)
+        .add_plugin(SpriteReloading)
+        .add_plugin(Particles)
+        .add_plugin(ScorePopups)
+        .add_plugin(Bloom)
+        .add_plugin(Lighting)
+        .add_plugin(VectorGraphics)
+        .add_plugin(Fallbacks)
+        .add_plugin(CrtFilter)
+        .add_plugin(DamageFlash)
+        .add_plugin(SplitScreen)
+        .add_plugin(Culling)
+        .add_plugin(Radar)
+        .add_plugin(HudOptions)
+        .add_plugin(ThreatIndicators)
+        .add_plugin(DebugOverlay)
+        .add_plugin(Waves)
+        .add_plugin(Duels)
+        .add_plugin(Powerups)
+        .add_plugin(StationDefense)
+        .add_plugin(Races)
+        .add_plugin(TimeLimits)
+        .add_plugin(SaveGame)
+        .add_plugin(Replays)
+        .add_plugin(Autosave)
+        // Lets config.ron and other assets be edited while the game is running in development. There's
+        // nothing to watch in a browser.
+        .insert_resource(AssetServerSettings {
+            watch_for_changes: cfg!(debug_assertions) && cfg!(not(target_arch = "wasm32")),
+            ..default()
+        })
+        .add_plugin(LaunchRunner)
+        .add_plugins_with(DefaultPlugins, |group| {
+            if headless {
+                group.disable::<WinitPlugin>().disable::<WindowPlugin>();
+            }
+            // Swaps in an asset server that reads from the mods folder too, see mods.rs.
+            #[cfg(not(target_arch = "wasm32"))]
+            group.add_before::<bevy::asset::AssetPlugin, _>(Mods);
+            group
+        })
+        .add_plugin(CoreGameplay)
+        .add_plugin(DeterministicSimulation)
+        .add_plugin(PhysicsPresets)
+        // Registers an asset type, so it has to come after the asset plugin in DefaultPlugins.
+        .add_plugin(GameAudio)
+        .add_plugin(Config)
+        .add_plugin(Definitions)
+        .add_plugin(ScriptedWaves)
+        .add_plugin(Themes)
+        .add_plugin(Pause)
+        .add_plugin(GameOverScreen)
+        .add_plugin(Benchmark)
+        .add_system(button_interaction)
+        .add_plugin(UpdateUI);
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_plugin(StreamOverlay);
+    // Copies frames back from the render world, so it needs the render plugins first. Browsers can't
+    // start a thread to write the GIF on and have nowhere to save it anyway.
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_plugin(ClipCapture);
+    #[cfg(feature = "netplay")]
+    app.add_plugin(Lobby);
+    #[cfg(feature = "twitch")]
+    app.add_plugin(TwitchChat);
+    #[cfg(feature = "scripting")]
+    app.add_plugin(GameScripts);
+    #[cfg(feature = "steam")]
+    app.add_plugin(SteamStats);
+    #[cfg(feature = "debug-physics")]
+    app.add_plugin(PhysicsDebug);
+    #[cfg(feature = "dev-tools")]
+    app.add_plugin(DevTools);
+    app.run();
+}
+
+fn setup_graphics(mut commands: Commands) {
+    // Add a camera so we can see the debug-render.
+    commands
+        .spawn_bundle(Camera2dBundle::default())
+        .insert(CameraShake::default())
+        .insert(CameraFollow(0));
+}
+
+struct MeteorDestroyedEvent {
+    position: Vec2,
+    size: u8
+}
+
+struct ShipDestroyedEvent {
+    player: u8,
+    position: Vec2
+}
+
+struct StartGameEvent {
+    players: u8
+}
+
+// Where each player's ship starts, the second ship only appears in two-player games.
+const SHIP_SPAWN_POSITIONS: [Vec2; 2] = [Vec2::new(-100.0, 0.0), Vec2::new(100.0, 0.0)];
+
+fn ship_spawn_position(game: &Game, player: u8) -> Vec2 {
+    if game.players > 1 {
+        SHIP_SPAWN_POSITIONS[player as usize]
+    } else {
+        Vec2::ZERO
+    }
+}
+
+// Meteors arrive with the first wave, see wave.rs. Duels spawn everything per round, see duel.rs,
+// and races spawn their own meteors once they start, see race.rs.
+fn setup_physics(
+    mode: Res<GameMode>,
+    asset_cache: Res<AssetCache>,
+    mut game_query: Query<&mut Game>,
+    mut commands: Commands
+) {
+    let mut game = game_query.single_mut();
+    if matches!(game.gameState, GameState::Loading) && asset_cache.ready() {
+        let ships = if *mode == GameMode::Duel { 0 } else { game.players };
+        for player in 0..ships {
+            commands.spawn_ship(ShipSpec::player(player).at(ship_spawn_position(&game, player)));
+        }
+        
+        game.gameState = GameState::InProgress
+    }
+}
+
+#[derive(Component)]
+struct ScreenWrap;
+
+// Despawns its entity once the timer runs out.
+#[derive(Component)]
+struct Lifetime(Timer);
+
+// On pooled entities waiting to be reused, see bullets.rs and meteor_pool.rs. Inserting it is how
+// a pooled entity is given back.
+#[derive(Component)]
+struct Parked;
+
+// Far enough outside the arena that nothing parked there is ever seen or run into.
+const PARKED_POSITION: Vec3 = Vec3::new(100_000.0, 100_000.0, 0.0);
+
+// How many entities each thread takes at a time when a system that runs over every one of a kind
+// is split across threads. Small enough that a few thousand still spread out.
+const PAR_BATCH_SIZE: usize = 256;
+
+// On entities that are done with, until despawn_dead despawns them after the frame's gameplay.
+// Whatever destroys something mid-game marks it with destroy rather than despawning it there and
+// then, so two things destroying it in the same frame can't trip over each other, and anything
+// inserted onto it later in the frame still has somewhere to go instead of panicking.
+#[derive(Component)]
+struct Dead;
+
+trait DestroyCommands {
+    fn destroy(&mut self, entity: Entity);
+}
+
+impl DestroyCommands for Commands<'_, '_> {
+    fn destroy(&mut self, entity: Entity) {
+        self.add(Destroy(vec![entity]));
+    }
+}
+
+// Marks entities Dead, leaving out any that are already gone.
+struct Destroy(Vec<Entity>);
+
+impl Command for Destroy {
+    fn write(self, world: &mut World) {
+        for entity in self.0 {
+            if let Some(mut entity) = world.get_entity_mut(entity) {
+                entity.insert(Dead);
+            }
+        }
+    }
+}
+
+// Despawns a whole frame's worth of entities as one command rather than one each.
+struct DespawnBatch(Vec<Entity>);
+
+impl Command for DespawnBatch {
+    fn write(self, world: &mut World) {
+        for entity in self.0 {
+            despawn_with_children_recursive(world, entity);
+        }
+    }
+}
+
+// The only place gameplay entities are despawned, children and all.
+fn despawn_dead(mut commands: Commands, query: Query<Entity, With<Dead>>) {
+    let dead: Vec<Entity> = query.iter().collect();
+    if !dead.is_empty() {
+        commands.add(DespawnBatch(dead));
+    }
+}
+
+fn expire_lifetimes(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Lifetime)>
+) {
+    let expired = Mutex::new(Vec::new());
+    let delta = time.delta();
+    query.par_for_each_mut(PAR_BATCH_SIZE, |(entity, mut lifetime)| {
+        if lifetime.0.tick(delta).just_finished() {
+            expired.lock().unwrap().push(entity);
+        }
+    });
+    let expired = expired.into_inner().unwrap();
+    if !expired.is_empty() {
+        commands.add(Destroy(expired));
+    }
+}
+
+#[derive(Component)]
+struct Spaceship;
+
+// Tags ships and the bullets they fire with the player that owns them.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+struct PlayerId(u8);
+
+#[derive(Component)]
+#[cfg_attr(feature = "dev-tools", derive(bevy_inspector_egui::Inspectable))]
+struct Meteor {
+    size: u8
+}
+
+const INITIAL_SCORE: u32 = 0;
+
+// Rewound by netplay along with everything else, see netplay.rs.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+#[cfg_attr(feature = "dev-tools", derive(bevy_inspector_egui::Inspectable))]
+struct Game {
+    score: u32,
+    player_scores: [u32; 2],
+    // Lives left between all players, the game is over once it reaches 0.
+    lives: u8,
+    // Each player's own lives when they aren't shared, see Game::lose_life.
+    player_lives: [u8; 2],
+    shared_lives: bool,
+    players: u8,
+    gameState: GameState
+}
+
+impl Game {
+    // Returns whether the player has a life left to respawn with.
+    fn lose_life(&mut self, player: u8) -> bool {
+        self.lives = self.lives.saturating_sub(1);
+        if self.shared_lives {
+            return self.lives > 0;
+        }
+        let player_lives = &mut self.player_lives[player as usize];
+        *player_lives = player_lives.saturating_sub(1);
+        *player_lives > 0
+    }
+
+    fn gain_life(&mut self, player: u8) {
+        self.lives = self.lives.saturating_add(1);
+        if !self.shared_lives {
+            let player_lives = &mut self.player_lives[player as usize];
+            *player_lives = player_lives.saturating_add(1);
+        }
+    }
+}
+
+// Per-run numbers shown on the game-over screen.
+#[derive(Default)]
+struct RunStats {
+    shots_fired: u32,
+    hits: u32,
+    waves_cleared: u32
+}
+
+impl RunStats {
+    fn accuracy(&self) -> f32 {
+        if self.shots_fired == 0 {
+            0.0
+        } else {
+            self.hits as f32 / self.shots_fired as f32
+        }
+    }
+}
+
+#[derive(Reflect, Clone, Copy, PartialEq, Default)]
+#[reflect_value(PartialEq)]
+#[cfg_attr(feature = "dev-tools", derive(bevy_inspector_egui::Inspectable))]
+enum GameState {
+    Loading,
+    InProgress,
+    Ended,
+    #[default]
+    Waiting
+}
+
+fn setup_game(
+    mut commands: Commands,
+    config: Res<GameConfig>
+) {
+    commands
+        .spawn()
+        .insert(Game {
+            score: INITIAL_SCORE,
+            player_scores: [INITIAL_SCORE; 2],
+            lives: config.initial_lives,
+            player_lives: [config.initial_lives; 2],
+            shared_lives: true,
+            players: 1,
+            gameState: GameState::Waiting
+        });
+}
+
+fn controls(
+    keyboard_input: Res<Input<KeyCode>>,
+    config: Res<GameConfig>,
+    handling: Res<Handling>,
+    mut body: Query<(&mut Transform, &mut ExternalImpulse, &mut Velocity, &ControlScheme)>
+) {
+    let thrust = config.thrust_impulse * handling.thrust;
+    let rotation = config.rotation_rate * handling.rotation;
+    for (mut transform, mut impulse, mut velocity, scheme) in body.iter_mut() {
+        if keyboard_input.pressed(scheme.thrust) {
+            let axis_angle = transform.rotation.to_axis_angle();
+            impulse.impulse = Vec2::from_angle(axis_angle.1 * axis_angle.0.z) * thrust;
+        }
+        if keyboard_input.pressed(scheme.left) {
+            velocity.angvel = 0.0;
+            transform.rotate_axis(Vec3::new(0.0, 0.0, 1.0), rotation);
+        }
+        if keyboard_input.pressed(scheme.right) {
+            velocity.angvel = 0.0;
+            transform.rotate_axis(Vec3::new(0.0, 0.0, 1.0), -rotation);
+        }
+    }    
+}
+
+// The play area is the window, or the size it would have been when running headless.
+fn arena_size(windows: &Windows, window_descriptor: &WindowDescriptor) -> Vec2 {
+    windows
+        .get_primary()
+        .map(|window| Vec2::new(window.width(), window.height()))
+        .unwrap_or_else(|| Vec2::new(window_descriptor.width, window_descriptor.height))
+}
+
+fn screen_wrap(
+    windows: Res<Windows>,
+    window_descriptor: Res<WindowDescriptor>,
+    mut q: Query<(&mut Transform, Option<&Sprite>, Option<&TextureAtlasSprite>, &ScreenWrap)>
+) {
+    let arena = arena_size(&windows, &window_descriptor);
+    q.par_for_each_mut(PAR_BATCH_SIZE, |(mut transform, sprite, atlas_sprite, _)| {
+        let mut biggest_dimension = 0.0;
+        let custom_size = sprite.and_then(|sprite| sprite.custom_size)
+            .or_else(|| atlas_sprite.and_then(|sprite| sprite.custom_size));
+        if let Some(size) = custom_size {
+            biggest_dimension = size.x.max(size.y)
+        }
+        if (transform.translation.x.abs() - biggest_dimension / 2.0) > arena.x / 2.0 {
+            transform.translation.x *= -1.0;
+        }
+        if (transform.translation.y.abs() - biggest_dimension / 2.0) > arena.y / 2.0 {
+            transform.translation.y *= -1.0;
+        }
+    });
+}
+
+fn spaceship_collision(
+    rapier_context: Res<RapierContext>,
+    definitions: Res<EntityDefinitions>,
+    query_ship: Query<(Entity, &PlayerId, &Transform, Option<&Shield>), With<Spaceship>>,
+    query_meteor: Query<(Entity, &Meteor, &Velocity, &Transform), Without<Parked>>,
+    query_bullets: Query<(Entity, &PlayerId, Option<&Ricochets>), (With<Bullet>, Without<Parked>)>,
+    mut commands: Commands,
+    mut shake_event: EventWriter<CameraShakeEvent>,
+    mut destroyed_event: EventWriter<MeteorDestroyedEvent>,
+    mut ship_destroyed_event: EventWriter<ShipDestroyedEvent>,
+    mut score_event: EventWriter<ScoreEvent>
+) {
+    let meteor_definition = &definitions.meteor;
+
+    // What's already been destroyed this frame, since it's still in the queries until the commands
+    // are applied. A ship between two meteors or a bullet through two overlapping ones only counts once.
+    let mut spent: Vec<Entity> = Vec::new();
+    for (entity_meteor, meteor, meteor_velocity, transform) in query_meteor.iter() {
+        for (entity_ship, player, ship_transform, shield) in query_ship.iter() {
+            if shield.is_some() || spent.contains(&entity_ship) {
+                continue;
+            }
+            if rapier_context.intersection_pair(entity_meteor, entity_ship) == Some(true) {
+                spent.push(entity_ship);
+                commands.destroy(entity_ship);
+                shake_event.send(CameraShakeEvent { trauma: SHIP_HIT_TRAUMA });
+                ship_destroyed_event.send(ShipDestroyedEvent {
+                    player: player.0,
+                    position: ship_transform.translation.truncate()
+                });
+            }
+        }
+        
+        for (entity_bullets, owner, ricochets) in query_bullets.iter() {
+            if spent.contains(&entity_bullets) {
+                continue;
+            }
+            if rapier_context.intersection_pair(entity_meteor, entity_bullets) == Some(true) {
+                spent.push(entity_bullets);
+                // Big splits are where frame spikes turn up, so each hit gets its own span when profiling.
+                let _split = info_span!("meteor_hit", size = meteor.size).entered();
+                score_event.send(ScoreEvent {
+                    amount: meteor_definition.points,
+                    source: ScoreSource::MeteorShot {
+                        player: owner.0,
+                        ricochets: ricochets.map_or(0, |ricochets| ricochets.0)
+                    },
+                    position: transform.translation.truncate()
+                });
+                destroyed_event.send(MeteorDestroyedEvent {
+                    position: transform.translation.truncate(),
+                    size: meteor.size
+                });
+                shake_event.send(CameraShakeEvent { trauma: METEOR_TRAUMA_PER_SIZE * meteor.size as f32 });
+                    
+                if meteor.size > meteor_definition.smallest_size {
+                    let split_angle = meteor_definition.split_angle;
+                    let half = MeteorSpec::new(meteor.size / 2).at(transform.translation.truncate());
+                    commands.spawn_meteor(
+                        half.with_velocity(meteor_velocity.linvel.rotate(Vec2::from_angle(split_angle)))
+                    );
+                    commands.spawn_meteor(
+                        half.with_velocity(meteor_velocity.linvel.rotate(Vec2::from_angle(-split_angle)))
+                    );
+                }
+                commands.entity(entity_meteor).insert(Parked);
+                commands.entity(entity_bullets).insert(Parked);
+                // The meteor's gone, so no other bullet can hit it.
+                break;
+            }
+        }
+    }
+}
+
+// Lives, respawns and the end of the game for every ship destroyed this frame, whatever destroyed
+// it. A player only loses one life a frame however many things hit their ship. Duels are decided by
+// rounds rather than lives, see duel.rs.
+fn lose_lives(
+    mode: Res<GameMode>,
+    mut query_game: Query<&mut Game>,
+    mut commands: Commands,
+    mut ship_destroyed_event: EventReader<ShipDestroyedEvent>
+) {
+    let mut destroyed: Vec<u8> = ship_destroyed_event.iter().map(|ev| ev.player).collect();
+    if *mode == GameMode::Duel || destroyed.is_empty() {
+        return;
+    }
+    destroyed.sort_unstable();
+    destroyed.dedup();
+    let mut game = query_game.single_mut();
+    for player in destroyed {
+        if !mode.rules().game_over || game.lose_life(player) {
+            commands.spawn_ship(ShipSpec::player(player).at(ship_spawn_position(&game, player)));
+        } else if game.lives == 0 {
+            game.gameState = GameState::Ended
+        }
+    }
+}
+
+fn meteor_collider(config: &GameConfig, definitions: &EntityDefinitions, size: u8) -> Collider {
+    Collider::ball(config.asteroid_base * size as f32 / 2.0 * definitions.meteor.hitbox)
+}
+
+#[derive(Component)]
+struct Bullet;
+
+#[derive(Component)]
+struct ReadyToFire(bool);
+
+const HEAT_PER_SHOT: f32 = 0.08;
+const WEAPON_COOLING: f32 = 0.4;
+// Once overheated the weapon stays locked until it has cooled back down to this.
+const OVERHEAT_RECOVERY: f32 = 0.3;
+const RAPID_FIRE_INTERVAL: f32 = 0.1;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct Weapon {
+    heat: f32,
+    overheated: bool,
+    cooldown: Timer
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Weapon {
+            heat: 0.0,
+            overheated: false,
+            cooldown: Timer::from_seconds(RAPID_FIRE_INTERVAL, false)
+        }
+    }
+}
+
+// A child of the ship where its bullets come from, pointing the way they're fired, one for each
+// mount in game.entities.ron.
+#[derive(Component)]
+struct MuzzlePoint;
+
+fn cool_weapons(time: Res<Time>, mut query: Query<&mut Weapon>) {
+    for mut weapon in query.iter_mut() {
+        weapon.cooldown.tick(time.delta());
+        weapon.heat = (weapon.heat - WEAPON_COOLING * time.delta_seconds()).max(0.0);
+        if weapon.overheated && weapon.heat <= OVERHEAT_RECOVERY {
+            weapon.overheated = false;
+        }
+    }
+}
+
+const MUZZLE_FLASH_COLOUR: Color = Color::rgb(1.0, 0.95, 0.7);
+const MUZZLE_FLASH_SIZE: Vec2 = Vec2::new(14.0, 8.0);
+// Long enough for a few frames at 60fps.
+const MUZZLE_FLASH_DURATION: f32 = 0.05;
+const MUZZLE_FLASH_LIGHT_RADIUS: f32 = 150.0;
+
+// Muzzles are placed from the ship's transform as it is right now rather than their GlobalTransform,
+// which isn't brought up to date until after the frame's gameplay and is still the identity on a
+// ship spawned this frame. Ships are never parented, so their transform is already in world space.
+fn spawn_bullet(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    config: Res<GameConfig>,
+    definitions: Res<EntityDefinitions>,
+    mut query: Query<
+        (&Velocity, &Transform, &ControlScheme, &PlayerId, &mut Weapon, Option<&RapidFire>, Option<&Children>),
+        With<Spaceship>,
+    >,
+    query_muzzles: Query<&Transform, (With<MuzzlePoint>, Without<Spaceship>)>,
+    mut run_stats: ResMut<RunStats>,
+    mut pool: ResMut<BulletPool>,
+) {
+    for (ship_velocity, ship_transform, scheme, player, mut weapon, rapid_fire, children) in query.iter_mut() {
+        let auto_fire = rapid_fire.is_some() && keyboard_input.pressed(scheme.fire) && weapon.cooldown.finished();
+        if weapon.overheated || !(keyboard_input.just_pressed(scheme.fire) || auto_fire) {
+            continue;
+        }
+        weapon.cooldown.reset();
+        weapon.heat += HEAT_PER_SHOT;
+        if weapon.heat >= 1.0 {
+            weapon.overheated = true;
+        }
+        let muzzles = children
+            .into_iter()
+            .flat_map(|children| children.iter())
+            .filter_map(|child| query_muzzles.get(*child).ok());
+        for muzzle in muzzles {
+            let muzzle = ship_transform.mul_transform(*muzzle);
+            let facing = (muzzle.rotation * Vec3::X).truncate();
+            run_stats.shots_fired += 1;
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: MUZZLE_FLASH_COLOUR,
+                        custom_size: Some(MUZZLE_FLASH_SIZE),
+                        ..default()
+                    },
+                    transform: Transform {
+                        translation: muzzle.translation + Vec3::Z,
+                        rotation: muzzle.rotation,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(PointLight2d {
+                    colour: MUZZLE_FLASH_COLOUR,
+                    radius: MUZZLE_FLASH_LIGHT_RADIUS,
+                    intensity: 0.6,
+                })
+                .insert(Lifetime(Timer::from_seconds(MUZZLE_FLASH_DURATION, false)));
+            let spec = BulletSpec::player(player.0)
+                .at(muzzle.translation.truncate())
+                .with_velocity(facing * config.bullet_speed + ship_velocity.linvel);
+            fire_bullet(&mut commands, &mut pool, &definitions.bullet, spec);
+        }
+    }
+}
+
+#[derive(Component)]
+struct ScoreUI;
+
+#[derive(Component)]
+struct LivesUI;
+
+#[derive(Component)]
+struct StartButton {
+    players: u8,
+    // None plays whichever mode is picked on the main menu.
+    mode: Option<GameMode>
+}
+
+fn update_game_state(
+    mut commands: Commands,
+    mut game_event: EventReader<StartGameEvent>,
+    mut query_game: Query<&mut Game>,
+    mut run_stats: ResMut<RunStats>,
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+    mode: Res<GameMode>,
+    gameplay_query: Query<Entity, GameplayEntity>
+) {
+    let mut game = query_game.single_mut();
+
+    for ev in game_event.iter() {
+        // Restarting mid-run has to clear out whatever is still flying around.
+        for entity in gameplay_query.iter() {
+            commands.destroy(entity);
+        }
+        game.gameState = GameState::Loading;
+        // Only two-player games can keep lives apart, one player always has the whole pool.
+        game.shared_lives = settings.shared_lives || ev.players < 2;
+        let lives = mode.initial_lives(&config);
+        game.player_lives = [lives; 2];
+        game.lives = if game.shared_lives { lives } else { lives.saturating_mul(ev.players) };
+        game.score = INITIAL_SCORE;
+        game.player_scores = [INITIAL_SCORE; 2];
+        // A duel always needs both ships.
+        game.players = if *mode == GameMode::Duel { 2 } else { ev.players };
+        *run_stats = RunStats::default();
+    }
+}
+
+fn end_game(query_game: Query<&Game>, mut state: ResMut<State<AppState>>) {
+    if matches!(query_game.single().gameState, GameState::Ended) {
+        if let Err(err) = state.set(AppState::GameOver) {
+            warn!("Could not end game: {:?}", err);
+        }
+    }
+}
+
+// Everything that belongs to a run and has to go when it restarts or ends. Parked bullets stay
+// in the pool for the next one.
+type GameplayEntity = (
+    Or<(
+        With<Meteor>,
+        With<Spaceship>,
+        With<Bullet>,
+        With<Powerup>,
+        With<Station>,
+        With<RepairPickup>,
+        With<Checkpoint>,
+        With<RaceGhost>,
+    )>,
+    Without<Parked>,
+);
+
+fn cleanup_game(
+    mut commands: Commands,
+    mut query_game: Query<&mut Game>,
+    gameplay_query: Query<Entity, GameplayEntity>
+) {
+    for entity in gameplay_query.iter() {
+        commands.destroy(entity);
+    }
+    query_game.single_mut().gameState = GameState::Waiting;
+}
+
+fn button_interaction(
+    mut interaction_query: Query<
+        (&Interaction, &mut UiColor, Option<&StartButton>),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut start_game_event: EventWriter<StartGameEvent>,
+    mut mode: ResMut<GameMode>,
+    choice: Res<ModeChoice>,
+    ui_channel: Res<AudioChannel<UiChannel>>,
+    ui_sounds: Res<UiSounds>,
+    config: Res<GameConfig>,
+) {
+    for (interaction, mut color, start_button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Clicked => {
+                *color = config.button_pressed_colour.into();
+                if let Some(start_button) = start_button {
+                    ui_channel.play(ui_sounds.confirm.clone());
+                    *mode = start_button.mode.unwrap_or(choice.0);
+                    start_game_event.send(StartGameEvent { players: start_button.players });
+                } else {
+                    ui_channel.play(ui_sounds.click.clone());
+                }
+            }
+            Interaction::Hovered => {
+                *color = config.button_hovered_colour.into();
+                ui_channel.play(ui_sounds.hover.clone());
+            }
+            Interaction::None => {
+                *color = config.button_colour.into();
+            }
+        }
+    }
+}
+
+fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                padding: UiRect { left: Val::Px(10.0), right: Val::Px(10.0), top: Val::Px(10.0), bottom: Val::Px(10.0) },
+                size: Size { width: Val::Percent(100.0), height: Val::Percent(100.0) },
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::FlexEnd,
+                ..Default::default()
+            },
+            color: UiColor::from(Color::Rgba { red: 0.0, green: 0.0, blue: 0.0, alpha: 0.0 }),
+            ..default()
+        })
+        .insert(HudCorners)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text {
+                    sections: vec![
+                        TextSection {
+                            value: format!("Final Score: {}", 0),
+                            style: TextStyle {
+                                font: asset_server.load("BungeeSpice-Regular.ttf"),
+                                font_size: 40.0,
+                                ..default()
+                            },
+                        },
+                    ],
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(ScoreUI)
+            .insert(Tint::Accent)
+            .insert(HudElement { part: HudPart::Score, opacity: 1.0 });
+            parent.spawn_bundle(TextBundle {
+                text: Text {
+                    sections: vec![
+                        TextSection {
+                            value: format!("Lives: {}", 3),
+                            style: TextStyle {
+                                font: asset_server.load("BungeeSpice-Regular.ttf"),
+                                font_size: 40.0,
+                                ..default()
+                            },
+                        },
+                    ],
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(LivesUI)
+            .insert(Tint::Accent)
+            .insert(HudElement { part: HudPart::Lives, opacity: 1.0 });
+        });     
+}
+
+// The HUD text is only rebuilt when Game actually changes rather than every frame.
+fn update_score(
+    query_game: Query<&Game, Changed<Game>>,
+    mut query_score: Query<&mut Text, With<ScoreUI>>,
+) {    
+    if query_game.is_empty() {
+        return;
+    }
+    for mut ts in query_score.iter_mut() {
+        if let Some(text_value) = ts.sections.get_mut(0) {
+          if let Ok(game) = query_game.get_single() {
+            text_value.value = format!("Final Score: {}", game.score);
+          }
+        }
+    }
+}
+// Modes without a game over never use up their lives, so there's no count to show.
+fn update_lives(
+    query_game: Query<&Game, Changed<Game>>,
+    mode: Res<GameMode>,
+    mut query_lives: Query<&mut Text, With<LivesUI>>,
+) {    
+    if query_game.is_empty() {
+        return;
+    }
+    for mut ts in query_lives.iter_mut() {
+        if let Some(text_value) = ts.sections.get_mut(0) {
+          if let Ok(game) = query_game.get_single() {
+            text_value.value = if mode.rules().game_over {
+                format!("Lives: {}", game.lives)
+            } else {
+                "Lives: Unlimited".to_string()
+            };
+          }
+        }
+    }
+}
+
+pub struct UpdateUI;
+
+impl Plugin for UpdateUI {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(setup_ui)
+            .add_system(update_score)
+            .add_system(update_lives);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::CommandQueue;
+    use bevy::input::keyboard::KeyboardInput;
+    use bevy::input::ButtonState;
+
+    use super::*;
+    use crate::definitions::BulletDefinition;
+    use crate::deterministic::FIXED_DELTA;
+    use crate::game_mode::CustomRules;
+    use crate::server::headless_app;
+    use crate::spawning::BulletBundle;
+    use crate::wave::WaveState;
+    use crate::wave_script::{ScriptedSpawn, Spawnable, WaveScript, WaveScripts};
+
+    const TEST_SEED: u64 = 1;
+    // Ten seconds of fixed steps, far more than anything below needs.
+    const MAX_FRAMES: u32 = 600;
+
+    fn test_app() -> App {
+        let mut app = headless_app(LaunchOptions::default(), TEST_SEED);
+        // Runs the startup systems, so there's a Game to play.
+        app.update();
+        app
+    }
+
+    // Returns once every player's ship is out.
+    fn start_game(app: &mut App, players: u8) {
+        app.world.send_event(StartGameEvent { players });
+        app.world.resource_mut::<State<AppState>>().set(AppState::InGame).unwrap();
+        run_until(app, |app| ship_count(app) == players as usize);
+    }
+
+    // Steps the app until the condition holds, failing the test if it never does.
+    fn run_until(app: &mut App, mut condition: impl FnMut(&mut App) -> bool) {
+        for _ in 0..MAX_FRAMES {
+            if condition(app) {
+                return;
+            }
+            app.update();
+        }
+        panic!("Still waiting after {} frames", MAX_FRAMES);
+    }
+
+    fn game(app: &mut App) -> &Game {
+        app.world.query::<&Game>().single(&app.world)
+    }
+
+    fn meteor_sizes(app: &mut App) -> Vec<u8> {
+        let mut sizes: Vec<u8> = app.world.query::<&Meteor>().iter(&app.world).map(|meteor| meteor.size).collect();
+        sizes.sort_unstable();
+        sizes
+    }
+
+    fn ship_count(app: &mut App) -> usize {
+        app.world.query_filtered::<(), With<Spaceship>>().iter(&app.world).count()
+    }
+
+    // Applies whatever the closure queues up straight away, like the end of a stage would.
+    fn apply_commands(app: &mut App, queue_up: impl FnOnce(&mut Commands)) {
+        let mut queue = CommandQueue::default();
+        queue_up(&mut Commands::new(&mut queue, &app.world));
+        queue.apply(&mut app.world);
+    }
+
+    fn spawn_meteor(app: &mut App, size: u8, position: Vec2) {
+        apply_commands(app, |commands| commands.spawn_meteor(MeteorSpec::new(size).at(position)));
+        app.update();
+    }
+
+    // A still bullet from player one, dropped straight onto the position.
+    fn spawn_bullet_at(app: &mut App, position: Vec2) {
+        app.world
+            .spawn()
+            .insert_bundle(BulletBundle::new(BulletSpec::player(0).at(position), &BulletDefinition::default()));
+    }
+
+    #[test]
+    fn spawn_meteor_spawns_a_meteor() {
+        let mut app = test_app();
+        apply_commands(&mut app, |commands| {
+            commands.spawn_meteor(MeteorSpec::medium().at(Vec2::new(200.0, 100.0)).with_velocity(Vec2::new(30.0, 0.0)))
+        });
+        app.update();
+
+        let (meteor, transform, velocity) = app
+            .world
+            .query_filtered::<(&Meteor, &Transform, &Velocity), With<Collider>>()
+            .single(&app.world);
+        assert_eq!(meteor.size, 4);
+        assert!(transform.translation.truncate().abs_diff_eq(Vec2::new(200.0, 100.0), 1.0));
+        assert_eq!(velocity.linvel, Vec2::new(30.0, 0.0));
+    }
+
+    #[test]
+    fn spawn_ship_spawns_a_ship_for_that_player() {
+        let mut app = test_app();
+        apply_commands(&mut app, |commands| {
+            commands.spawn_ship(ShipSpec::player(1).facing(0.0));
+        });
+        app.update();
+
+        let (player, scheme) = app
+            .world
+            .query_filtered::<(&PlayerId, &ControlScheme), (With<Spaceship>, With<Weapon>, With<Collider>)>()
+            .single(&app.world);
+        assert_eq!(player.0, 1);
+        assert_eq!(scheme.thrust, ControlScheme::for_player(1).thrust);
+    }
+
+    #[test]
+    fn spawned_ships_sit_where_their_spec_puts_them() {
+        let mut app = test_app();
+        let spec = ShipSpec::player(0).at(Vec2::new(-120.0, 80.0));
+        apply_commands(&mut app, |commands| {
+            commands.spawn_ship(spec);
+        });
+
+        let transform = app.world.query_filtered::<&Transform, With<Spaceship>>().single(&app.world);
+        assert_eq!(transform.translation, Vec3::new(-120.0, 80.0, 0.0));
+        assert_eq!(transform.rotation, Quat::from_rotation_z(spec.orientation));
+        assert_eq!(transform.scale, Vec3::ONE);
+    }
+
+    #[test]
+    fn starting_a_game_spawns_the_ship_then_the_first_wave() {
+        let mut app = test_app();
+        start_game(&mut app, 1);
+
+        assert!(matches!(game(&mut app).gameState, GameState::InProgress));
+        assert_eq!(game(&mut app).lives, 3);
+        assert!(meteor_sizes(&mut app).is_empty());
+
+        run_until(&mut app, |app| !meteor_sizes(app).is_empty());
+        assert_eq!(app.world.resource::<WaveState>().number, 1);
+    }
+
+    #[test]
+    fn shooting_a_meteor_splits_it_in_two_and_scores() {
+        let mut app = test_app();
+        start_game(&mut app, 1);
+        let position = Vec2::new(200.0, 0.0);
+        spawn_meteor(&mut app, 8, position);
+        spawn_bullet_at(&mut app, position);
+
+        run_until(&mut app, |app| meteor_sizes(app) != vec![8]);
+        app.update();
+        assert_eq!(meteor_sizes(&mut app), vec![4, 4]);
+        assert!(app.world.query_filtered::<(), (With<Bullet>, Without<Parked>)>().iter(&app.world).next().is_none());
+        assert_eq!(game(&mut app).score, 1);
+        assert_eq!(game(&mut app).player_scores, [1, 0]);
+        assert_eq!(app.world.resource::<RunStats>().hits, 1);
+    }
+
+    #[test]
+    fn scoring_past_the_extra_life_score_earns_a_life() {
+        let mut app = test_app();
+        start_game(&mut app, 1);
+        let every = app.world.resource::<GameConfig>().extra_life_score;
+        app.world.query::<&mut Game>().single_mut(&mut app.world).score = every - 1;
+        let position = Vec2::new(200.0, 0.0);
+        spawn_meteor(&mut app, 8, position);
+        spawn_bullet_at(&mut app, position);
+
+        run_until(&mut app, |app| game(app).score == every);
+        assert_eq!(game(&mut app).lives, 4);
+    }
+
+    #[test]
+    fn scores_keep_counting_past_255() {
+        let mut app = test_app();
+        start_game(&mut app, 1);
+        {
+            let mut game = app.world.query::<&mut Game>().single_mut(&mut app.world);
+            game.score = 255;
+            game.player_scores = [255, 0];
+        }
+        let position = Vec2::new(200.0, 0.0);
+        spawn_meteor(&mut app, 8, position);
+        spawn_bullet_at(&mut app, position);
+
+        run_until(&mut app, |app| game(app).score != 255);
+        assert_eq!(game(&mut app).score, 256);
+        assert_eq!(game(&mut app).player_scores, [256, 0]);
+    }
+
+    #[test]
+    fn a_bullet_through_overlapping_meteors_only_hits_one() {
+        let mut app = test_app();
+        start_game(&mut app, 1);
+        let position = Vec2::new(200.0, 0.0);
+        spawn_meteor(&mut app, 8, position);
+        spawn_meteor(&mut app, 8, position);
+        spawn_bullet_at(&mut app, position);
+
+        run_until(&mut app, |app| meteor_sizes(app) != vec![8, 8]);
+        app.update();
+        assert_eq!(meteor_sizes(&mut app), vec![4, 4, 8]);
+        assert_eq!(game(&mut app).score, 1);
+        assert_eq!(app.world.resource::<RunStats>().hits, 1);
+    }
+
+    #[test]
+    fn ships_fire_a_bullet_from_every_muzzle() {
+        let mut app = test_app();
+        start_game(&mut app, 1);
+        let (ship, fire) = app
+            .world
+            .query_filtered::<(Entity, &ControlScheme), With<Spaceship>>()
+            .single(&app.world);
+        let fire = fire.fire;
+        let muzzle = app
+            .world
+            .spawn()
+            .insert_bundle(TransformBundle::from(Transform::from_xyz(0.0, 10.0, 0.0)))
+            .insert(MuzzlePoint)
+            .id();
+        app.world.entity_mut(ship).push_children(&[muzzle]);
+
+        app.world.send_event(KeyboardInput { scan_code: 0, key_code: Some(fire), state: ButtonState::Pressed });
+        app.update();
+        let bullets = app.world.query_filtered::<(), (With<Bullet>, Without<Parked>)>().iter(&app.world).count();
+        assert_eq!(bullets, 2);
+        assert_eq!(app.world.resource::<RunStats>().shots_fired, 2);
+    }
+
+    #[test]
+    fn a_new_ship_fires_from_its_muzzle_on_its_first_frame() {
+        let mut app = test_app();
+        start_game(&mut app, 1);
+        let position = Vec2::new(100.0, 50.0);
+        apply_commands(&mut app, |commands| {
+            commands.spawn_ship(ShipSpec::player(1).at(position));
+        });
+
+        let fire = ControlScheme::for_player(1).fire;
+        app.world.send_event(KeyboardInput { scan_code: 0, key_code: Some(fire), state: ButtonState::Pressed });
+        app.update();
+        let (transform, velocity, _) = app
+            .world
+            .query_filtered::<(&Transform, &Velocity, &PlayerId), (With<Bullet>, Without<Parked>)>()
+            .iter(&app.world)
+            .find(|(_, _, player)| player.0 == 1)
+            .unwrap();
+        let mount = app.world.resource::<EntityDefinitions>().ship.mounts[0];
+        let facing = Quat::from_rotation_z(ShipSpec::player(1).orientation);
+        let muzzle = position + (facing * mount.offset.extend(0.0)).truncate();
+        let heading = (facing * Quat::from_rotation_z(mount.angle) * Vec3::X).truncate();
+        assert!(velocity.linvel.normalize().abs_diff_eq(heading, 1e-4));
+        // Physics may already have moved it on by one step.
+        let moved = transform.translation.truncate() - muzzle;
+        assert!(moved.perp_dot(heading).abs() < 1e-3);
+        assert!(moved.dot(heading) >= -1e-3 && moved.dot(heading) <= velocity.linvel.length() * FIXED_DELTA + 1e-3);
+    }
+
+    #[test]
+    fn the_smallest_meteors_break_up_completely() {
+        let mut app = test_app();
+        start_game(&mut app, 1);
+        let position = Vec2::new(200.0, 0.0);
+        spawn_meteor(&mut app, 2, position);
+        spawn_bullet_at(&mut app, position);
+
+        run_until(&mut app, |app| meteor_sizes(app).is_empty());
+        app.update();
+        assert!(meteor_sizes(&mut app).is_empty());
+        assert_eq!(game(&mut app).score, 1);
+    }
+
+    #[test]
+    fn meteor_hits_cost_a_life_each_until_the_game_is_over() {
+        let mut app = test_app();
+        start_game(&mut app, 1);
+        // Sat where the ship respawns, so every new ship flies straight into it.
+        spawn_meteor(&mut app, 8, Vec2::ZERO);
+
+        let mut lives_seen = vec![game(&mut app).lives];
+        run_until(&mut app, |app| {
+            let lives = game(app).lives;
+            if lives_seen.last() != Some(&lives) {
+                lives_seen.push(lives);
+            }
+            *app.world.resource::<State<AppState>>().current() == AppState::GameOver
+        });
+        assert_eq!(lives_seen, vec![3, 2, 1, 0]);
+        // Leaving the game clears it away.
+        assert!(matches!(game(&mut app).gameState, GameState::Waiting));
+        assert_eq!(ship_count(&mut app), 0);
+        assert!(meteor_sizes(&mut app).is_empty());
+    }
+
+    #[test]
+    fn a_ship_hit_by_two_meteors_at_once_only_loses_one_life() {
+        let mut app = test_app();
+        start_game(&mut app, 1);
+        apply_commands(&mut app, |commands| {
+            commands.spawn_meteor(MeteorSpec::large());
+            commands.spawn_meteor(MeteorSpec::large());
+        });
+
+        run_until(&mut app, |app| game(app).lives != 3);
+        assert_eq!(game(&mut app).lives, 2);
+    }
+
+    #[test]
+    fn hardcore_games_end_on_the_first_hit() {
+        let options = LaunchOptions { mode: GameMode::Hardcore, ..default() };
+        let mut app = headless_app(options, TEST_SEED);
+        app.update();
+        start_game(&mut app, 1);
+        assert_eq!(game(&mut app).lives, 1);
+        spawn_meteor(&mut app, 8, Vec2::ZERO);
+
+        run_until(&mut app, |app| *app.world.resource::<State<AppState>>().current() == AppState::GameOver);
+    }
+
+    #[test]
+    fn zen_games_respawn_without_losing_lives() {
+        let options = LaunchOptions { mode: GameMode::Zen, ..default() };
+        let mut app = headless_app(options, TEST_SEED);
+        app.update();
+        start_game(&mut app, 1);
+        spawn_meteor(&mut app, 8, Vec2::ZERO);
+
+        // Sat on the respawn point, the meteor keeps destroying each new ship.
+        let mut ships_lost = 0;
+        let mut had_ship = true;
+        run_until(&mut app, |app| {
+            let has_ship = ship_count(app) > 0;
+            if had_ship && !has_ship {
+                ships_lost += 1;
+            }
+            had_ship = has_ship;
+            ships_lost >= 3
+        });
+        assert_eq!(game(&mut app).lives, 3);
+        assert!(matches!(game(&mut app).gameState, GameState::InProgress));
+    }
+
+    #[test]
+    fn custom_games_play_by_their_own_rules() {
+        let rules = CustomRules { lives: 7, meteors: 4, ..default() };
+        let options = LaunchOptions { mode: GameMode::Custom(rules), ..default() };
+        let mut app = headless_app(options, TEST_SEED);
+        app.update();
+        start_game(&mut app, 1);
+        assert_eq!(game(&mut app).lives, 7);
+
+        run_until(&mut app, |app| app.world.resource::<WaveState>().number == 1);
+        app.update();
+        assert_eq!(meteor_sizes(&mut app).len(), 4);
+    }
+
+    #[test]
+    fn scripted_waves_spawn_on_their_timeline() {
+        let mut app = test_app();
+        let spawn = |at: f32, size: u8, position: Vec2| ScriptedSpawn {
+            at,
+            spawn: Spawnable::Meteor { size },
+            position,
+            velocity: Vec2::ZERO,
+        };
+        app.world.resource_mut::<WaveScripts>().add(WaveScript {
+            wave: 1,
+            spawns: vec![spawn(1.0, 4, Vec2::new(-0.5, -0.5)), spawn(0.0, 2, Vec2::new(0.5, 0.5))],
+        });
+        start_game(&mut app, 1);
+
+        run_until(&mut app, |app| !meteor_sizes(app).is_empty());
+        assert_eq!(meteor_sizes(&mut app), vec![2]);
+        run_until(&mut app, |app| meteor_sizes(app).len() == 2);
+        assert_eq!(meteor_sizes(&mut app), vec![2, 4]);
+        assert_eq!(app.world.resource::<WaveState>().number, 1);
+    }
+
+    #[test]
+    fn races_end_once_every_checkpoint_is_flown_through() {
+        let options = LaunchOptions { mode: GameMode::Race, ..default() };
+        let mut app = headless_app(options, TEST_SEED);
+        app.update();
+        start_game(&mut app, 1);
+
+        // The ship is dropped onto each ring left for a few frames at a time, only the next one counts.
+        let mut frame = 0;
+        run_until(&mut app, |app| {
+            let rings: Vec<Vec2> = app
+                .world
+                .query_filtered::<&Transform, With<Checkpoint>>()
+                .iter(&app.world)
+                .map(|transform| transform.translation.truncate())
+                .collect();
+            if !rings.is_empty() {
+                let ring = rings[frame / 3 % rings.len()];
+                let mut query_ship = app.world.query_filtered::<&mut Transform, With<Spaceship>>();
+                for mut transform in query_ship.iter_mut(&mut app.world) {
+                    transform.translation = ring.extend(0.0);
+                }
+            }
+            frame += 1;
+            *app.world.resource::<State<AppState>>().current() == AppState::GameOver
+        });
+        // Finished rather than out of lives.
+        assert_eq!(game(&mut app).lives, 3);
+    }
+
+    #[test]
+    fn separate_lives_only_cost_the_player_that_was_hit() {
+        let mut game = Game {
+            score: INITIAL_SCORE,
+            player_scores: [INITIAL_SCORE; 2],
+            lives: 2,
+            player_lives: [1; 2],
+            shared_lives: false,
+            players: 2,
+            gameState: GameState::InProgress
+        };
+        assert!(!game.lose_life(0));
+        assert_eq!(game.player_lives, [0, 1]);
+        assert_eq!(game.lives, 1);
+        assert!(!game.lose_life(1));
+        assert_eq!(game.lives, 0);
+    }
+}
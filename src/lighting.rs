@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+
+use crate::{Lifetime, Meteor, MeteorDestroyedEvent, ShipDestroyedEvent};
+
+// Meteors sit a little below full brightness so nearby lights have room to brighten them.
+const AMBIENT_LIGHT: f32 = 0.7;
+const MAX_BRIGHTNESS: f32 = 1.4;
+const EXPLOSION_LIGHT_COLOUR: Color = Color::rgb(1.0, 0.7, 0.4);
+const EXPLOSION_LIGHT_DURATION: f32 = 0.5;
+const EXPLOSION_RADIUS_PER_SIZE: f32 = 40.0;
+const SHIP_EXPLOSION_RADIUS: f32 = 500.0;
+
+// Brightens meteors within `radius`, falling off to nothing at the edge. Lights with a
+// Lifetime fade out as it runs down.
+#[derive(Component, Clone, Copy)]
+pub struct PointLight2d {
+    pub colour: Color,
+    pub radius: f32,
+    pub intensity: f32,
+}
+
+pub struct Lighting;
+
+impl Plugin for Lighting {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(spawn_explosion_lights)
+            .add_system(light_meteors.after(spawn_explosion_lights));
+    }
+}
+
+fn spawn_explosion_lights(
+    mut commands: Commands,
+    mut meteor_event: EventReader<MeteorDestroyedEvent>,
+    mut ship_event: EventReader<ShipDestroyedEvent>,
+) {
+    let explosions = meteor_event
+        .iter()
+        .map(|ev| (ev.position, ev.size as f32 * EXPLOSION_RADIUS_PER_SIZE))
+        .chain(ship_event.iter().map(|ev| (ev.position, SHIP_EXPLOSION_RADIUS)));
+    for (position, radius) in explosions {
+        commands
+            .spawn_bundle(TransformBundle::from(Transform::from_translation(position.extend(0.0))))
+            .insert(PointLight2d {
+                colour: EXPLOSION_LIGHT_COLOUR,
+                radius,
+                intensity: 1.0,
+            })
+            .insert(Lifetime(Timer::from_seconds(EXPLOSION_LIGHT_DURATION, false)));
+    }
+}
+
+fn light_meteors(
+    query_lights: Query<(&PointLight2d, &GlobalTransform, Option<&Lifetime>)>,
+    mut query_meteor: Query<(&GlobalTransform, &mut TextureAtlasSprite), With<Meteor>>,
+) {
+    for (meteor_transform, mut sprite) in query_meteor.iter_mut() {
+        let position = meteor_transform.translation().truncate();
+        let mut light = Vec3::splat(AMBIENT_LIGHT);
+        for (point_light, light_transform, lifetime) in query_lights.iter() {
+            let distance = light_transform.translation().truncate().distance(position);
+            let falloff = (1.0 - distance / point_light.radius).max(0.0);
+            let fade = lifetime.map(|lifetime| 1.0 - lifetime.0.percent()).unwrap_or(1.0);
+            let strength = point_light.intensity * falloff * falloff * fade;
+            let colour = point_light.colour;
+            light += Vec3::new(colour.r(), colour.g(), colour.b()) * strength;
+        }
+        let light = light.min(Vec3::splat(MAX_BRIGHTNESS));
+        sprite.color = Color::rgb(light.x, light.y, light.z);
+    }
+}
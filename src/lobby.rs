@@ -0,0 +1,239 @@
+// Where an online game is set up when the game is launched with --lobby. One player hosts as
+// player 1 and the other joins as player 2, each typing in the port to play on and the other
+// player's address, since there's no matchmaking server to hand out room codes. Pressing Ready
+// starts looking for the other player, and once both are ready GGRS synchronises the two games
+// and netplay.rs starts the match. The port after the one typed in is used too, for checking the
+// two games stay in sync.
+use std::net::SocketAddr;
+
+use bevy::prelude::*;
+use ggrs::{P2PSession, SessionState};
+
+use crate::launch::NetplayOptions;
+use crate::menu::{spawn_menu_button, spawn_menu_root, spawn_menu_title, MenuButton, MENU_TEXT};
+use crate::netplay::{end_session, GgrsConfig, NetplayConnection};
+use crate::AppState;
+use crate::ui::set_text;
+
+const DEFAULT_PORT: &str = "7000";
+const ERROR_COLOUR: Color = Color::rgb(1.0, 0.3, 0.3);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LobbyField {
+    Port,
+    Address,
+}
+
+#[derive(Component, Clone, Copy)]
+enum LobbyButton {
+    Role,
+    Field(LobbyField),
+    Ready,
+}
+
+struct LobbyForm {
+    // 0 hosts as player 1, 1 joins as player 2.
+    local_player: usize,
+    port: String,
+    address: String,
+    editing: LobbyField,
+    ready: bool,
+    error: Option<String>,
+}
+
+impl Default for LobbyForm {
+    fn default() -> Self {
+        LobbyForm {
+            local_player: 0,
+            port: DEFAULT_PORT.to_string(),
+            address: String::new(),
+            editing: LobbyField::Address,
+            ready: false,
+            error: None,
+        }
+    }
+}
+
+impl LobbyForm {
+    fn options(&self) -> Result<NetplayOptions, String> {
+        let local_port = self.port.parse().map_err(|_| format!("{} isn't a port number", self.port))?;
+        let remote: SocketAddr = self
+            .address
+            .parse()
+            .map_err(|_| "Enter the other player's address like 192.168.1.20:7000".to_string())?;
+        Ok(NetplayOptions { local_port, remote, local_player: self.local_player, spectator: None })
+    }
+}
+
+#[derive(Component)]
+struct LobbyPlayers;
+
+#[derive(Component)]
+struct LobbyError;
+
+#[derive(Component)]
+struct LobbyScreen;
+
+pub struct Lobby;
+
+impl Plugin for Lobby {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<LobbyForm>()
+            .add_system_set(SystemSet::on_enter(AppState::Lobby).with_system(setup_lobby))
+            .add_system_set(
+                SystemSet::on_update(AppState::Lobby)
+                    .with_system(drop_failed_session.before(lobby_button_action))
+                    .with_system(lobby_button_action)
+                    .with_system(type_into_field)
+                    .with_system(update_lobby_ui.after(lobby_button_action).after(type_into_field)),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Lobby).with_system(despawn_lobby));
+    }
+}
+
+fn setup_lobby(mut commands: Commands, asset_server: Res<AssetServer>, mut form: ResMut<LobbyForm>) {
+    // Back from a match, whose session has ended.
+    form.ready = false;
+    form.error = None;
+    let text_style = TextStyle {
+        font: asset_server.load("BungeeSpice-Regular.ttf"),
+        font_size: 30.0,
+        color: MENU_TEXT,
+    };
+    spawn_menu_root(&mut commands)
+        .insert(LobbyScreen)
+        .with_children(|parent| {
+            spawn_menu_title(parent, &asset_server, "Online (Experimental)");
+            spawn_menu_button(parent, &asset_server, "").insert(LobbyButton::Role);
+            spawn_menu_button(parent, &asset_server, "").insert(LobbyButton::Field(LobbyField::Port));
+            spawn_menu_button(parent, &asset_server, "").insert(LobbyButton::Field(LobbyField::Address));
+            parent
+                .spawn_bundle(TextBundle::from_section("", text_style.clone()))
+                .insert(LobbyPlayers);
+            parent
+                .spawn_bundle(TextBundle::from_section("", TextStyle { color: ERROR_COLOUR, ..text_style }))
+                .insert(LobbyError);
+            spawn_menu_button(parent, &asset_server, "").insert(LobbyButton::Ready);
+            // Local games run outside the rollback schedule, so they can't be played from here.
+            spawn_menu_button(parent, &asset_server, "Quit").insert(MenuButton::Quit);
+        });
+}
+
+fn despawn_lobby(mut commands: Commands, query: Query<Entity, With<LobbyScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn lobby_button_action(
+    mut commands: Commands,
+    mut form: ResMut<LobbyForm>,
+    query: Query<(&Interaction, &LobbyButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            // The details can't change while the other player is being looked for.
+            LobbyButton::Role | LobbyButton::Field(_) if form.ready => {}
+            LobbyButton::Role => form.local_player = 1 - form.local_player,
+            LobbyButton::Field(field) => form.editing = *field,
+            LobbyButton::Ready if form.ready => {
+                end_session(&mut commands);
+                form.ready = false;
+            }
+            LobbyButton::Ready => match form.options() {
+                Ok(options) => {
+                    commands.insert_resource(NetplayConnection(options));
+                    form.ready = true;
+                    form.error = None;
+                }
+                Err(err) => form.error = Some(err),
+            },
+        }
+    }
+}
+
+// netplay.rs takes the connection away again if the session couldn't be started.
+fn drop_failed_session(connection: Option<Res<NetplayConnection>>, mut form: ResMut<LobbyForm>) {
+    if form.ready && connection.is_none() {
+        form.ready = false;
+        form.error = Some(format!("Could not play on port {}", form.port));
+    }
+}
+
+fn type_into_field(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut form: ResMut<LobbyForm>,
+    mut character_event: EventReader<ReceivedCharacter>,
+) {
+    let typed: String = character_event.iter().map(|ev| ev.char).filter(|c| c.is_ascii_graphic()).collect();
+    if form.ready {
+        return;
+    }
+    let backspace = keyboard_input.just_pressed(KeyCode::Back);
+    let field = match form.editing {
+        LobbyField::Port => &mut form.port,
+        LobbyField::Address => &mut form.address,
+    };
+    if backspace {
+        field.pop();
+    }
+    field.push_str(&typed);
+}
+
+fn update_lobby_ui(
+    form: Res<LobbyForm>,
+    session: Option<Res<P2PSession<GgrsConfig>>>,
+    query_buttons: Query<(&LobbyButton, &Children)>,
+    mut query_text: Query<&mut Text>,
+    query_players: Query<Entity, With<LobbyPlayers>>,
+    query_error: Query<Entity, With<LobbyError>>,
+) {
+    let cursor = |field: LobbyField| if form.editing == field && !form.ready { "_" } else { "" };
+    for (button, children) in query_buttons.iter() {
+        let label = match button {
+            LobbyButton::Role if form.local_player == 0 => "Hosting as P1".to_string(),
+            LobbyButton::Role => "Joining as P2".to_string(),
+            LobbyButton::Field(LobbyField::Port) => format!("Your Port: {}{}", form.port, cursor(LobbyField::Port)),
+            LobbyButton::Field(LobbyField::Address) => {
+                format!("Their Address: {}{}", form.address, cursor(LobbyField::Address))
+            }
+            LobbyButton::Ready if form.ready => "Cancel".to_string(),
+            LobbyButton::Ready => "Ready".to_string(),
+        };
+        set_entity_text(&mut query_text, children[0], &label);
+    }
+
+    // GGRS only hears from the other player once they're ready too, so connected means ready.
+    let remote_status = match session.map(|session| session.current_state()) {
+        Some(SessionState::Running) => "Ready",
+        Some(SessionState::Synchronizing) => "Connecting",
+        None => "Waiting",
+    };
+    let local_status = if form.ready { "Ready" } else { "Not Ready" };
+    let mut statuses = [(" (You)", local_status), ("", remote_status)];
+    if form.local_player == 1 {
+        statuses.swap(0, 1);
+    }
+    let players = statuses
+        .iter()
+        .enumerate()
+        .map(|(player, (you, status))| format!("P{}{}: {}", player + 1, you, status))
+        .collect::<Vec<_>>()
+        .join("\n");
+    for entity in query_players.iter() {
+        set_entity_text(&mut query_text, entity, &players);
+    }
+    for entity in query_error.iter() {
+        set_entity_text(&mut query_text, entity, form.error.as_deref().unwrap_or_default());
+    }
+}
+
+fn set_entity_text(query_text: &mut Query<&mut Text>, entity: Entity, value: &str) {
+    if let Ok(mut text) = query_text.get_mut(entity) {
+        set_text(&mut text, value);
+    }
+}
@@ -0,0 +1,351 @@
+use bevy::app::AppExit;
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+
+use crate::game_mode::GameMode;
+use crate::launch::LaunchOptions;
+use crate::palette::Tint;
+use crate::replay::{has_replay, WatchReplayButton};
+use crate::savegame::{has_saved_run, ContinueButton};
+use crate::transition::SlideIn;
+use crate::{AppState, StartButton, StartGameEvent};
+
+pub const MENU_BACKGROUND: Color = Color::rgba(0.0, 0.0, 0.05, 0.95);
+pub const MENU_TEXT: Color = Color::rgb(0.9, 0.9, 0.9);
+
+#[derive(Component, Clone, Copy)]
+pub enum MenuButton {
+    Modes,
+    CustomGame,
+    Settings,
+    Leaderboard,
+    Quit,
+    Back,
+}
+
+// Everything spawned for a menu screen hangs off one of these so it can be despawned in one go.
+#[derive(Component)]
+pub struct MenuScreen;
+
+#[derive(Component)]
+struct MainMenuScreen;
+
+// The mode Start and Two Players play, whichever was last picked on the mode select screen. Starts as
+// the one the game was launched with. Duels have their own button, as they're always two players.
+pub struct ModeChoice(pub GameMode);
+
+// In the order the mode select screen lists them, down the first column then the second.
+const MENU_MODES: [GameMode; 8] = [
+    GameMode::Classic,
+    GameMode::Survival,
+    GameMode::TimeAttack,
+    GameMode::Hardcore,
+    GameMode::Zen,
+    GameMode::Ricochet,
+    GameMode::Defense,
+    GameMode::Race,
+];
+
+const MODE_SELECT_COLUMNS: usize = 2;
+const MODE_SELECT_COLUMN_WIDTH: f32 = 480.0;
+
+#[derive(Component)]
+struct ModeSelectScreen;
+
+// One of the mode select screen's modes, which starts a game of it like any other start button.
+#[derive(Component)]
+struct ModeSelectButton;
+
+// Switches the mode select screen's games between one and two players.
+#[derive(Component)]
+struct PlayersButton;
+
+fn mode_label(mode: GameMode) -> String {
+    format!("Mode: {}", mode.label())
+}
+
+fn players_label(players: u8) -> String {
+    format!("Players: {}", players)
+}
+
+pub struct MainMenu;
+
+impl Plugin for MainMenu {
+    fn build(&self, app: &mut App) {
+        let mode = app.world.get_resource::<LaunchOptions>().map_or(GameMode::default(), |options| options.mode);
+        app
+            .insert_resource(ModeChoice(mode))
+            .add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(setup_main_menu))
+            .add_system_set(SystemSet::on_exit(AppState::MainMenu).with_system(despawn_main_menu))
+            .add_system_set(SystemSet::on_pause(AppState::MainMenu).with_system(hide_main_menu))
+            .add_system_set(SystemSet::on_resume(AppState::MainMenu).with_system(show_main_menu))
+            .add_system_set(SystemSet::on_enter(AppState::ModeSelect).with_system(setup_mode_select))
+            .add_system_set(SystemSet::on_exit(AppState::ModeSelect).with_system(despawn_mode_select))
+            .add_system_set(SystemSet::on_pause(AppState::ModeSelect).with_system(hide_mode_select))
+            .add_system_set(SystemSet::on_resume(AppState::ModeSelect).with_system(show_mode_select))
+            .add_system_set(
+                SystemSet::on_update(AppState::ModeSelect)
+                    .with_system(toggle_players)
+                    .with_system(remember_mode_choice),
+            )
+            .add_system(menu_button_action)
+            .add_system(enter_game_on_start);
+    }
+}
+
+pub fn spawn_menu_root<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut root = commands.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            position_type: PositionType::Absolute,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            flex_direction: FlexDirection::ColumnReverse,
+            ..default()
+        },
+        color: MENU_BACKGROUND.into(),
+        ..default()
+    });
+    root.insert(MenuScreen).insert(SlideIn::default());
+    root
+}
+
+pub fn spawn_menu_title(parent: &mut ChildBuilder, asset_server: &AssetServer, title: &str) {
+    parent
+        .spawn_bundle(
+            TextBundle::from_section(
+                title,
+                TextStyle {
+                    font: asset_server.load("BungeeSpice-Regular.ttf"),
+                    font_size: 80.0,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                margin: UiRect { bottom: Val::Px(30.0), ..default() },
+                ..default()
+            }),
+        )
+        .insert(Tint::Accent);
+}
+
+pub fn spawn_menu_button<'w, 's, 'a>(
+    parent: &'a mut ChildBuilder<'w, 's, '_>,
+    asset_server: &AssetServer,
+    label: &str,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut button = parent.spawn_bundle(ButtonBundle {
+        style: Style {
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            padding: UiRect::all(Val::Px(10.0)),
+            margin: UiRect { top: Val::Px(5.0), bottom: Val::Px(5.0), ..default() },
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    });
+    button.with_children(|parent| {
+        parent.spawn_bundle(TextBundle::from_section(
+            label,
+            TextStyle {
+                font: asset_server.load("BungeeSpice-Regular.ttf"),
+                font_size: 40.0,
+                color: MENU_TEXT,
+            },
+        ));
+    });
+    button
+}
+
+fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>, choice: Res<ModeChoice>) {
+    spawn_menu_root(&mut commands)
+        .insert(MainMenuScreen)
+        .with_children(|parent| {
+            spawn_menu_title(parent, &asset_server, "Asteroids");
+            if has_saved_run() {
+                spawn_menu_button(parent, &asset_server, "Continue").insert(ContinueButton);
+            }
+            spawn_menu_button(parent, &asset_server, "Start").insert(StartButton { players: 1, mode: None });
+            spawn_menu_button(parent, &asset_server, "Two Players").insert(StartButton { players: 2, mode: None });
+            spawn_menu_button(parent, &asset_server, &mode_label(choice.0)).insert(MenuButton::Modes);
+            spawn_menu_button(parent, &asset_server, "Duel")
+                .insert(StartButton { players: 2, mode: Some(GameMode::Duel) });
+            spawn_menu_button(parent, &asset_server, "Settings").insert(MenuButton::Settings);
+            spawn_menu_button(parent, &asset_server, "Leaderboard").insert(MenuButton::Leaderboard);
+            if has_replay() {
+                spawn_menu_button(parent, &asset_server, "Watch Best Run").insert(WatchReplayButton);
+            }
+            spawn_menu_button(parent, &asset_server, "Quit").insert(MenuButton::Quit);
+        });
+}
+
+fn despawn_main_menu(mut commands: Commands, query: Query<Entity, With<MainMenuScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Laid out in columns, as a button and a line about it for every mode is too tall for one.
+fn setup_mode_select(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let description_style = TextStyle {
+        font: asset_server.load("BungeeSpice-Regular.ttf"),
+        font_size: 20.0,
+        color: MENU_TEXT,
+    };
+    let column_style = Style {
+        size: Size::new(Val::Px(MODE_SELECT_COLUMN_WIDTH), Val::Auto),
+        flex_direction: FlexDirection::ColumnReverse,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let rows = (MENU_MODES.len() + MODE_SELECT_COLUMNS - 1) / MODE_SELECT_COLUMNS;
+    spawn_menu_root(&mut commands)
+        .insert(ModeSelectScreen)
+        .with_children(|parent| {
+            spawn_menu_title(parent, &asset_server, "Select Mode");
+            spawn_menu_button(parent, &asset_server, &players_label(1)).insert(PlayersButton);
+            parent
+                .spawn_bundle(NodeBundle { color: Color::NONE.into(), ..default() })
+                .with_children(|parent| {
+                    for column in MENU_MODES.chunks(rows) {
+                        parent
+                            .spawn_bundle(NodeBundle {
+                                style: column_style.clone(),
+                                color: Color::NONE.into(),
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                for mode in column {
+                                    spawn_menu_button(parent, &asset_server, mode.label())
+                                        .insert(StartButton { players: 1, mode: Some(*mode) })
+                                        .insert(ModeSelectButton);
+                                    parent.spawn_bundle(
+                                        TextBundle::from_section(mode.description(), description_style.clone())
+                                            .with_style(Style {
+                                                margin: UiRect { bottom: Val::Px(10.0), ..default() },
+                                                ..default()
+                                            }),
+                                    );
+                                }
+                            });
+                    }
+                });
+            spawn_menu_button(parent, &asset_server, "Custom Game").insert(MenuButton::CustomGame);
+            spawn_menu_button(parent, &asset_server, "Back").insert(MenuButton::Back);
+        });
+}
+
+fn despawn_mode_select(mut commands: Commands, query: Query<Entity, With<ModeSelectScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn hide_mode_select(mut query: Query<&mut Style, With<ModeSelectScreen>>) {
+    for mut style in query.iter_mut() {
+        style.display = Display::None;
+    }
+}
+
+fn show_mode_select(mut commands: Commands, mut query: Query<(Entity, &mut Style), With<ModeSelectScreen>>) {
+    for (entity, mut style) in query.iter_mut() {
+        style.display = Display::Flex;
+        commands.entity(entity).insert(SlideIn::default());
+    }
+}
+
+fn toggle_players(
+    query_button: Query<(&Interaction, &Children), (Changed<Interaction>, With<PlayersButton>)>,
+    mut query_start: Query<&mut StartButton, With<ModeSelectButton>>,
+    mut query_text: Query<&mut Text>,
+) {
+    for (interaction, children) in query_button.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let mut players = 1;
+        for mut start_button in query_start.iter_mut() {
+            start_button.players = if start_button.players == 1 { 2 } else { 1 };
+            players = start_button.players;
+        }
+        if let Ok(mut text) = query_text.get_mut(children[0]) {
+            text.sections[0].value = players_label(players);
+        }
+    }
+}
+
+// The picked mode is also what Start and Two Players go on to play.
+fn remember_mode_choice(
+    query: Query<(&Interaction, &StartButton), (Changed<Interaction>, With<ModeSelectButton>)>,
+    mut choice: ResMut<ModeChoice>,
+) {
+    for (interaction, start_button) in query.iter() {
+        if let (Interaction::Clicked, Some(mode)) = (interaction, start_button.mode) {
+            choice.0 = mode;
+        }
+    }
+}
+
+// Screens pushed on top hide the main menu so only their buttons can be clicked or focused.
+fn hide_main_menu(mut query: Query<&mut Style, With<MainMenuScreen>>) {
+    for mut style in query.iter_mut() {
+        style.display = Display::None;
+    }
+}
+
+fn show_main_menu(mut commands: Commands, mut query: Query<(Entity, &mut Style), With<MainMenuScreen>>) {
+    for (entity, mut style) in query.iter_mut() {
+        style.display = Display::Flex;
+        commands.entity(entity).insert(SlideIn::default());
+    }
+}
+
+// Settings and the leaderboard are pushed on top of whichever screen opened them,
+// so Back pops straight back to it.
+fn menu_button_action(
+    query: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut state: ResMut<State<AppState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for (interaction, button) in query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let result = match button {
+            MenuButton::Modes => state.push(AppState::ModeSelect),
+            MenuButton::CustomGame => state.push(AppState::CustomGame),
+            MenuButton::Settings => state.push(AppState::Settings),
+            MenuButton::Leaderboard => state.push(AppState::Leaderboard),
+            MenuButton::Back => state.pop(),
+            MenuButton::Quit => {
+                exit.send(AppExit);
+                Ok(())
+            }
+        };
+        if let Err(err) = result {
+            warn!("Menu transition failed: {:?}", err);
+        }
+    }
+}
+
+fn enter_game_on_start(
+    mut start_game_event: EventReader<StartGameEvent>,
+    mut state: ResMut<State<AppState>>,
+) {
+    if start_game_event.iter().last().is_none() {
+        return;
+    }
+    let result = match state.current() {
+        AppState::MainMenu => state.set(AppState::InGame),
+        // The mode select and custom game screens sit on top of the main menu, and all of them have to go.
+        AppState::ModeSelect | AppState::CustomGame => state.replace(AppState::InGame),
+        // Restarts from the pause or game-over screens are already inside the game.
+        _ => return,
+    };
+    if let Err(err) = result {
+        warn!("Could not start game: {:?}", err);
+    }
+}
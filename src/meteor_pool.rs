@@ -0,0 +1,86 @@
+// Meteors are recycled like bullets are, see bullets.rs, so a big meteor breaking into a cascade
+// of fragments doesn't have to build each one from scratch. A spent meteor loses the components
+// that make it a meteor to the rest of the game and waits far outside the arena with collisions
+// off until spawn_meteor takes it back, see spawning.rs. A batch is made ahead of time while the
+// game loads.
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::animation::SpriteSheets;
+use crate::launch::LaunchOptions;
+use crate::radar::RadarBlip;
+use crate::threat_indicator::Threat;
+use crate::{DestroyCommands, Meteor, Parked, ScreenWrap, PARKED_POSITION};
+
+const PREWARMED_METEORS: usize = 64;
+
+#[derive(Default)]
+pub struct MeteorPool {
+    parked: Vec<Entity>,
+    enabled: bool,
+}
+
+impl MeteorPool {
+    pub fn take(&mut self) -> Option<Entity> {
+        self.parked.pop()
+    }
+}
+
+pub struct MeteorPooling;
+
+impl Plugin for MeteorPooling {
+    fn build(&self, app: &mut App) {
+        let online = app.world.get_resource::<LaunchOptions>().map_or(false, |options| options.online());
+        app
+            .insert_resource(MeteorPool { parked: Vec::new(), enabled: !online })
+            .add_startup_system(prewarm_meteors)
+            .add_system(park_meteors);
+    }
+}
+
+// A meteor's body, the same whether it's in play or parked.
+pub fn meteor_body() -> (RigidBody, GravityScale) {
+    (RigidBody::Dynamic, GravityScale(0.0))
+}
+
+fn prewarm_meteors(mut commands: Commands, sprite_sheets: Res<SpriteSheets>, mut pool: ResMut<MeteorPool>) {
+    if !pool.enabled {
+        return;
+    }
+    for _ in 0..PREWARMED_METEORS {
+        let meteor = commands
+            .spawn_bundle(SpriteSheetBundle {
+                texture_atlas: sprite_sheets.atlas(),
+                transform: Transform::from_translation(PARKED_POSITION),
+                ..default()
+            })
+            .insert_bundle(meteor_body())
+            .insert(Velocity::zero())
+            .insert(Collider::ball(1.0))
+            .insert(CollisionGroups::new(0, 0))
+            .insert(Parked)
+            .id();
+        pool.parked.push(meteor);
+    }
+}
+
+// Radar dots and threat arrows go away by themselves once their meteor stops being one.
+pub fn park_meteors(
+    mut commands: Commands,
+    mut pool: ResMut<MeteorPool>,
+    mut query_spent: Query<(Entity, &mut Transform, &mut Velocity), (With<Meteor>, Added<Parked>)>,
+) {
+    for (meteor, mut transform, mut velocity) in query_spent.iter_mut() {
+        if !pool.enabled {
+            commands.destroy(meteor);
+            continue;
+        }
+        transform.translation = PARKED_POSITION;
+        *velocity = Velocity::zero();
+        commands
+            .entity(meteor)
+            .remove_bundle::<(Meteor, RadarBlip, Threat, ScreenWrap)>()
+            .insert(CollisionGroups::new(0, 0));
+        pool.parked.push(meteor);
+    }
+}
@@ -0,0 +1,119 @@
+// Mods, each a folder in mods/ laid out like assets/. A file in a mod is loaded in place of the game's
+// own file at the same path, so a mod can swap a sprite, a sound, config.ron or game.entities.ron just
+// by shipping its own copy. Folders are merged rather than replaced, so any .wave.ron a mod puts in
+// waves/ plays alongside the game's, and any .palette.ron in palettes/ is offered in the settings
+// next to the built-in palettes, see palette.rs. Mods are applied in alphabetical order of their
+// folder names, or in the order they're listed in mods/load_order.txt, one to a line, which also
+// leaves out any that aren't listed. Where two mods have the same file the one applied later wins.
+// Mods are only looked for at startup. Bevy only watches the files it reads itself, so assets aren't
+// reloaded when they're edited while any mods are in use. Online games don't check both sides have
+// the same mods, and browsers can't list folders, so there are no mods on the web.
+use std::path::{Path, PathBuf};
+
+use bevy::asset::{create_platform_default_asset_io, AssetIo, AssetIoError, BoxedFuture, FileAssetIo, Metadata};
+use bevy::prelude::*;
+use bevy::tasks::futures_lite::future;
+
+const MODS_FOLDER: &str = "mods";
+const LOAD_ORDER_FILE: &str = "load_order.txt";
+
+struct ModAssetIo {
+    base: Box<dyn AssetIo>,
+    // The mod applied last first, since its files win.
+    mods: Vec<FileAssetIo>,
+}
+
+impl ModAssetIo {
+    fn source(&self, path: &Path) -> &dyn AssetIo {
+        self.mods
+            .iter()
+            .find(|mod_io| mod_io.is_file(path))
+            .map_or(&*self.base, |mod_io| mod_io as &dyn AssetIo)
+    }
+}
+
+impl AssetIo for ModAssetIo {
+    fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+        self.source(path).load_path(path)
+    }
+
+    // Everything that's in the folder in the game or in any of the mods.
+    fn read_directory(&self, path: &Path) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+        let base = self.base.read_directory(path);
+        let mut entries: Vec<PathBuf> = Vec::new();
+        for mod_io in self.mods.iter() {
+            if let Ok(mod_entries) = mod_io.read_directory(path) {
+                entries.extend(mod_entries);
+            }
+        }
+        match base {
+            Ok(base_entries) => entries.extend(base_entries),
+            Err(err) if entries.is_empty() => return Err(err),
+            Err(_) => {}
+        }
+        entries.sort();
+        entries.dedup();
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn get_metadata(&self, path: &Path) -> Result<Metadata, AssetIoError> {
+        self.mods
+            .iter()
+            .find_map(|mod_io| mod_io.get_metadata(path).ok())
+            .map_or_else(|| self.base.get_metadata(path), Ok)
+    }
+
+    fn watch_path_for_changes(&self, path: &Path) -> Result<(), AssetIoError> {
+        self.base.watch_path_for_changes(path)
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        self.base.watch_for_changes()
+    }
+}
+
+// The mods to apply, in the order they're applied.
+fn load_order(mods_io: &FileAssetIo) -> Vec<String> {
+    if let Ok(bytes) = future::block_on(mods_io.load_path(Path::new(LOAD_ORDER_FILE))) {
+        return String::from_utf8_lossy(&bytes)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect();
+    }
+    let mut names: Vec<String> = match mods_io.read_directory(Path::new("")) {
+        Ok(entries) => entries
+            .filter(|entry| mods_io.is_dir(entry))
+            .filter_map(|entry| entry.to_str().map(String::from))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    names.sort();
+    names
+}
+
+// Has to be added before the AssetPlugin in DefaultPlugins, which only makes its own AssetServer if
+// there isn't one already.
+pub struct Mods;
+
+impl Plugin for Mods {
+    fn build(&self, app: &mut App) {
+        let mods_io = FileAssetIo::new(MODS_FOLDER, false);
+        let mut mods = Vec::new();
+        for name in load_order(&mods_io) {
+            if !mods_io.is_dir(Path::new(&name)) {
+                warn!("There's no mod called {} in the {} folder", name, MODS_FOLDER);
+                continue;
+            }
+            info!("Loading the {} mod", name);
+            mods.push(FileAssetIo::new(Path::new(MODS_FOLDER).join(&name), false));
+        }
+        if mods.is_empty() {
+            return;
+        }
+        mods.reverse();
+        let base = create_platform_default_asset_io(app);
+        app.insert_resource(AssetServer::new(ModAssetIo { base, mods }));
+    }
+}
@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+
+use crate::audio::{load_sound, MusicChannel};
+use crate::threat_indicator::Threat;
+use crate::config::GameConfig;
+use crate::game_mode::GameMode;
+use crate::{AppState, Game, ShipDestroyedEvent};
+
+const CROSSFADE_DURATION: f32 = 1.5;
+const DUCK_DURATION: f32 = 1.5;
+const DUCK_VOLUME: f32 = 0.3;
+const DANGER_INTERVAL: f32 = 1.0;
+// This many threats on screen at once counts as the field being as dangerous as it gets.
+const MAX_DANGER_THREATS: f32 = 12.0;
+
+// 0.0 is a calm field with plenty of lives, 1.0 is a crowded field on the last life.
+#[derive(Default)]
+pub struct DangerLevel(pub f32);
+
+struct MusicTrack {
+    instance: Handle<AudioInstance>,
+    volume: f32,
+}
+
+// All tracks loop for the whole session and are crossfaded by volume rather than restarted,
+// which also keeps the gameplay stems in time with each other.
+struct Music {
+    menu: MusicTrack,
+    game: MusicTrack,
+    // Gameplay layers that fade in once danger passes their threshold.
+    stems: Vec<(MusicTrack, f32)>,
+    duck: Timer,
+    danger_timer: Timer,
+}
+
+pub struct BackgroundMusic;
+
+impl Plugin for BackgroundMusic {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<DangerLevel>()
+            .add_startup_system(start_music)
+            .add_system(update_danger_level)
+            .add_system(duck_on_ship_destroyed)
+            .add_system(
+                crossfade_music
+                    .after(duck_on_ship_destroyed)
+                    .after(update_danger_level),
+            );
+    }
+}
+
+fn start_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    music_channel: Res<AudioChannel<MusicChannel>>,
+    mut audio_sources: ResMut<Assets<AudioSource>>,
+) {
+    let mut play_looping = |path: &str| MusicTrack {
+        instance: music_channel
+            .play(load_sound(&asset_server, &mut audio_sources, path))
+            .looped()
+            .with_volume(0.0)
+            .handle(),
+        volume: 0.0,
+    };
+    let menu = play_looping("music/menu.wav");
+    let game = play_looping("music/game.wav");
+    let stems = vec![
+        (play_looping("music/game_drums.wav"), 0.3),
+        (play_looping("music/game_lead.wav"), 0.65),
+    ];
+
+    let mut duck = Timer::from_seconds(DUCK_DURATION, false);
+    duck.tick(duck.duration());
+    commands.insert_resource(Music {
+        menu,
+        game,
+        stems,
+        duck,
+        danger_timer: Timer::from_seconds(DANGER_INTERVAL, true),
+    });
+}
+
+fn update_danger_level(
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    mode: Res<GameMode>,
+    query_game: Query<&Game>,
+    query_threats: Query<(), With<Threat>>,
+    mut music: ResMut<Music>,
+    mut danger: ResMut<DangerLevel>,
+) {
+    if !music.danger_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let game = query_game.single();
+    let crowding = (query_threats.iter().count() as f32 / MAX_DANGER_THREATS).min(1.0);
+    // Modes without spare lives are never closer to losing than they started.
+    let spare_lives = mode.initial_lives(&config).saturating_sub(1) as f32;
+    let lives_lost = if spare_lives > 0.0 {
+        1.0 - (game.lives.saturating_sub(1) as f32 / spare_lives).min(1.0)
+    } else {
+        0.0
+    };
+    danger.0 = (crowding * 0.7 + lives_lost * 0.3).clamp(0.0, 1.0);
+}
+
+fn duck_on_ship_destroyed(
+    mut destroyed_event: EventReader<ShipDestroyedEvent>,
+    mut music: ResMut<Music>,
+) {
+    if destroyed_event.iter().last().is_some() {
+        music.duck.reset();
+    }
+}
+
+fn fade_towards(track: &mut MusicTrack, target: f32, step: f32) {
+    track.volume = if track.volume < target {
+        (track.volume + step).min(target)
+    } else {
+        (track.volume - step).max(target)
+    };
+}
+
+fn crossfade_music(
+    time: Res<Time>,
+    state: Res<State<AppState>>,
+    mode: Res<GameMode>,
+    danger: Res<DangerLevel>,
+    mut music: ResMut<Music>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    // Ambient modes keep the menu's music going in game.
+    let in_game = matches!(state.current(), AppState::InGame | AppState::Paused) && !mode.rules().ambient_music;
+    let step = time.delta_seconds() / CROSSFADE_DURATION;
+
+    // Reborrow once so the tracks can be borrowed mutably at the same time.
+    let music = &mut *music;
+    music.duck.tick(time.delta());
+    // The channel itself carries the music volume setting, this only handles fades and ducking.
+    let duck = if music.duck.finished() { 1.0 } else { DUCK_VOLUME };
+
+    let on = |enabled: bool| if enabled { 1.0 } else { 0.0 };
+    fade_towards(&mut music.menu, on(!in_game), step);
+    fade_towards(&mut music.game, on(in_game), step);
+    for (stem, threshold) in music.stems.iter_mut() {
+        fade_towards(stem, on(in_game && danger.0 >= *threshold), step);
+    }
+
+    let tracks = [&music.menu, &music.game].into_iter().chain(music.stems.iter().map(|(stem, _)| stem));
+    for track in tracks {
+        if let Some(instance) = audio_instances.get_mut(&track.instance) {
+            instance.set_volume((track.volume * duck) as f64, AudioTween::default());
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+use bevy::ui::UiSystem;
+
+use crate::palette::Tint;
+use crate::AppState;
+
+const UNFOCUSED_BUTTON: Color = Color::NONE;
+
+// The button arrow keys / d-pad are currently sitting on.
+#[derive(Component)]
+pub struct Focused;
+
+enum NavAction {
+    Previous,
+    Next,
+    Activate,
+}
+
+pub struct MenuNavigation;
+
+impl Plugin for MenuNavigation {
+    fn build(&self, app: &mut App) {
+        // Activating a button sets its Interaction before anything in Update looks for clicks, and it's
+        // let go of after all of them, so each sees the click exactly once.
+        app
+            .add_system_to_stage(CoreStage::PreUpdate, navigate_menus.after(UiSystem::Focus))
+            .add_system_to_stage(CoreStage::Last, release_activated_button)
+            .add_system(highlight_focus);
+    }
+}
+
+fn read_nav_action(
+    keyboard_input: &Input<KeyCode>,
+    gamepads: &Gamepads,
+    gamepad_input: &Input<GamepadButton>,
+) -> Option<NavAction> {
+    let pad = |button_type: GamepadButtonType| {
+        gamepads
+            .iter()
+            .any(|gamepad| gamepad_input.just_pressed(GamepadButton(*gamepad, button_type)))
+    };
+    if keyboard_input.any_just_pressed([KeyCode::Up, KeyCode::Left])
+        || pad(GamepadButtonType::DPadUp)
+        || pad(GamepadButtonType::DPadLeft)
+    {
+        Some(NavAction::Previous)
+    } else if keyboard_input.any_just_pressed([KeyCode::Down, KeyCode::Right, KeyCode::Tab])
+        || pad(GamepadButtonType::DPadDown)
+        || pad(GamepadButtonType::DPadRight)
+    {
+        Some(NavAction::Next)
+    } else if keyboard_input.any_just_pressed([KeyCode::Return, KeyCode::NumpadEnter])
+        || pad(GamepadButtonType::South)
+    {
+        Some(NavAction::Activate)
+    } else {
+        None
+    }
+}
+
+fn navigate_menus(
+    mut commands: Commands,
+    state: Res<State<AppState>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    query_buttons: Query<(Entity, &GlobalTransform, &Node), With<Button>>,
+    query_focused: Query<Entity, With<Focused>>,
+    mut query_interaction: Query<&mut Interaction>,
+) {
+    // The arrow keys fly the ship during play.
+    if *state.current() == AppState::InGame {
+        return;
+    }
+    let action = match read_nav_action(&keyboard_input, &gamepads, &gamepad_input) {
+        Some(action) => action,
+        None => return,
+    };
+
+    // Buttons on screens hidden underneath the current one have no size, top to bottom then left to right.
+    let mut buttons: Vec<(Entity, Vec3)> = query_buttons
+        .iter()
+        .filter(|(_, _, node)| node.size != Vec2::ZERO)
+        .map(|(entity, transform, _)| (entity, transform.translation()))
+        .collect();
+    if buttons.is_empty() {
+        return;
+    }
+    buttons.sort_by(|(_, a), (_, b)| b.y.total_cmp(&a.y).then(a.x.total_cmp(&b.x)));
+
+    let focused = query_focused.iter().next();
+    let index = focused.and_then(|focused| buttons.iter().position(|(entity, _)| *entity == focused));
+
+    let next = match (action, index) {
+        (NavAction::Activate, Some(index)) => {
+            if let Ok(mut interaction) = query_interaction.get_mut(buttons[index].0) {
+                *interaction = Interaction::Clicked;
+            }
+            return;
+        }
+        (NavAction::Activate, None) | (NavAction::Next, None) | (NavAction::Previous, None) => 0,
+        (NavAction::Next, Some(index)) => (index + 1) % buttons.len(),
+        (NavAction::Previous, Some(index)) => (index + buttons.len() - 1) % buttons.len(),
+    };
+
+    if let Some(focused) = focused {
+        commands.entity(focused).remove::<Focused>();
+    }
+    commands.entity(buttons[next].0).insert(Focused);
+}
+
+// A keyboard "click" has no mouse release to end it, so let it go at the end of the frame.
+fn release_activated_button(
+    mouse_input: Res<Input<MouseButton>>,
+    mut query: Query<&mut Interaction, With<Focused>>,
+) {
+    if mouse_input.pressed(MouseButton::Left) {
+        return;
+    }
+    for mut interaction in query.iter_mut() {
+        if *interaction == Interaction::Clicked {
+            *interaction = Interaction::None;
+        }
+    }
+}
+
+// The focused colour comes from the palette. Tints keep the alpha they find and unfocused buttons are
+// see-through, so the button is made opaque first.
+fn highlight_focus(
+    mut commands: Commands,
+    removed: RemovedComponents<Focused>,
+    mut query_added: Query<(Entity, &mut UiColor), Added<Focused>>,
+    mut query_colors: Query<&mut UiColor, Without<Focused>>,
+) {
+    for (entity, mut color) in query_added.iter_mut() {
+        *color = Color::WHITE.into();
+        commands.entity(entity).insert(Tint::Focus);
+    }
+    for entity in removed.iter() {
+        if let Ok(mut color) = query_colors.get_mut(entity) {
+            commands.entity(entity).remove::<Tint>();
+            *color = UNFOCUSED_BUTTON.into();
+        }
+    }
+}
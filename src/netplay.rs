@@ -0,0 +1,511 @@
+// Online two-player games over GGRS rollback networking. Each copy of the game only sends its own
+// player's held controls, packed as in ControlScheme::held. GGRS predicts the other player's
+// controls until they arrive and, when a prediction was wrong, rewinds the rollback components
+// and steps the game forward again. That's why physics and the core gameplay systems run in the
+// rollback schedule here, on the fixed timestep from deterministic.rs.
+//
+// The Game, ships' weapons, the run's shot and hit counts and the gameplay RNG are rewound along
+// with transforms and velocities, the resources by way of NetplayFrame. This is still experimental:
+// waves, meteor splits and respawns are spawned with SpawnCommands by systems outside the rollback
+// schedule, so the two games can drift apart. To notice when they do, each machine sends the other
+// a checksum of every confirmed frame from the port after its GGRS one, and the first frame whose
+// checksums differ is reported. Spectators are sent the confirmed inputs by one of the players and
+// simulate the game the same way, just without ever having to roll back.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::net::{SocketAddr, UdpSocket};
+
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::{ButtonState, InputSystem};
+use bevy::prelude::*;
+use bevy_ggrs::{GGRSPlugin, PlayerInputs, Rollback, RollbackIdProvider, SessionType};
+use bevy_rapier2d::prelude::*;
+use ggrs::{
+    Config, P2PSession, PlayerHandle, PlayerType, SessionBuilder, SessionState, SpectatorSession, UdpNonBlockingSocket,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::bullets::Ricochets;
+use crate::deterministic::Deterministic;
+use crate::input::{ControlScheme, ARROWS};
+use crate::launch::{LaunchOptions, NetplayOptions, SpectateOptions};
+use crate::replay::GameRng;
+use crate::spectator::Spectating;
+use crate::toast::ToastEvent;
+use crate::{
+    despawn_dead, AppState, Bullet, Dead, Game, Meteor, RunStats, Spaceship, StartGameEvent, Weapon, PIXELS_PER_METER,
+};
+
+const NETPLAY_FPS: usize = 60;
+const INPUT_DELAY: usize = 2;
+// GGRS numbers spectators on from the players.
+const SPECTATOR_HANDLE: usize = 2;
+// Both machines have to simulate the same game, so unless --seed says otherwise they use this.
+const NETPLAY_SEED: u64 = 0x5eed;
+const CONTROLS_STAGE: &str = "netplay_controls";
+const GAMEPLAY_STAGE: &str = "netplay_gameplay";
+const CHECKSUM_STAGE: &str = "netplay_checksum";
+// How many frames of checksums are kept waiting for the other machine's, which may never arrive.
+const KEPT_CHECKSUMS: u32 = 600;
+
+pub struct GgrsConfig;
+
+impl Config for GgrsConfig {
+    // Held controls, see ControlScheme::held.
+    type Input = u8;
+    type State = u8;
+    type Address = SocketAddr;
+}
+
+// The keys held on this machine. Input<KeyCode> can't tell us that once the rollback schedule
+// has pressed both players' keys in it.
+#[derive(Default)]
+pub struct LocalKeys(pub Input<KeyCode>);
+
+// Rewound with everything else, so replayed frames see the same key presses as the first time.
+// It also carries what the rollback schedule changes in resources, which GGRS can't rewind.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+struct NetplayFrame {
+    number: u32,
+    previous_controls: [u8; 2],
+    shots_fired: u32,
+    hits: u32,
+    // GameRng is reseeded from this at the start of every frame.
+    rng_seed: u64,
+}
+
+// Checksums of frames GGRS hasn't confirmed yet, overwritten if a frame gets simulated again.
+#[derive(Default)]
+struct PendingChecksums(BTreeMap<u32, u64>);
+
+// Swaps confirmed checksums with the other player to catch the games drifting apart.
+struct ChecksumExchange {
+    socket: UdpSocket,
+    remote: SocketAddr,
+    local: BTreeMap<u32, u64>,
+    received: BTreeMap<u32, u64>,
+    desynced: bool,
+}
+
+// Who to play, set from --netplay or by the lobby. The session starts as soon as it's inserted.
+pub struct NetplayConnection(pub NetplayOptions);
+
+// There while a match is being played, so the next session can start another.
+struct NetplayMatch;
+
+// Without a connection yet, the lobby inserts one once the player is ready.
+pub fn add_rollback_gameplay(app: &mut App, options: &LaunchOptions, gameplay: SystemSet) {
+    if app.world.get_resource::<Deterministic>().is_none() {
+        app.insert_resource(Deterministic { seed: options.seed.unwrap_or(NETPLAY_SEED) });
+    }
+    app.add_plugin(
+        RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(PIXELS_PER_METER).with_default_system_setup(false),
+    );
+
+    GGRSPlugin::<GgrsConfig>::new()
+        .with_update_frequency(NETPLAY_FPS)
+        .with_input_system(local_input)
+        .register_rollback_type::<Transform>()
+        .register_rollback_type::<Velocity>()
+        .register_rollback_type::<Ricochets>()
+        .register_rollback_type::<NetplayFrame>()
+        .register_rollback_type::<Game>()
+        .register_rollback_type::<Weapon>()
+        .with_rollback_schedule(
+            Schedule::default()
+                .with_stage(
+                    CONTROLS_STAGE,
+                    SystemStage::single_threaded()
+                        .with_system(apply_controls)
+                        .with_system(restore_resources.after(apply_controls)),
+                )
+                .with_stage(GAMEPLAY_STAGE, SystemStage::single_threaded().with_system_set(gameplay))
+                .with_stage(
+                    PhysicsStages::SyncBackend,
+                    SystemStage::single_threaded()
+                        .with_system_set(RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsStages::SyncBackend)),
+                )
+                .with_stage(
+                    PhysicsStages::StepSimulation,
+                    SystemStage::single_threaded().with_system_set(RapierPhysicsPlugin::<NoUserData>::get_systems(
+                        PhysicsStages::StepSimulation,
+                    )),
+                )
+                .with_stage(
+                    PhysicsStages::Writeback,
+                    SystemStage::single_threaded()
+                        .with_system_set(RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsStages::Writeback)),
+                )
+                .with_stage(
+                    CHECKSUM_STAGE,
+                    SystemStage::single_threaded()
+                        .with_system(save_resources)
+                        .with_system(record_checksum.after(save_resources)),
+                ),
+        )
+        .build(app);
+
+    if let Some(netplay) = options.netplay {
+        app.insert_resource(NetplayConnection(netplay));
+    }
+    if let Some(spectate) = options.spectate {
+        app
+            .insert_resource(spectate)
+            .add_plugin(Spectating)
+            .add_startup_system(start_spectating);
+    }
+    app
+        .init_resource::<LocalKeys>()
+        .init_resource::<PendingChecksums>()
+        .add_stage_before(
+            CoreStage::Last,
+            PhysicsStages::DetectDespawn,
+            SystemStage::parallel()
+                .with_system_set(RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsStages::DetectDespawn)),
+        )
+        .add_system(start_session)
+        .add_system_to_stage(CoreStage::PreUpdate, track_local_keys.after(InputSystem))
+        .add_system(log_network_events)
+        .add_system(start_netplay_game)
+        .add_system(report_checksums)
+        .add_system_set(SystemSet::on_exit(AppState::InGame).with_system(leave_netplay_game))
+        // Before despawn_dead, so nothing is left to insert Rollback on once it's gone.
+        .add_system_to_stage(CoreStage::PostUpdate, tag_rollback_entities.before(despawn_dead));
+}
+
+fn start_session(
+    mut commands: Commands,
+    connection: Option<Res<NetplayConnection>>,
+    deterministic: Res<Deterministic>,
+    mut rip: ResMut<RollbackIdProvider>,
+    query_frame: Query<(), With<NetplayFrame>>,
+) {
+    let netplay = match connection.filter(|connection| connection.is_added()) {
+        Some(connection) => connection.0,
+        None => return,
+    };
+    let players = [PlayerType::Local, PlayerType::Remote(netplay.remote)];
+    let remote_player = 1 - netplay.local_player;
+    let session = UdpNonBlockingSocket::bind_to_port(netplay.local_port)
+        .map_err(|err| err.to_string())
+        .and_then(|socket| {
+            SessionBuilder::<GgrsConfig>::new()
+                .with_num_players(2)
+                .with_input_delay(INPUT_DELAY)
+                .add_player(players[0].clone(), netplay.local_player)
+                .and_then(|builder| builder.add_player(players[1].clone(), remote_player))
+                .and_then(|builder| match netplay.spectator {
+                    Some(spectator) => builder.add_player(PlayerType::Spectator(spectator), SPECTATOR_HANDLE),
+                    None => Ok(builder),
+                })
+                .and_then(|builder| builder.start_p2p_session(socket))
+                .map_err(|err| err.to_string())
+        });
+    match session {
+        Ok(session) => {
+            info!("Waiting for player {} at {}", remote_player + 1, netplay.remote);
+            commands.insert_resource(session);
+            commands.insert_resource(SessionType::P2PSession);
+            if query_frame.is_empty() {
+                spawn_frame(&mut commands, &mut rip, &deterministic);
+            }
+            match bind_checksum_exchange(&netplay) {
+                Ok(exchange) => commands.insert_resource(exchange),
+                Err(err) => warn!("Could not open port {} to check for desyncs: {}", netplay.local_port + 1, err),
+            }
+        }
+        Err(err) => {
+            warn!("Could not start netplay: {}", err);
+            commands.remove_resource::<NetplayConnection>();
+        }
+    }
+}
+
+// Spectators follow along with the inputs the host sends them and never roll back.
+fn start_spectating(
+    mut commands: Commands,
+    spectate: Res<SpectateOptions>,
+    deterministic: Res<Deterministic>,
+    mut rip: ResMut<RollbackIdProvider>,
+) {
+    match UdpNonBlockingSocket::bind_to_port(spectate.local_port) {
+        Ok(socket) => {
+            info!("Waiting to watch the game hosted at {}", spectate.host);
+            let session = SessionBuilder::<GgrsConfig>::new()
+                .with_num_players(2)
+                .start_spectator_session(spectate.host, socket);
+            commands.insert_resource(session);
+            commands.insert_resource(SessionType::SpectatorSession);
+            spawn_frame(&mut commands, &mut rip, &deterministic);
+        }
+        Err(err) => warn!("Could not start spectating: {}", err),
+    }
+}
+
+fn spawn_frame(commands: &mut Commands, rip: &mut RollbackIdProvider, deterministic: &Deterministic) {
+    commands
+        .spawn()
+        .insert(NetplayFrame { rng_seed: deterministic.seed, ..default() })
+        .insert(Rollback::new(rip.next_id()));
+}
+
+// Checksums go between the ports after each machine's GGRS port, e.g. 7001 for a game on 7000.
+fn bind_checksum_exchange(netplay: &NetplayOptions) -> std::io::Result<ChecksumExchange> {
+    let socket = UdpSocket::bind(("0.0.0.0", netplay.local_port + 1))?;
+    socket.set_nonblocking(true)?;
+    let mut remote = netplay.remote;
+    remote.set_port(remote.port() + 1);
+    Ok(ChecksumExchange {
+        socket,
+        remote,
+        local: BTreeMap::new(),
+        received: BTreeMap::new(),
+        desynced: false,
+    })
+}
+
+// Stops looking for the other player, e.g. when they're no longer ready in the lobby.
+pub fn end_session(commands: &mut Commands) {
+    commands.remove_resource::<P2PSession<GgrsConfig>>();
+    commands.remove_resource::<SpectatorSession<GgrsConfig>>();
+    commands.remove_resource::<SessionType>();
+    commands.remove_resource::<NetplayConnection>();
+    commands.remove_resource::<ChecksumExchange>();
+}
+
+// The session only lasts one match. The frame counter goes with it, so the next match starts
+// counting from the same frame on both machines.
+fn leave_netplay_game(
+    mut commands: Commands,
+    mut checksums: ResMut<PendingChecksums>,
+    query_frame: Query<Entity, With<NetplayFrame>>,
+) {
+    end_session(&mut commands);
+    commands.remove_resource::<NetplayMatch>();
+    checksums.0.clear();
+    for entity in query_frame.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn track_local_keys(mut local_keys: ResMut<LocalKeys>, mut keyboard_event: EventReader<KeyboardInput>) {
+    local_keys.0.clear();
+    for ev in keyboard_event.iter() {
+        if let Some(key) = ev.key_code {
+            match ev.state {
+                ButtonState::Pressed => local_keys.0.press(key),
+                ButtonState::Released => local_keys.0.release(key),
+            }
+        }
+    }
+}
+
+// Whichever player this machine is, it's played with the first player's keys.
+fn local_input(_handle: In<PlayerHandle>, local_keys: Res<LocalKeys>) -> u8 {
+    ARROWS.held(&local_keys.0)
+}
+
+// Presses each player's scheme keys the way the gameplay systems expect. Starting from the
+// previous frame's keys means just_pressed comes out right even when a frame is replayed.
+fn apply_controls(
+    inputs: Res<PlayerInputs<GgrsConfig>>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut query_frame: Query<&mut NetplayFrame>,
+) {
+    let mut frame = match query_frame.get_single_mut() {
+        Ok(frame) => frame,
+        Err(_) => return,
+    };
+    let controls = [inputs[0].0, inputs[1].0];
+    for (player, held) in frame.previous_controls.iter().enumerate() {
+        ControlScheme::for_player(player as u8).press(&mut keyboard_input, *held);
+    }
+    keyboard_input.clear();
+    for (player, held) in controls.iter().enumerate() {
+        ControlScheme::for_player(player as u8).press(&mut keyboard_input, *held);
+    }
+    frame.previous_controls = controls;
+    frame.number += 1;
+}
+
+// Puts the resources back the way this frame found them the first time it was simulated.
+fn restore_resources(
+    query_frame: Query<&NetplayFrame>,
+    mut run_stats: ResMut<RunStats>,
+    mut rng: ResMut<GameRng>,
+) {
+    if let Ok(frame) = query_frame.get_single() {
+        run_stats.shots_fired = frame.shots_fired;
+        run_stats.hits = frame.hits;
+        rng.0 = StdRng::seed_from_u64(frame.rng_seed);
+    }
+}
+
+// Systems outside the rollback schedule draw from GameRng between frames too, which is why it's
+// reseeded here rather than left where this frame got to.
+fn save_resources(mut query_frame: Query<&mut NetplayFrame>, run_stats: Res<RunStats>, mut rng: ResMut<GameRng>) {
+    if let Ok(mut frame) = query_frame.get_single_mut() {
+        frame.shots_fired = run_stats.shots_fired;
+        frame.hits = run_stats.hits;
+        frame.rng_seed = rng.0.gen();
+        rng.0 = StdRng::seed_from_u64(frame.rng_seed);
+    }
+}
+
+fn record_checksum(
+    query_frame: Query<&NetplayFrame>,
+    query_game: Query<&Game>,
+    query_rollback: Query<(&Rollback, &Transform, Option<&Velocity>)>,
+    mut checksums: ResMut<PendingChecksums>,
+) {
+    let frame = match query_frame.get_single() {
+        Ok(frame) => frame,
+        Err(_) => return,
+    };
+    let mut entities: Vec<_> = query_rollback.iter().collect();
+    entities.sort_by_key(|(rollback, _, _)| rollback.id());
+    let mut hasher = DefaultHasher::new();
+    (frame.shots_fired, frame.hits, frame.rng_seed).hash(&mut hasher);
+    for game in query_game.iter() {
+        (game.score, game.player_scores, game.lives, game.player_lives).hash(&mut hasher);
+    }
+    for (rollback, transform, velocity) in entities {
+        rollback.id().hash(&mut hasher);
+        for value in transform.translation.to_array().iter().chain(transform.rotation.to_array().iter()) {
+            value.to_bits().hash(&mut hasher);
+        }
+        if let Some(velocity) = velocity {
+            for value in velocity.linvel.to_array().iter().chain([velocity.angvel].iter()) {
+                value.to_bits().hash(&mut hasher);
+            }
+        }
+    }
+    checksums.0.insert(frame.number, hasher.finish());
+}
+
+// Confirmed frames can't be rolled back any more, so their checksums are final and are sent to the
+// other player to compare with theirs.
+fn report_checksums(
+    session: Option<Res<P2PSession<GgrsConfig>>>,
+    spectating: Option<Res<SpectatorSession<GgrsConfig>>>,
+    exchange: Option<ResMut<ChecksumExchange>>,
+    mut checksums: ResMut<PendingChecksums>,
+    mut toast_event: EventWriter<ToastEvent>,
+) {
+    let later = match (session, spectating) {
+        (Some(session), _) if session.confirmed_frame() >= 0 => {
+            checksums.0.split_off(&(session.confirmed_frame() as u32 + 1))
+        }
+        // Spectators only ever simulate confirmed frames.
+        (None, Some(_)) => BTreeMap::new(),
+        _ => return,
+    };
+    let confirmed = std::mem::replace(&mut checksums.0, later);
+    for (frame, checksum) in confirmed.iter() {
+        debug!("Netplay frame {} checksum {:016x}", frame, checksum);
+    }
+    let mut exchange = match exchange {
+        Some(exchange) => exchange,
+        None => return,
+    };
+    for (frame, checksum) in confirmed {
+        let mut packet = [0; 12];
+        packet[..4].copy_from_slice(&frame.to_le_bytes());
+        packet[4..].copy_from_slice(&checksum.to_le_bytes());
+        exchange.socket.send_to(&packet, exchange.remote).ok();
+        exchange.local.insert(frame, checksum);
+    }
+    let mut packet = [0; 12];
+    loop {
+        match exchange.socket.recv_from(&mut packet) {
+            Ok((12, from)) if from.ip() == exchange.remote.ip() => {
+                let mut frame = [0; 4];
+                let mut checksum = [0; 8];
+                frame.copy_from_slice(&packet[..4]);
+                checksum.copy_from_slice(&packet[4..]);
+                exchange.received.insert(u32::from_le_bytes(frame), u64::from_le_bytes(checksum));
+            }
+            Ok(_) => continue,
+            // Nothing more has arrived.
+            Err(_) => break,
+        }
+    }
+    compare_checksums(&mut exchange, &mut toast_event);
+}
+
+fn compare_checksums(exchange: &mut ChecksumExchange, toast_event: &mut EventWriter<ToastEvent>) {
+    for (frame, theirs) in std::mem::take(&mut exchange.received) {
+        let ours = match exchange.local.remove(&frame) {
+            Some(ours) => ours,
+            None => {
+                exchange.received.insert(frame, theirs);
+                continue;
+            }
+        };
+        if ours != theirs && !exchange.desynced {
+            exchange.desynced = true;
+            error!("Netplay desync at frame {}: {:016x} here, {:016x} for the other player", frame, ours, theirs);
+            toast_event.send(ToastEvent("The two games have fallen out of sync".to_string()));
+        }
+    }
+    // Whatever the other machine hasn't sent by now isn't coming.
+    let newest = exchange.local.keys().chain(exchange.received.keys()).max().copied().unwrap_or(0);
+    let oldest_kept = newest.saturating_sub(KEPT_CHECKSUMS);
+    exchange.local = exchange.local.split_off(&oldest_kept);
+    exchange.received = exchange.received.split_off(&oldest_kept);
+}
+
+fn log_network_events(
+    session: Option<ResMut<P2PSession<GgrsConfig>>>,
+    spectating: Option<ResMut<SpectatorSession<GgrsConfig>>>,
+) {
+    if let Some(mut session) = session {
+        for event in session.events() {
+            info!("Netplay: {:?}", event);
+        }
+    }
+    if let Some(mut spectating) = spectating {
+        for event in spectating.events() {
+            info!("Spectating: {:?}", event);
+        }
+    }
+}
+
+// Both ships start as soon as the two machines are in sync, or a spectator is with the host.
+fn start_netplay_game(
+    session: Option<Res<P2PSession<GgrsConfig>>>,
+    spectating: Option<Res<SpectatorSession<GgrsConfig>>>,
+    started: Option<Res<NetplayMatch>>,
+    mut commands: Commands,
+    mut state: ResMut<State<AppState>>,
+    mut start_game_event: EventWriter<StartGameEvent>,
+) {
+    let running = session.map_or(false, |session| session.current_state() == SessionState::Running)
+        || spectating.map_or(false, |spectating| spectating.current_state() == SessionState::Running);
+    if !running || started.is_some() {
+        return;
+    }
+    commands.insert_resource(NetplayMatch);
+    warn!("Netplay is experimental, the two games aren't checked for staying in sync");
+    start_game_event.send(StartGameEvent { players: 2 });
+    if let Err(err) = state.set(AppState::InGame) {
+        warn!("Could not start netplay game: {:?}", err);
+    }
+}
+
+// New ships, meteors and bullets, and the Game, join the rollback as soon as they've been spawned.
+fn tag_rollback_entities(
+    mut commands: Commands,
+    mut rip: ResMut<RollbackIdProvider>,
+    query: Query<
+        Entity,
+        (Or<(With<Spaceship>, With<Meteor>, With<Bullet>, With<Game>)>, Without<Rollback>, Without<Dead>),
+    >,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).insert(Rollback::new(rip.next_id()));
+    }
+}
@@ -0,0 +1,276 @@
+// Colours that carry meaning come from the Palette resource rather than constants at each use, so the
+// colourblind palettes in the settings can swap all of them at once. Anything drawn in one of them
+// gets a Tint saying which, and is recoloured whenever the palette or its Tint changes. A tint keeps
+// the alpha the entity already has, so fades like the threat arrows and glows only touch the alpha.
+// The colourblind palettes are built around the Okabe-Ito colours, which stay apart under each kind
+// of colour blindness. Mods can add palettes of their own as .palette.ron files in palettes/, see
+// mods.rs, each with a name for the settings and every colour below, e.g.
+// (name: "Sunset", colours: (bullet: Rgba(red: 1.0, green: 0.5, blue: 0.2, alpha: 1.0), accent: ...)).
+use std::collections::BTreeMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+
+use crate::config::GameConfig;
+use crate::powerups::PowerupKind;
+use crate::settings::{ColourPalette, Settings};
+use crate::theme::Theme;
+
+#[cfg(not(target_arch = "wasm32"))]
+const PALETTES_FOLDER: &str = "palettes";
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum Tint {
+    Bullet,
+    // Titles and the HUD.
+    Accent,
+    // The menu button arrow keys / d-pad are on.
+    Focus,
+    // Threat arrows, the damage vignette and an overheated gun.
+    Danger,
+    Heat,
+    // The ships' radar blips.
+    Friendly,
+    // The meteors' radar blips.
+    Meteor,
+    Powerup(PowerupKind),
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Palette {
+    bullet: Color,
+    accent: Color,
+    focus: Color,
+    danger: Color,
+    heat: Color,
+    friendly: Color,
+    meteor: Color,
+    shield: Color,
+    rapid_fire: Color,
+    double_points: Color,
+}
+
+impl Palette {
+    // The standard palette's bullets are whatever config.ron asks for.
+    fn new(scheme: ColourPalette, config: &GameConfig) -> Self {
+        match scheme {
+            ColourPalette::Standard => Palette {
+                bullet: config.bullet_colour,
+                accent: Color::rgb(0.0, 1.0, 0.0),
+                focus: Color::rgb(0.2, 0.35, 0.2),
+                danger: Color::rgb(1.0, 0.3, 0.2),
+                heat: Color::rgb(1.0, 0.6, 0.2),
+                friendly: Color::rgb(0.0, 1.0, 0.0),
+                meteor: Color::rgb(0.8, 0.8, 0.8),
+                shield: Color::rgb(0.3, 0.6, 1.0),
+                rapid_fire: Color::rgb(1.0, 0.6, 0.2),
+                double_points: Color::rgb(1.0, 0.9, 0.2),
+            },
+            // Red and green look alike, so friendly is blue and danger is orange.
+            ColourPalette::Deuteranopia => Palette {
+                bullet: Color::rgb(0.95, 0.9, 0.25),
+                accent: Color::rgb(0.35, 0.7, 0.9),
+                focus: Color::rgb(0.1, 0.3, 0.45),
+                danger: Color::rgb(0.85, 0.35, 0.0),
+                heat: Color::rgb(0.35, 0.7, 0.9),
+                friendly: Color::rgb(0.35, 0.7, 0.9),
+                meteor: Color::rgb(0.8, 0.8, 0.8),
+                shield: Color::rgb(0.35, 0.7, 0.9),
+                rapid_fire: Color::rgb(0.9, 0.6, 0.0),
+                double_points: Color::rgb(0.95, 0.9, 0.25),
+            },
+            // As deuteranopia, but reds also look dark, so danger is a brighter orange.
+            ColourPalette::Protanopia => Palette {
+                bullet: Color::rgb(0.95, 0.9, 0.25),
+                accent: Color::rgb(0.35, 0.7, 0.9),
+                focus: Color::rgb(0.1, 0.3, 0.45),
+                danger: Color::rgb(1.0, 0.65, 0.0),
+                heat: Color::rgb(0.35, 0.7, 0.9),
+                friendly: Color::rgb(0.35, 0.7, 0.9),
+                meteor: Color::rgb(0.8, 0.8, 0.8),
+                shield: Color::rgb(0.35, 0.7, 0.9),
+                rapid_fire: Color::rgb(0.8, 0.6, 0.7),
+                double_points: Color::rgb(0.95, 0.9, 0.25),
+            },
+            // Blue and yellow are the ones to avoid, so it's reds against teals.
+            ColourPalette::Tritanopia => Palette {
+                bullet: Color::rgb(1.0, 0.5, 0.6),
+                accent: Color::rgb(0.0, 0.8, 0.8),
+                focus: Color::rgb(0.05, 0.35, 0.35),
+                danger: Color::rgb(0.95, 0.15, 0.15),
+                heat: Color::rgb(0.0, 0.8, 0.8),
+                friendly: Color::rgb(0.0, 0.8, 0.8),
+                meteor: Color::rgb(0.8, 0.8, 0.8),
+                shield: Color::rgb(0.0, 0.8, 0.8),
+                rapid_fire: Color::rgb(0.95, 0.4, 0.2),
+                double_points: Color::rgb(0.95, 0.95, 0.95),
+            },
+        }
+    }
+
+    pub fn colour(&self, tint: Tint) -> Color {
+        match tint {
+            Tint::Bullet => self.bullet,
+            Tint::Accent => self.accent,
+            Tint::Focus => self.focus,
+            Tint::Danger => self.danger,
+            Tint::Heat => self.heat,
+            Tint::Friendly => self.friendly,
+            Tint::Meteor => self.meteor,
+            Tint::Powerup(PowerupKind::Shield) => self.shield,
+            Tint::Powerup(PowerupKind::RapidFire) => self.rapid_fire,
+            Tint::Powerup(PowerupKind::DoublePoints) => self.double_points,
+        }
+    }
+}
+
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "9b3e6f12-4c8a-4d5e-b7a1-2f6c0d9e8a43"]
+struct PaletteFile {
+    name: String,
+    colours: Palette,
+}
+
+#[derive(Default)]
+struct PaletteFileLoader;
+
+impl AssetLoader for PaletteFileLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let file: PaletteFile = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(file));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["palette.ron"]
+    }
+}
+
+// The palettes added by mods, by name.
+#[derive(Default)]
+pub struct ModPalettes {
+    // Keeps the files loaded.
+    handles: Vec<Handle<PaletteFile>>,
+    palettes: BTreeMap<String, Palette>,
+}
+
+impl ModPalettes {
+    pub fn names(&self) -> Vec<String> {
+        self.palettes.keys().cloned().collect()
+    }
+}
+
+pub struct Palettes;
+
+impl Plugin for Palettes {
+    fn build(&self, app: &mut App) {
+        app
+            .add_asset::<PaletteFile>()
+            .init_asset_loader::<PaletteFileLoader>()
+            .init_resource::<ModPalettes>()
+            .insert_resource(Palette::new(ColourPalette::Standard, &GameConfig::default()))
+            .add_system(apply_mod_palettes)
+            .add_system(update_palette.after(apply_mod_palettes))
+            // After everything spawned during the frame, so nothing is drawn in the wrong colour first.
+            .add_system_to_stage(CoreStage::PostUpdate, apply_tints);
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_startup_system(load_mod_palettes);
+    }
+}
+
+// The game has no palette files of its own, so the folder is only there when a mod adds one.
+#[cfg(not(target_arch = "wasm32"))]
+fn load_mod_palettes(asset_server: Res<AssetServer>, mut palettes: ResMut<ModPalettes>) {
+    if !asset_server.asset_io().is_dir(Path::new(PALETTES_FOLDER)) {
+        return;
+    }
+    match asset_server.load_folder(PALETTES_FOLDER) {
+        Ok(handles) => palettes.handles = handles.into_iter().map(|handle| handle.typed()).collect(),
+        Err(err) => warn!("Could not load the {} folder: {:?}", PALETTES_FOLDER, err),
+    }
+}
+
+fn apply_mod_palettes(
+    files: Res<Assets<PaletteFile>>,
+    mut asset_events: EventReader<AssetEvent<PaletteFile>>,
+    mut palettes: ResMut<ModPalettes>,
+) {
+    for ev in asset_events.iter() {
+        if let AssetEvent::Created { handle } | AssetEvent::Modified { handle } = ev {
+            if let Some(file) = files.get(handle) {
+                info!("Loaded the {} palette", file.name);
+                palettes.palettes.insert(file.name.clone(), file.colours.clone());
+            }
+        }
+    }
+}
+
+// The config is watched too, since the standard palette takes its bullet colour from there. A mod
+// palette that's no longer there falls back to the built-in one picked before it. A theme's palette
+// stands in for the standard one, see theme.rs.
+fn update_palette(
+    settings: Res<Settings>,
+    config: Res<GameConfig>,
+    mod_palettes: Res<ModPalettes>,
+    theme: Res<Theme>,
+    mut palette: ResMut<Palette>,
+) {
+    if !settings.is_changed() && !config.is_changed() && !mod_palettes.is_changed() && !theme.is_changed() {
+        return;
+    }
+    let mod_palette = settings.mod_palette.as_ref().and_then(|name| mod_palettes.palettes.get(name));
+    *palette = match (mod_palette, &theme.palette) {
+        (Some(mod_palette), _) => mod_palette.clone(),
+        (None, Some(themed)) if settings.palette == ColourPalette::Standard => themed.clone(),
+        _ => Palette::new(settings.palette, &config),
+    };
+}
+
+fn tinted(tint: Color, current: Color) -> Color {
+    let mut colour = tint;
+    colour.set_a(current.a());
+    colour
+}
+
+fn apply_tints(
+    palette: Res<Palette>,
+    mut query: Query<(
+        &Tint,
+        ChangeTrackers<Tint>,
+        Option<&mut Text>,
+        Option<&mut UiColor>,
+        Option<&mut Sprite>,
+        Option<&mut TextureAtlasSprite>,
+    )>,
+) {
+    for (tint, tracker, text, ui_colour, sprite, atlas_sprite) in query.iter_mut() {
+        if !palette.is_changed() && !tracker.is_changed() {
+            continue;
+        }
+        let colour = palette.colour(*tint);
+        if let Some(mut text) = text {
+            for section in text.sections.iter_mut() {
+                section.style.color = tinted(colour, section.style.color);
+            }
+        }
+        if let Some(mut ui_colour) = ui_colour {
+            ui_colour.0 = tinted(colour, ui_colour.0);
+        }
+        if let Some(mut sprite) = sprite {
+            sprite.color = tinted(colour, sprite.color);
+        }
+        if let Some(mut atlas_sprite) = atlas_sprite {
+            atlas_sprite.color = tinted(colour, atlas_sprite.color);
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::Velocity;
+use rand::random;
+
+use crate::animation::SpriteSheets;
+use crate::culling::Culled;
+use crate::input::ControlScheme;
+use crate::settings::{PhysicsPreset, Settings};
+use crate::{AppState, MeteorDestroyedEvent, Parked, ShipDestroyedEvent, Spaceship};
+
+const PARTICLE_Z: f32 = 5.0;
+// Exhaust comes out of the back of the ship, which is half its length behind the centre.
+const EXHAUST_OFFSET: f32 = 65.0 / 2.0;
+const EXHAUST_RATE: f32 = 60.0;
+const EXHAUST_SPREAD: f32 = 0.4;
+const TRAIL_INTERVAL: f32 = 0.02;
+// Enough for a few big explosions at once before the pool has to grow.
+const PREWARMED_PARTICLES: usize = 1000;
+// Most particles that can be alive at once with the Performance physics preset.
+const PERFORMANCE_PARTICLE_CAP: usize = 300;
+
+// Describes one kind of particle and how many of them an emitter throws out at once.
+#[derive(Clone, Copy)]
+pub struct ParticleEmitter {
+    pub count: usize,
+    pub speed: f32,
+    pub lifetime: f32,
+    pub size: f32,
+    pub colour: Color,
+    // Fraction of speed lost per second, so particles slow down as they spread.
+    pub drag: f32,
+}
+
+const SPARKS: ParticleEmitter = ParticleEmitter {
+    count: 6,
+    speed: 180.0,
+    lifetime: 0.4,
+    size: 3.0,
+    colour: Color::rgb(1.0, 0.85, 0.4),
+    drag: 2.0,
+};
+
+const DUST: ParticleEmitter = ParticleEmitter {
+    count: 4,
+    speed: 40.0,
+    lifetime: 1.2,
+    size: 5.0,
+    colour: Color::rgb(0.55, 0.5, 0.45),
+    drag: 1.0,
+};
+
+const SHIP_SPARKS: ParticleEmitter = ParticleEmitter {
+    count: 40,
+    speed: 250.0,
+    lifetime: 0.8,
+    size: 4.0,
+    colour: Color::rgb(0.6, 0.9, 1.0),
+    drag: 1.5,
+};
+
+const EXHAUST: ParticleEmitter = ParticleEmitter {
+    count: 1,
+    speed: 120.0,
+    lifetime: 0.35,
+    size: 3.0,
+    colour: Color::rgb(1.0, 0.55, 0.2),
+    drag: 3.0,
+};
+
+// Trail particles stay where they were dropped, so drag and speed don't matter for them.
+pub const BULLET_TRAIL: ParticleEmitter = ParticleEmitter {
+    count: 1,
+    speed: 0.0,
+    lifetime: 0.25,
+    size: 3.0,
+    colour: Color::rgb(0.9, 0.6, 0.6),
+    drag: 0.0,
+};
+
+impl ParticleEmitter {
+    // Meteor explosions get more particles thrown further the bigger the meteor was.
+    fn scaled(self, size: u8) -> Self {
+        let scale = size as f32;
+        ParticleEmitter {
+            count: self.count * size as usize,
+            speed: self.speed * (1.0 + scale / 8.0),
+            size: self.size * (1.0 + scale / 16.0),
+            ..self
+        }
+    }
+
+    // A quarter of the particles thrown half as far, for reduced motion.
+    fn calmed(self) -> Self {
+        ParticleEmitter { count: (self.count / 4).max(1), speed: self.speed / 2.0, ..self }
+    }
+}
+
+// Leaves a line of fading particles behind a fast moving entity so it can be followed by eye.
+#[derive(Component)]
+pub struct Trail {
+    emitter: ParticleEmitter,
+    interval: Timer,
+}
+
+impl Trail {
+    pub fn new(emitter: ParticleEmitter) -> Self {
+        Trail {
+            emitter,
+            interval: Timer::from_seconds(TRAIL_INTERVAL, true),
+        }
+    }
+}
+
+// Finished particles are hidden and kept for the next emitter instead of being despawned, as an
+// explosion can throw out hundreds of them in one frame.
+#[derive(Component)]
+pub struct Particle {
+    velocity: Vec2,
+    drag: f32,
+    size: f32,
+    colour: Color,
+    lifetime: Timer,
+}
+
+// Particles are tinted from the blank cell of the sprite atlas, see animation.rs.
+pub struct ParticlePool {
+    parked: Vec<Entity>,
+    // Every particle there is, parked or not.
+    total: usize,
+    cap: usize,
+    atlas: Handle<TextureAtlas>,
+    blank: usize,
+}
+
+impl Default for ParticlePool {
+    fn default() -> Self {
+        ParticlePool { parked: Vec::new(), total: 0, cap: usize::MAX, atlas: default(), blank: 0 }
+    }
+}
+
+pub struct Particles;
+
+impl Plugin for Particles {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ParticlePool>()
+            .add_startup_system(prewarm_particles)
+            .add_system(apply_particle_cap)
+            .add_system(explosion_particles)
+            .add_system_set(SystemSet::on_update(AppState::InGame).with_system(exhaust_particles))
+            .add_system(emit_trails)
+            .add_system(update_particles);
+    }
+}
+
+// Throws `emitter.count` particles out from `position` in random directions within `spread`
+// radians of `direction`, on top of `base_velocity`.
+pub fn emit_particles(
+    commands: &mut Commands,
+    pool: &mut ParticlePool,
+    emitter: &ParticleEmitter,
+    position: Vec2,
+    base_velocity: Vec2,
+    direction: f32,
+    spread: f32,
+) {
+    for _ in 0..emitter.count {
+        if pool.total - pool.parked.len() >= pool.cap {
+            break;
+        }
+        let angle = direction + (random::<f32>() - 0.5) * spread;
+        let speed = emitter.speed * (0.5 + random::<f32>() * 0.5);
+        let lifetime = emitter.lifetime * (0.7 + random::<f32>() * 0.3);
+        let particle = pool.parked.pop().unwrap_or_else(|| {
+            pool.total += 1;
+            commands.spawn_bundle(SpriteSheetBundle { texture_atlas: pool.atlas.clone(), ..default() }).id()
+        });
+        commands
+            .entity(particle)
+            .insert(TextureAtlasSprite {
+                index: pool.blank,
+                color: emitter.colour,
+                custom_size: Some(Vec2::splat(emitter.size)),
+                ..default()
+            })
+            .insert(Transform::from_translation(position.extend(PARTICLE_Z)))
+            .insert(Visibility { is_visible: true })
+            .insert(Particle {
+                velocity: base_velocity + Vec2::from_angle(angle) * speed,
+                drag: emitter.drag,
+                size: emitter.size,
+                colour: emitter.colour,
+                lifetime: Timer::from_seconds(lifetime, false),
+            });
+    }
+}
+
+fn prewarm_particles(mut commands: Commands, sprite_sheets: Res<SpriteSheets>, mut pool: ResMut<ParticlePool>) {
+    pool.atlas = sprite_sheets.atlas();
+    pool.blank = sprite_sheets.blank();
+    for _ in 0..PREWARMED_PARTICLES {
+        let particle = commands
+            .spawn_bundle(SpriteSheetBundle {
+                texture_atlas: pool.atlas.clone(),
+                visibility: Visibility { is_visible: false },
+                ..default()
+            })
+            .id();
+        pool.parked.push(particle);
+        pool.total += 1;
+    }
+}
+
+// Particles already out over a lowered cap are left to finish.
+fn apply_particle_cap(settings: Res<Settings>, mut pool: ResMut<ParticlePool>) {
+    if !settings.is_changed() {
+        return;
+    }
+    pool.cap = match settings.physics_preset {
+        PhysicsPreset::Quality => usize::MAX,
+        PhysicsPreset::Performance => PERFORMANCE_PARTICLE_CAP,
+    };
+}
+
+fn explosion_particles(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut pool: ResMut<ParticlePool>,
+    mut meteor_event: EventReader<MeteorDestroyedEvent>,
+    mut ship_event: EventReader<ShipDestroyedEvent>,
+) {
+    let burst = |emitter: ParticleEmitter| if settings.reduced_motion { emitter.calmed() } else { emitter };
+    for ev in meteor_event.iter() {
+        let _explosion = info_span!("meteor_explosion", size = ev.size).entered();
+        emit_particles(&mut commands, &mut pool, &burst(SPARKS.scaled(ev.size)), ev.position, Vec2::ZERO, 0.0, TAU);
+        emit_particles(&mut commands, &mut pool, &burst(DUST.scaled(ev.size)), ev.position, Vec2::ZERO, 0.0, TAU);
+    }
+    for ev in ship_event.iter() {
+        emit_particles(&mut commands, &mut pool, &burst(SHIP_SPARKS), ev.position, Vec2::ZERO, 0.0, TAU);
+    }
+}
+
+// Emitted at a steady rate rather than once per frame so the stream looks the same at any frame rate.
+fn exhaust_particles(
+    mut commands: Commands,
+    mut pool: ResMut<ParticlePool>,
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    query_ship: Query<(&Transform, &Velocity, &ControlScheme), With<Spaceship>>,
+    mut pending: Local<f32>,
+) {
+    *pending += EXHAUST_RATE * time.delta_seconds();
+    let count = pending.floor();
+    *pending -= count;
+    for (transform, velocity, scheme) in query_ship.iter() {
+        if !keyboard_input.pressed(scheme.thrust) {
+            continue;
+        }
+        let (axis, angle) = transform.rotation.to_axis_angle();
+        let backwards = Vec2::from_angle(axis.z * angle) * -1.0;
+        let rear = transform.translation.truncate() + backwards * EXHAUST_OFFSET;
+        let emitter = ParticleEmitter { count: count as usize, ..EXHAUST };
+        let direction = backwards.y.atan2(backwards.x);
+        emit_particles(&mut commands, &mut pool, &emitter, rear, velocity.linvel, direction, EXHAUST_SPREAD);
+    }
+}
+
+// Parked and culled bullets are out of sight and shouldn't leave anything behind.
+fn emit_trails(
+    mut commands: Commands,
+    mut pool: ResMut<ParticlePool>,
+    time: Res<Time>,
+    mut query: Query<(&mut Trail, &Transform), (Without<Parked>, Without<Culled>)>,
+) {
+    for (mut trail, transform) in query.iter_mut() {
+        let drops = trail.interval.tick(time.delta()).times_finished_this_tick();
+        let emitter = ParticleEmitter { count: drops as usize, ..trail.emitter };
+        emit_particles(&mut commands, &mut pool, &emitter, transform.translation.truncate(), Vec2::ZERO, 0.0, 0.0);
+    }
+}
+
+// Particles shrink and fade out over their lifetime, then go back to the pool.
+fn update_particles(
+    time: Res<Time>,
+    mut pool: ResMut<ParticlePool>,
+    mut query: Query<(Entity, &mut Particle, &mut Transform, &mut TextureAtlasSprite, &mut Visibility)>,
+) {
+    for (entity, mut particle, mut transform, mut sprite, mut visibility) in query.iter_mut() {
+        if !visibility.is_visible {
+            continue;
+        }
+        if particle.lifetime.tick(time.delta()).just_finished() {
+            visibility.is_visible = false;
+            pool.parked.push(entity);
+            continue;
+        }
+        let drag = (1.0 - particle.drag * time.delta_seconds()).max(0.0);
+        particle.velocity *= drag;
+        transform.translation += (particle.velocity * time.delta_seconds()).extend(0.0);
+
+        let remaining = 1.0 - particle.lifetime.percent();
+        sprite.custom_size = Some(Vec2::splat(particle.size * (0.3 + 0.7 * remaining)));
+        let mut colour = particle.colour;
+        colour.set_a(remaining);
+        sprite.color = colour;
+    }
+}
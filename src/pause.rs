@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+use bevy::window::WindowFocused;
+use bevy_rapier2d::prelude::RapierConfiguration;
+
+use crate::launch::LaunchOptions;
+use crate::menu::{spawn_menu_button, spawn_menu_root, spawn_menu_title, MenuButton};
+use crate::transition::SlideIn;
+use crate::{AppState, Game, StartGameEvent};
+
+const PAUSE_DIM: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+
+#[derive(Component, Clone, Copy)]
+enum PauseButton {
+    Resume,
+    Restart,
+    QuitToMenu,
+}
+
+#[derive(Component)]
+struct PauseScreen;
+
+pub struct Pause;
+
+impl Plugin for Pause {
+    fn build(&self, app: &mut App) {
+        let options = app.world.get_resource::<LaunchOptions>();
+        // The other player's game carries on regardless, and stopping this one would leave it behind.
+        let online = options.map_or(false, |options| options.online());
+        // Benchmarks keep going whatever else is in front of them.
+        let bench = options.map_or(false, |options| options.bench.is_some());
+        if !online {
+            app.add_system(toggle_pause);
+            if !bench {
+                app.add_system(pause_on_focus_lost.after(toggle_pause));
+            }
+        }
+        app
+            .add_system_set(
+                SystemSet::on_enter(AppState::Paused)
+                    .with_system(setup_pause_menu)
+                    .with_system(freeze_physics),
+            )
+            .add_system_set(SystemSet::on_update(AppState::Paused).with_system(pause_button_action))
+            .add_system_set(SystemSet::on_pause(AppState::Paused).with_system(hide_pause_menu))
+            .add_system_set(SystemSet::on_resume(AppState::Paused).with_system(show_pause_menu))
+            .add_system_set(
+                SystemSet::on_exit(AppState::Paused)
+                    .with_system(despawn_pause_menu)
+                    .with_system(resume_physics),
+            );
+    }
+}
+
+fn pause_pressed(
+    keyboard_input: &Input<KeyCode>,
+    gamepads: &Gamepads,
+    gamepad_input: &Input<GamepadButton>,
+) -> bool {
+    keyboard_input.just_pressed(KeyCode::Escape)
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad_input.just_pressed(GamepadButton(*gamepad, GamepadButtonType::Start)))
+}
+
+fn toggle_pause(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    mut state: ResMut<State<AppState>>,
+) {
+    if !pause_pressed(&keyboard_input, &gamepads, &gamepad_input) {
+        return;
+    }
+    let result = match state.current() {
+        AppState::InGame => state.push(AppState::Paused),
+        AppState::Paused => state.pop(),
+        _ => Ok(()),
+    };
+    if let Err(err) = result {
+        warn!("Pause toggle failed: {:?}", err);
+    }
+}
+
+// Alt-tabbing away or a notification on a phone would otherwise leave the ship flying unwatched.
+fn pause_on_focus_lost(
+    windows: Res<Windows>,
+    mut focused_event: EventReader<WindowFocused>,
+    mut state: ResMut<State<AppState>>,
+) {
+    let primary = windows.get_primary().map(|window| window.id());
+    let lost = focused_event.iter().any(|ev| !ev.focused && Some(ev.id) == primary);
+    if lost && *state.current() == AppState::InGame {
+        if let Err(err) = state.push(AppState::Paused) {
+            warn!("Could not pause on losing focus: {:?}", err);
+        }
+    }
+}
+
+fn setup_pause_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    // Dim rather than hide the play field so the player can see what they paused on.
+    spawn_menu_root(&mut commands)
+        .insert(UiColor(PAUSE_DIM))
+        .insert(PauseScreen)
+        .with_children(|parent| {
+            spawn_menu_title(parent, &asset_server, "Paused");
+            spawn_menu_button(parent, &asset_server, "Resume").insert(PauseButton::Resume);
+            spawn_menu_button(parent, &asset_server, "Restart").insert(PauseButton::Restart);
+            spawn_menu_button(parent, &asset_server, "Settings").insert(MenuButton::Settings);
+            spawn_menu_button(parent, &asset_server, "Quit to Menu").insert(PauseButton::QuitToMenu);
+        });
+}
+
+fn pause_button_action(
+    query: Query<(&Interaction, &PauseButton), Changed<Interaction>>,
+    query_game: Query<&Game>,
+    mut state: ResMut<State<AppState>>,
+    mut start_game_event: EventWriter<StartGameEvent>,
+) {
+    for (interaction, button) in query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let result = match button {
+            PauseButton::Resume => state.pop(),
+            PauseButton::Restart => {
+                start_game_event.send(StartGameEvent { players: query_game.single().players });
+                state.pop()
+            }
+            PauseButton::QuitToMenu => state.replace(AppState::MainMenu),
+        };
+        if let Err(err) = result {
+            warn!("Pause menu transition failed: {:?}", err);
+        }
+    }
+}
+
+fn hide_pause_menu(mut query: Query<&mut Style, With<PauseScreen>>) {
+    for mut style in query.iter_mut() {
+        style.display = Display::None;
+    }
+}
+
+fn show_pause_menu(mut commands: Commands, mut query: Query<(Entity, &mut Style), With<PauseScreen>>) {
+    for (entity, mut style) in query.iter_mut() {
+        style.display = Display::Flex;
+        commands.entity(entity).insert(SlideIn::default());
+    }
+}
+
+fn despawn_pause_menu(mut commands: Commands, query: Query<Entity, With<PauseScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn freeze_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = false;
+}
+
+fn resume_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = true;
+}
@@ -0,0 +1,60 @@
+// The physics preset from the video settings. Quality gives meteors and ships continuous collision
+// detection so nothing tunnels through anything at speed, Performance leaves that to the bullets,
+// which need it most, and runs the solver with fewer iterations. Performance also caps particles,
+// see particles.rs. Online games always use Quality so every peer steps the same physics.
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_rapier2d::rapier::dynamics::IntegrationParameters;
+
+use crate::launch::LaunchOptions;
+use crate::settings::{PhysicsPreset, Settings};
+use crate::{Meteor, Spaceship};
+
+const PERFORMANCE_VELOCITY_ITERATIONS: usize = 2;
+const PERFORMANCE_FRICTION_ITERATIONS: usize = 2;
+
+pub struct PhysicsPresets;
+
+impl Plugin for PhysicsPresets {
+    fn build(&self, app: &mut App) {
+        if app.world.get_resource::<LaunchOptions>().map_or(false, |options| options.online()) {
+            return;
+        }
+        app
+            .add_system(apply_solver_iterations)
+            .add_system(apply_ccd);
+    }
+}
+
+fn apply_solver_iterations(settings: Res<Settings>, context: Option<ResMut<RapierContext>>) {
+    let mut context = match context {
+        Some(context) if settings.is_changed() => context,
+        _ => return,
+    };
+    let defaults = IntegrationParameters::default();
+    let parameters = &mut context.integration_parameters;
+    match settings.physics_preset {
+        PhysicsPreset::Quality => {
+            parameters.max_velocity_iterations = defaults.max_velocity_iterations;
+            parameters.max_velocity_friction_iterations = defaults.max_velocity_friction_iterations;
+        }
+        PhysicsPreset::Performance => {
+            parameters.max_velocity_iterations = PERFORMANCE_VELOCITY_ITERATIONS;
+            parameters.max_velocity_friction_iterations = PERFORMANCE_FRICTION_ITERATIONS;
+        }
+    }
+}
+
+// Pooled meteors become meteors again when they're reused, so they're caught here as well.
+fn apply_ccd(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    query_all: Query<Entity, Or<(With<Meteor>, With<Spaceship>)>>,
+    query_added: Query<Entity, Or<(Added<Meteor>, Added<Spaceship>)>>,
+) {
+    let ccd = Ccd { enabled: settings.physics_preset == PhysicsPreset::Quality };
+    let bodies = if settings.is_changed() { query_all.iter().collect::<Vec<_>>() } else { query_added.iter().collect() };
+    for body in bodies {
+        commands.entity(body).insert(ccd);
+    }
+}
@@ -0,0 +1,58 @@
+// Menus, the pause screen and the game over screen only update when there's something to react to,
+// so the game doesn't keep the CPU and GPU busy while it sits at one. Everything else runs every
+// frame as usual, as does the lobby, which has a connection to poll.
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::winit::{UpdateMode, WinitSettings};
+
+use crate::AppState;
+
+// Gamepads aren't winit events, so without a wake up now and then a button press could sit unread.
+const GAMEPAD_POLL: Duration = Duration::from_millis(100);
+// Long enough for the fade and slide between screens to play out before slowing down.
+const TRANSITION_TIME: f32 = 0.5;
+
+pub struct PowerSaving;
+
+impl Plugin for PowerSaving {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(WinitSettings::game())
+            .add_system(choose_update_mode);
+    }
+}
+
+fn idle_state(state: &AppState) -> bool {
+    matches!(
+        state,
+        AppState::MainMenu
+            | AppState::ModeSelect
+            | AppState::CustomGame
+            | AppState::Settings
+            | AppState::Leaderboard
+            | AppState::Paused
+            | AppState::GameOver
+    )
+}
+
+fn choose_update_mode(
+    time: Res<Time>,
+    state: Res<State<AppState>>,
+    mut settings: ResMut<WinitSettings>,
+    mut transition: Local<Timer>,
+) {
+    if state.is_changed() {
+        *transition = Timer::from_seconds(TRANSITION_TIME, false);
+    }
+    let idle = idle_state(state.current()) && transition.tick(time.delta()).finished();
+    let reactive = matches!(settings.focused_mode, UpdateMode::Reactive { .. });
+    if idle == reactive {
+        return;
+    }
+    *settings = if idle {
+        WinitSettings { focused_mode: UpdateMode::Reactive { max_wait: GAMEPAD_POLL }, ..WinitSettings::desktop_app() }
+    } else {
+        WinitSettings::game()
+    };
+}
@@ -0,0 +1,271 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::definitions::{EntityDefinitions, PowerupDefinition};
+use crate::game_mode::GameMode;
+use crate::palette::Tint;
+use crate::replay::GameRng;
+use crate::spawning::{PowerupSpec, SpawnCommands};
+use crate::{AppState, DestroyCommands, MeteorDestroyedEvent, PlayerId, Spaceship, Weapon};
+
+const HUD_BAR_WIDTH: f32 = 80.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+pub enum PowerupKind {
+    Shield,
+    RapidFire,
+    DoublePoints,
+}
+
+pub const POWERUP_KINDS: [PowerupKind; 3] =
+    [PowerupKind::Shield, PowerupKind::RapidFire, PowerupKind::DoublePoints];
+
+impl PowerupKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PowerupKind::Shield => "Shield",
+            PowerupKind::RapidFire => "Rapid Fire",
+            PowerupKind::DoublePoints => "x2 Points",
+        }
+    }
+}
+
+// A pickup floating in the field, collected by flying a ship over it.
+#[derive(Component)]
+pub struct Powerup {
+    pub kind: PowerupKind,
+}
+
+// The modifiers below sit on a ship for as long as their timer runs.
+#[derive(Component)]
+pub struct Shield(pub Timer);
+
+#[derive(Component)]
+pub struct RapidFire(pub Timer);
+
+#[derive(Component)]
+pub struct DoublePoints(pub Timer);
+
+pub trait TimedEffect: Component {
+    fn timer(&self) -> &Timer;
+    fn timer_mut(&mut self) -> &mut Timer;
+}
+
+macro_rules! timed_effect {
+    ($effect:ty) => {
+        impl TimedEffect for $effect {
+            fn timer(&self) -> &Timer {
+                &self.0
+            }
+            fn timer_mut(&mut self) -> &mut Timer {
+                &mut self.0
+            }
+        }
+    };
+}
+
+timed_effect!(Shield);
+timed_effect!(RapidFire);
+timed_effect!(DoublePoints);
+
+pub fn grant_powerup(commands: &mut Commands, definition: &PowerupDefinition, ship: Entity, kind: PowerupKind) {
+    let timer = Timer::from_seconds(definition.effect_duration, false);
+    match kind {
+        PowerupKind::Shield => commands.entity(ship).insert(Shield(timer)),
+        PowerupKind::RapidFire => commands.entity(ship).insert(RapidFire(timer)),
+        PowerupKind::DoublePoints => commands.entity(ship).insert(DoublePoints(timer)),
+    };
+}
+
+#[derive(Component)]
+struct EffectHud(PowerupKind);
+
+#[derive(Component)]
+struct EffectBar(PowerupKind);
+
+#[derive(Component)]
+struct HeatBar;
+
+pub struct Powerups;
+
+impl Plugin for Powerups {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(setup_powerup_hud)
+            .add_system(drop_powerups)
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(collect_powerups)
+                    .with_system(expire_effect::<Shield>)
+                    .with_system(expire_effect::<RapidFire>)
+                    .with_system(expire_effect::<DoublePoints>),
+            )
+            .add_system(update_powerup_hud);
+    }
+}
+
+// Modes without shields drop one of the others in their place.
+fn drop_powerups(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    mode: Res<GameMode>,
+    definitions: Res<EntityDefinitions>,
+    mut destroyed_event: EventReader<MeteorDestroyedEvent>,
+) {
+    let shields = mode.rules().shields;
+    let kinds: Vec<PowerupKind> =
+        POWERUP_KINDS.into_iter().filter(|kind| shields || *kind != PowerupKind::Shield).collect();
+    for ev in destroyed_event.iter() {
+        if rng.0.gen::<f32>() > definitions.powerup.drop_chance {
+            continue;
+        }
+        let kind = kinds[rng.0.gen_range(0..kinds.len())];
+        commands.spawn_powerup(PowerupSpec::new(kind).at(ev.position));
+    }
+}
+
+fn collect_powerups(
+    mut commands: Commands,
+    definitions: Res<EntityDefinitions>,
+    query_ship: Query<(Entity, &Transform), With<Spaceship>>,
+    query_powerup: Query<(Entity, &Transform, &Powerup)>,
+) {
+    for (entity_powerup, powerup_transform, powerup) in query_powerup.iter() {
+        let collector = query_ship.iter().find(|(_, ship_transform)| {
+            ship_transform.translation.truncate().distance(powerup_transform.translation.truncate())
+                < definitions.powerup.pickup_radius
+        });
+        if let Some((entity_ship, _)) = collector {
+            grant_powerup(&mut commands, &definitions.powerup, entity_ship, powerup.kind);
+            commands.destroy(entity_powerup);
+        }
+    }
+}
+
+fn expire_effect<T: TimedEffect>(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut T)>,
+) {
+    for (entity, mut effect) in query.iter_mut() {
+        if effect.timer_mut().tick(time.delta()).just_finished() {
+            commands.entity(entity).remove::<T>();
+        }
+    }
+}
+
+fn setup_powerup_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("BungeeSpice-Regular.ttf");
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect { left: Val::Px(10.0), top: Val::Px(10.0), ..default() },
+                flex_direction: FlexDirection::ColumnReverse,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            for kind in POWERUP_KINDS {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            display: Display::None,
+                            ..default()
+                        },
+                        color: Color::NONE.into(),
+                        ..default()
+                    })
+                    .insert(EffectHud(kind))
+                    .with_children(|row| {
+                        row.spawn_bundle(TextBundle::from_section(
+                            kind.label(),
+                            TextStyle { font: font.clone(), font_size: 20.0, ..default() },
+                        ))
+                        .insert(Tint::Powerup(kind));
+                        row.spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(HUD_BAR_WIDTH), Val::Px(6.0)),
+                                margin: UiRect { left: Val::Px(8.0), ..default() },
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .insert(EffectBar(kind))
+                        .insert(Tint::Powerup(kind));
+                    });
+            }
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style { align_items: AlignItems::Center, ..default() },
+                    color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn_bundle(TextBundle::from_section(
+                        "Heat",
+                        TextStyle { font: font.clone(), font_size: 20.0, color: Color::rgb(0.9, 0.9, 0.9) },
+                    ));
+                    row.spawn_bundle(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(0.0), Val::Px(6.0)),
+                            margin: UiRect { left: Val::Px(8.0), ..default() },
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .insert(HeatBar)
+                    .insert(Tint::Heat);
+                });
+        });
+}
+
+fn remaining_fraction(timer: &Timer) -> f32 {
+    1.0 - timer.percent()
+}
+
+// The strip follows the first player's ship, which is the one the main HUD belongs to.
+fn update_powerup_hud(
+    query_ship: Query<
+        (&PlayerId, &Weapon, Option<&Shield>, Option<&RapidFire>, Option<&DoublePoints>),
+        With<Spaceship>,
+    >,
+    mut query_rows: Query<(&mut Style, &EffectHud), Without<EffectBar>>,
+    mut query_bars: Query<(&mut Style, &EffectBar), (Without<EffectHud>, Without<HeatBar>)>,
+    mut query_heat: Query<(&mut Style, &mut Tint), (With<HeatBar>, Without<EffectHud>, Without<EffectBar>)>,
+) {
+    let ship = query_ship.iter().find(|(player, ..)| player.0 == 0);
+    let remaining = |kind: PowerupKind| -> Option<f32> {
+        let (_, _, shield, rapid_fire, double_points) = ship?;
+        match kind {
+            PowerupKind::Shield => shield.map(|effect| remaining_fraction(effect.timer())),
+            PowerupKind::RapidFire => rapid_fire.map(|effect| remaining_fraction(effect.timer())),
+            PowerupKind::DoublePoints => double_points.map(|effect| remaining_fraction(effect.timer())),
+        }
+    };
+
+    for (mut style, row) in query_rows.iter_mut() {
+        let display = if remaining(row.0).is_some() { Display::Flex } else { Display::None };
+        if style.display != display {
+            style.display = display;
+        }
+    }
+    for (mut style, bar) in query_bars.iter_mut() {
+        if let Some(fraction) = remaining(bar.0) {
+            style.size.width = Val::Px(HUD_BAR_WIDTH * fraction);
+        }
+    }
+    for (mut style, mut tint) in query_heat.iter_mut() {
+        let (heat, overheated) = ship.map(|(_, weapon, ..)| (weapon.heat, weapon.overheated)).unwrap_or((0.0, false));
+        style.size.width = Val::Px(HUD_BAR_WIDTH * heat.min(1.0));
+        // Only swapped when it changes, since a changed tint is recoloured.
+        let heat_tint = if overheated { Tint::Danger } else { Tint::Heat };
+        if *tint != heat_tint {
+            *tint = heat_tint;
+        }
+    }
+}
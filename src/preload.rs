@@ -0,0 +1,103 @@
+// Everything in assets/ is loaded while the game starts up and kept loaded, so nothing has to come
+// off the disk the first time it's needed mid-game. A run waits in GameState::Loading until it's all
+// arrived. Online games don't wait for it, as the peers would finish loading at different times and
+// start out of step.
+use bevy::asset::{AssetServerError, LoadState};
+use bevy::prelude::*;
+
+use crate::launch::LaunchOptions;
+
+// Everything in assets/, which has to be kept up to date by hand for the browser build.
+#[cfg(target_arch = "wasm32")]
+const WEB_ASSETS: [&str; 32] = [
+    "BungeeSpice-Regular.ttf",
+    "button.png",
+    "button_pressed.png",
+    "config.ron",
+    "game.entities.ron",
+    "meteor_2.png",
+    "meteor_2_spin.png",
+    "meteor_4.png",
+    "meteor_4_spin.png",
+    "meteor_8.png",
+    "meteor_8_spin.png",
+    "music/game.wav",
+    "music/game_drums.wav",
+    "music/game_lead.wav",
+    "music/menu.wav",
+    "sfx/explosion.wav",
+    "sfx/ship_explosion.wav",
+    "sfx/stinger_extra_life.wav",
+    "sfx/stinger_high_score.wav",
+    "sfx/stinger_wave_clear.wav",
+    "sfx/thrust.wav",
+    "sfx/ui_click.wav",
+    "sfx/ui_confirm.wav",
+    "sfx/ui_hover.wav",
+    "shaders/crt.wgsl",
+    "spaceship.png",
+    "themes/cartoon.theme.ron",
+    "themes/classic.theme.ron",
+    "themes/neon.theme.ron",
+    "thruster.png",
+    "waves/meteor_ring.wave.ron",
+];
+
+#[derive(Default)]
+pub struct AssetCache {
+    handles: Vec<HandleUntyped>,
+    ready: bool,
+}
+
+impl AssetCache {
+    pub fn ready(&self) -> bool {
+        self.ready
+    }
+}
+
+pub struct Preloading;
+
+impl Plugin for Preloading {
+    fn build(&self, app: &mut App) {
+        let online = app.world.get_resource::<LaunchOptions>().map_or(false, |options| options.online());
+        app
+            .insert_resource(AssetCache { handles: Vec::new(), ready: online })
+            .add_startup_system(preload_assets)
+            .add_system(check_preloaded);
+    }
+}
+
+// Files nothing knows how to load are left out, like the sprites on the headless server.
+#[cfg(not(target_arch = "wasm32"))]
+fn load_assets(asset_server: &AssetServer) -> Result<Vec<HandleUntyped>, AssetServerError> {
+    asset_server.load_folder(".")
+}
+
+// Browsers can't list what's in a folder, so there everything is asked for by name.
+#[cfg(target_arch = "wasm32")]
+fn load_assets(asset_server: &AssetServer) -> Result<Vec<HandleUntyped>, AssetServerError> {
+    Ok(WEB_ASSETS.iter().map(|path| asset_server.load_untyped(*path)).collect())
+}
+
+fn preload_assets(asset_server: Res<AssetServer>, mut cache: ResMut<AssetCache>) {
+    match load_assets(&asset_server) {
+        Ok(handles) => cache.handles = handles,
+        Err(err) => {
+            warn!("Could not preload assets: {:?}", err);
+            cache.ready = true;
+        }
+    }
+}
+
+// A file that fails to load is only missed when it's used, rather than holding the game up.
+fn check_preloaded(asset_server: Res<AssetServer>, mut cache: ResMut<AssetCache>) {
+    if cache.ready {
+        return;
+    }
+    match asset_server.get_group_load_state(cache.handles.iter().map(|handle| handle.id)) {
+        LoadState::Loaded => info!("Preloaded {} assets", cache.handles.len()),
+        LoadState::Failed => warn!("Some assets failed to preload"),
+        _ => return,
+    }
+    cache.ready = true;
+}
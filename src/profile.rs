@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::attract::Demo;
+use crate::hud::HudLayout;
+use crate::replay::Playback;
+use crate::tutorial::TutorialProgress;
+use crate::{storage, AppState, Game, MeteorDestroyedEvent, RunStats};
+
+pub const PROFILE_FILE: &str = "profile.ron";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ShipSkin {
+    Classic,
+    Crimson,
+    Gold,
+}
+
+impl ShipSkin {
+    // Lifetime meteors destroyed needed to unlock each skin.
+    fn unlocked_at(&self) -> u32 {
+        match self {
+            ShipSkin::Classic => 0,
+            ShipSkin::Crimson => 250,
+            ShipSkin::Gold => 1000,
+        }
+    }
+}
+
+const SHIP_SKINS: [ShipSkin; 3] = [ShipSkin::Classic, ShipSkin::Crimson, ShipSkin::Gold];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Achievement {
+    FirstBlood,
+    Centurion,
+    Survivor,
+    Veteran,
+}
+
+impl Achievement {
+    // How much progress completes the achievement.
+    pub fn goal(&self) -> u32 {
+        match self {
+            Achievement::FirstBlood => 1,
+            Achievement::Centurion => 100,
+            Achievement::Survivor => 5,
+            Achievement::Veteran => 50,
+        }
+    }
+}
+
+// Carried over between runs and restarts, unlike Game and RunStats.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub games_played: u32,
+    pub total_score: u64,
+    pub best_score: u32,
+    pub meteors_destroyed: u32,
+    pub waves_cleared: u32,
+    pub shots_fired: u32,
+    pub hits: u32,
+    pub unlocked_skins: Vec<ShipSkin>,
+    pub achievements: BTreeMap<Achievement, u32>,
+    pub hud: HudLayout,
+    pub tutorial: TutorialProgress,
+}
+
+impl Profile {
+    // Progress only ever goes up, so a better result replaces a worse one.
+    fn progress(&mut self, achievement: Achievement, value: u32) {
+        let progress = self.achievements.entry(achievement).or_insert(0);
+        *progress = (*progress).max(value.min(achievement.goal()));
+    }
+
+    fn unlock_skins(&mut self) {
+        for skin in SHIP_SKINS {
+            if self.meteors_destroyed >= skin.unlocked_at() && !self.unlocked_skins.contains(&skin) {
+                info!("Unlocked the {:?} ship skin", skin);
+                self.unlocked_skins.push(skin);
+            }
+        }
+    }
+}
+
+pub struct PlayerProfile;
+
+impl Plugin for PlayerProfile {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(load_profile())
+            .add_system(count_meteors_destroyed)
+            .add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(record_run))
+            .add_system(save_profile);
+    }
+}
+
+fn load_profile() -> Profile {
+    let mut profile = storage::load::<Profile>(PROFILE_FILE).unwrap_or_default();
+    profile.unlock_skins();
+    profile
+}
+
+fn count_meteors_destroyed(
+    playback: Option<Res<Playback>>,
+    demo: Option<Res<Demo>>,
+    mut destroyed_event: EventReader<MeteorDestroyedEvent>,
+    mut profile: ResMut<Profile>,
+) {
+    let destroyed = destroyed_event.iter().count() as u32;
+    if destroyed == 0 || playback.is_some() || demo.is_some() {
+        return;
+    }
+    profile.meteors_destroyed += destroyed;
+    let total = profile.meteors_destroyed;
+    profile.progress(Achievement::FirstBlood, total);
+    profile.progress(Achievement::Centurion, total);
+    profile.unlock_skins();
+}
+
+fn record_run(
+    query_game: Query<&Game>,
+    run_stats: Res<RunStats>,
+    playback: Option<Res<Playback>>,
+    demo: Option<Res<Demo>>,
+    mut profile: ResMut<Profile>,
+) {
+    if playback.is_some() || demo.is_some() {
+        return;
+    }
+    let game = query_game.single();
+    profile.games_played += 1;
+    profile.total_score += game.score as u64;
+    profile.best_score = profile.best_score.max(game.score);
+    profile.waves_cleared += run_stats.waves_cleared;
+    profile.shots_fired += run_stats.shots_fired;
+    profile.hits += run_stats.hits;
+    profile.progress(Achievement::Survivor, run_stats.waves_cleared);
+    let games_played = profile.games_played;
+    profile.progress(Achievement::Veteran, games_played);
+}
+
+fn save_profile(profile: Res<Profile>) {
+    if profile.is_changed() && !profile.is_added() {
+        storage::save(PROFILE_FILE, &*profile);
+    }
+}
@@ -0,0 +1,403 @@
+// Checkpoint races: a course of rings is laid out around the arena and the player flies through them
+// in order against the clock, dodging a handful of meteors on the way. Taking the last ring ends the
+// run. Each ring is a sensor, so flying through one is picked up by the physics the same way ships
+// hitting meteors are. The best finish is kept along with the path flown to set it, and a faded ghost
+// ship flies that path alongside later runs. In a two-player race either ship can take the next ring,
+// but only one-player runs set a ghost. Which modes are races is down to their rules, see ModeRules.
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+use bevy_rapier2d::prelude::{Collider, RapierContext, Sensor};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::animation::SpriteSheets;
+use crate::definitions::EntityDefinitions;
+use crate::game_mode::GameMode;
+use crate::menu::MENU_TEXT;
+use crate::palette::Tint;
+use crate::replay::{GameRng, Playback};
+use crate::spawning::{MeteorSpec, SpawnCommands};
+use crate::ui::set_text;
+use crate::{
+    arena_size, storage, AppState, DestroyCommands, Game, GameState, PlayerId, Spaceship, StartGameEvent,
+};
+
+const GHOST_FILE: &str = "race_ghost.ron";
+// Where the rings sit as fractions of half the arena, in the order they're flown. Ships start in the
+// middle facing the first one.
+const COURSE: [Vec2; 6] = [
+    Vec2::new(0.0, 0.6),
+    Vec2::new(0.7, 0.45),
+    Vec2::new(0.7, -0.45),
+    Vec2::new(0.0, -0.6),
+    Vec2::new(-0.7, -0.45),
+    Vec2::new(-0.7, 0.45),
+];
+const RING_RADIUS: f32 = 50.0;
+const RING_DOTS: usize = 16;
+const RING_DOT_SIZE: f32 = 6.0;
+const RACE_METEORS: usize = 5;
+const RACE_METEOR_SIZE: u8 = 4;
+const RACE_METEOR_SPEED: f32 = 50.0;
+// Meteors start at least this far from the ships and the rings.
+const METEOR_CLEARANCE: f32 = 120.0;
+// How often the ghost's path is sampled, it's smoothed out in between.
+const GHOST_INTERVAL: f32 = 0.05;
+// A jump this far between samples is the ship wrapping, which isn't smoothed.
+const GHOST_WRAP_JUMP: f32 = 200.0;
+const GHOST_COLOUR: Color = Color::rgba(1.0, 1.0, 1.0, 0.35);
+// Just behind the ships.
+const GHOST_Z: f32 = -0.1;
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct GhostFrame {
+    position: Vec2,
+    rotation: f32,
+}
+
+// The best finish and the path player one flew to set it, a frame every GHOST_INTERVAL.
+#[derive(Serialize, Deserialize)]
+struct Ghost {
+    time: f32,
+    frames: Vec<GhostFrame>,
+}
+
+impl Ghost {
+    // Where the ghost is `elapsed` seconds into the race, or None once it has finished.
+    fn frame_at(&self, elapsed: f32) -> Option<GhostFrame> {
+        let step = elapsed / GHOST_INTERVAL;
+        let index = step as usize;
+        let from = *self.frames.get(index)?;
+        let to = match self.frames.get(index + 1) {
+            Some(to) if to.position.distance(from.position) < GHOST_WRAP_JUMP => *to,
+            _ => return Some(from),
+        };
+        let along = step.fract();
+        let turn = (to.rotation - from.rotation + PI).rem_euclid(TAU) - PI;
+        Some(GhostFrame {
+            position: from.position.lerp(to.position, along),
+            rotation: from.rotation + turn * along,
+        })
+    }
+}
+
+fn clock(seconds: f32) -> String {
+    format!("{:.2}s", seconds)
+}
+
+#[derive(Default)]
+pub struct Race {
+    // The rings' positions in the arena, empty when the run isn't a race.
+    course: Vec<Vec2>,
+    // Whether the clock is running, which waits for the game to finish loading like the ships do.
+    started: bool,
+    next: usize,
+    elapsed: f32,
+    finish: Option<f32>,
+    best: Option<Ghost>,
+    recording: Vec<GhostFrame>,
+}
+
+impl Race {
+    // The race's lines for the game over screen.
+    pub fn summary(&self) -> [String; 2] {
+        let time = match self.finish {
+            Some(finish) => format!("Time: {}", clock(finish)),
+            None => "Did Not Finish".to_string(),
+        };
+        let best = match &self.best {
+            Some(ghost) => format!("Best Time: {}", clock(ghost.time)),
+            None => "Best Time: -".to_string(),
+        };
+        [time, best]
+    }
+}
+
+#[derive(Component)]
+pub struct Checkpoint {
+    index: usize,
+}
+
+#[derive(Component)]
+struct CheckpointDot {
+    index: usize,
+}
+
+#[derive(Component)]
+pub struct RaceGhost;
+
+#[derive(Component)]
+struct RaceTimerUI;
+
+pub struct Races;
+
+impl Plugin for Races {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Race>()
+            .add_startup_system(setup_race_timer)
+            .add_system(lay_out_course)
+            .add_system(update_race_timer)
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(start_race)
+                    .with_system(run_race_clock.after(start_race))
+                    .with_system(pass_checkpoints.after(run_race_clock))
+                    .with_system(highlight_checkpoints.after(pass_checkpoints))
+                    .with_system(fly_ghost.after(run_race_clock)),
+            );
+    }
+}
+
+// Whatever was left of the last race's rings went with the rest of its gameplay entities.
+fn lay_out_course(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    windows: Res<Windows>,
+    window_descriptor: Res<WindowDescriptor>,
+    sprite_sheets: Res<SpriteSheets>,
+    mut race: ResMut<Race>,
+    mut game_event: EventReader<StartGameEvent>,
+) {
+    if game_event.iter().last().is_none() {
+        return;
+    }
+    if !mode.rules().checkpoints {
+        *race = Race::default();
+        return;
+    }
+    let half_arena = arena_size(&windows, &window_descriptor) / 2.0;
+    let course: Vec<Vec2> = COURSE.iter().map(|point| *point * half_arena).collect();
+    for (index, position) in course.iter().enumerate() {
+        commands
+            .spawn_bundle(SpatialBundle {
+                transform: Transform::from_translation(position.extend(0.0)),
+                ..default()
+            })
+            .insert(Checkpoint { index })
+            .insert(Collider::ball(RING_RADIUS))
+            .insert(Sensor)
+            .with_children(|parent| {
+                for dot in 0..RING_DOTS {
+                    let offset = Vec2::from_angle(dot as f32 / RING_DOTS as f32 * TAU) * RING_RADIUS;
+                    parent
+                        .spawn_bundle(SpriteSheetBundle {
+                            texture_atlas: sprite_sheets.atlas(),
+                            sprite: TextureAtlasSprite {
+                                index: sprite_sheets.blank(),
+                                custom_size: Some(Vec2::splat(RING_DOT_SIZE)),
+                                ..default()
+                            },
+                            transform: Transform::from_translation(offset.extend(0.0)),
+                            ..default()
+                        })
+                        .insert(CheckpointDot { index })
+                        .insert(Tint::Friendly);
+                }
+            });
+    }
+    *race = Race { course, best: storage::load::<Ghost>(GHOST_FILE), ..default() };
+}
+
+// Meteors are rolled on the gameplay RNG like the waves', so replays get the same ones.
+fn start_race(
+    mut commands: Commands,
+    sprite_sheets: Res<SpriteSheets>,
+    definitions: Res<EntityDefinitions>,
+    mut race: ResMut<Race>,
+    mut rng: ResMut<GameRng>,
+    query_game: Query<&Game>,
+) {
+    let in_progress = matches!(query_game.single().gameState, GameState::InProgress);
+    if race.started || race.course.is_empty() || !in_progress {
+        return;
+    }
+    race.started = true;
+    // Meteors start somewhere among the rings.
+    let half_arena = race.course.iter().fold(Vec2::ZERO, |extent, point| extent.max(point.abs()));
+    let mut placed = 0;
+    while placed < RACE_METEORS {
+        let position = Vec2::new(
+            rng.0.gen_range(-half_arena.x..half_arena.x),
+            rng.0.gen_range(-half_arena.y..half_arena.y),
+        );
+        let clear = position.length() > METEOR_CLEARANCE
+            && race.course.iter().all(|ring| ring.distance(position) > METEOR_CLEARANCE);
+        if !clear {
+            continue;
+        }
+        let velocity = Vec2::from_angle(rng.0.gen_range(0.0..TAU)) * RACE_METEOR_SPEED;
+        commands.spawn_meteor(MeteorSpec::new(RACE_METEOR_SIZE).at(position).with_velocity(velocity));
+        placed += 1;
+    }
+    if race.best.is_some() {
+        commands
+            .spawn_bundle(SpriteSheetBundle {
+                texture_atlas: sprite_sheets.atlas(),
+                sprite: TextureAtlasSprite {
+                    index: sprite_sheets.ship(),
+                    color: GHOST_COLOUR,
+                    custom_size: Some(definitions.ship.size),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, GHOST_Z),
+                ..default()
+            })
+            .insert(RaceGhost);
+    }
+}
+
+// Player one's path is sampled on a fixed interval, so the ghost keeps time whatever the frame rate.
+fn run_race_clock(
+    time: Res<Time>,
+    mut race: ResMut<Race>,
+    query_game: Query<&Game>,
+    query_ship: Query<(&PlayerId, &Transform), With<Spaceship>>,
+) {
+    let in_progress = matches!(query_game.single().gameState, GameState::InProgress);
+    if !race.started || race.finish.is_some() || !in_progress {
+        return;
+    }
+    race.elapsed += time.delta_seconds();
+    let frame = query_ship.iter().find(|(player, _)| player.0 == 0).map(|(_, transform)| {
+        let (axis, angle) = transform.rotation.to_axis_angle();
+        GhostFrame { position: transform.translation.truncate(), rotation: axis.z * angle }
+    });
+    // A ship waiting to respawn holds the ghost where it went down.
+    let frame = match frame.or_else(|| race.recording.last().copied()) {
+        Some(frame) => frame,
+        None => return,
+    };
+    while race.recording.len() as f32 * GHOST_INTERVAL <= race.elapsed {
+        race.recording.push(frame);
+    }
+}
+
+fn pass_checkpoints(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    playback: Option<Res<Playback>>,
+    mut race: ResMut<Race>,
+    mut query_game: Query<&mut Game>,
+    query_checkpoint: Query<(Entity, &Checkpoint)>,
+    query_ship: Query<Entity, With<Spaceship>>,
+) {
+    if !race.started || race.finish.is_some() {
+        return;
+    }
+    let passed = query_checkpoint.iter().find(|(entity_checkpoint, checkpoint)| {
+        checkpoint.index == race.next
+            && query_ship
+                .iter()
+                .any(|entity_ship| rapier_context.intersection_pair(*entity_checkpoint, entity_ship) == Some(true))
+    });
+    let entity_checkpoint = match passed {
+        Some((entity_checkpoint, _)) => entity_checkpoint,
+        None => return,
+    };
+    commands.destroy(entity_checkpoint);
+    race.next += 1;
+    if race.next < race.course.len() {
+        return;
+    }
+    let time = race.elapsed;
+    race.finish = Some(time);
+    let mut game = query_game.single_mut();
+    game.gameState = GameState::Ended;
+    // A replay isn't a new run, and two ships sharing the course don't make a path worth following.
+    let improved = race.best.as_ref().map_or(true, |best| time < best.time);
+    if improved && playback.is_none() && game.players == 1 {
+        let ghost = Ghost { time, frames: std::mem::take(&mut race.recording) };
+        storage::save(GHOST_FILE, &ghost);
+        race.best = Some(ghost);
+    }
+}
+
+fn highlight_checkpoints(
+    mut commands: Commands,
+    race: Res<Race>,
+    query_dot: Query<(Entity, &CheckpointDot, &Tint)>,
+) {
+    for (entity, dot, tint) in query_dot.iter() {
+        let wanted = if dot.index == race.next { Tint::Accent } else { Tint::Friendly };
+        if *tint != wanted {
+            commands.entity(entity).insert(wanted);
+        }
+    }
+}
+
+fn fly_ghost(race: Res<Race>, mut query_ghost: Query<(&mut Transform, &mut Visibility), With<RaceGhost>>) {
+    let frame = race.best.as_ref().and_then(|ghost| ghost.frame_at(race.elapsed));
+    for (mut transform, mut visibility) in query_ghost.iter_mut() {
+        let visible = frame.is_some();
+        if visibility.is_visible != visible {
+            visibility.is_visible = visible;
+        }
+        if let Some(frame) = frame {
+            transform.translation = frame.position.extend(GHOST_Z);
+            transform.rotation = Quat::from_rotation_z(frame.rotation);
+        }
+    }
+}
+
+fn setup_race_timer(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                // The UI's y axis points up in this version of Bevy, so this is the top of the screen.
+                align_items: AlignItems::FlexEnd,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: asset_server.load("BungeeSpice-Regular.ttf"),
+                            font_size: 30.0,
+                            color: MENU_TEXT,
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect { top: Val::Px(50.0), ..default() },
+                        display: Display::None,
+                        ..default()
+                    }),
+                )
+                .insert(RaceTimerUI);
+        });
+}
+
+// Only touches the text when something differs, so the layout isn't recomputed every frame.
+fn update_race_timer(
+    race: Res<Race>,
+    query_game: Query<&Game>,
+    mut query_text: Query<(&mut Style, &mut Text), With<RaceTimerUI>>,
+) {
+    let in_progress = matches!(query_game.single().gameState, GameState::InProgress);
+    let racing = in_progress && !race.course.is_empty();
+    let value = if racing {
+        let best = race.best.as_ref().map_or("-".to_string(), |ghost| clock(ghost.time));
+        let ring = (race.next + 1).min(race.course.len());
+        format!("Time {}  Best {}  Ring {}/{}", clock(race.elapsed), best, ring, race.course.len())
+    } else {
+        String::new()
+    };
+    let display = if racing { Display::Flex } else { Display::None };
+    for (mut style, mut text) in query_text.iter_mut() {
+        if style.display != display {
+            style.display = display;
+        }
+        set_text(&mut text, &value);
+    }
+}
@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+
+use crate::hud::{HudElement, HudPart};
+use crate::palette::Tint;
+
+const RADAR_SIZE: Vec2 = Vec2::new(160.0, 100.0);
+const RADAR_DOT_SIZE: f32 = 4.0;
+const RADAR_PANEL_COLOUR: Color = Color::rgba(0.0, 0.3, 0.0, 0.3);
+
+// Anything carrying a blip shows up on the radar, so new threats only need to insert one.
+#[derive(Component)]
+pub struct RadarBlip {
+    pub tint: Tint,
+}
+
+#[derive(Component)]
+struct RadarPanel;
+
+#[derive(Component)]
+struct RadarDot(Entity);
+
+pub struct Radar;
+
+impl Plugin for Radar {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(setup_radar)
+            .add_system(add_radar_dots)
+            .add_system(update_radar_dots);
+    }
+}
+
+fn setup_radar(mut commands: Commands) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(RADAR_SIZE.x), Val::Px(RADAR_SIZE.y)),
+                position_type: PositionType::Absolute,
+                position: UiRect { right: Val::Px(10.0), top: Val::Px(10.0), ..default() },
+                ..default()
+            },
+            color: RADAR_PANEL_COLOUR.into(),
+            ..default()
+        })
+        .insert(RadarPanel)
+        .insert(HudElement { part: HudPart::Radar, opacity: RADAR_PANEL_COLOUR.a() });
+}
+
+fn add_radar_dots(
+    mut commands: Commands,
+    query_panel: Query<Entity, With<RadarPanel>>,
+    query_blips: Query<(Entity, &RadarBlip), Added<RadarBlip>>,
+) {
+    let panel = query_panel.single();
+    for (entity, blip) in query_blips.iter() {
+        let dot = commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(RADAR_DOT_SIZE), Val::Px(RADAR_DOT_SIZE)),
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                ..default()
+            })
+            .insert(RadarDot(entity))
+            .insert(blip.tint)
+            .insert(HudElement { part: HudPart::Radar, opacity: 1.0 })
+            .id();
+        commands.entity(panel).add_child(dot);
+    }
+}
+
+fn update_radar_dots(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    query_blips: Query<&Transform, With<RadarBlip>>,
+    mut query_dots: Query<(Entity, &mut Style, &RadarDot)>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        // Headless there's no window for the radar to map the arena onto.
+        None => return,
+    };
+    let world_size = Vec2::new(window.width(), window.height());
+    for (dot, mut style, target) in query_dots.iter_mut() {
+        if let Ok(transform) = query_blips.get(target.0) {
+            // World coordinates are centred on the origin, the panel's are from its bottom left.
+            let normalised = (transform.translation.truncate() / world_size + 0.5)
+                .clamp(Vec2::ZERO, Vec2::ONE);
+            let position = normalised * (RADAR_SIZE - RADAR_DOT_SIZE);
+            style.position = UiRect {
+                left: Val::Px(position.x),
+                bottom: Val::Px(position.y),
+                ..default()
+            };
+        } else {
+            commands.entity(dot).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,298 @@
+// Runs are recorded as the RNG seed plus each frame's length and which controls were held.
+// Playing one back reseeds the gameplay RNG, feeds the recorded frame lengths into Time and
+// presses the recorded keys, so the game simulates the same run again.
+use std::time::Duration;
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{random, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::attract::Demo;
+use crate::deterministic::Deterministic;
+use crate::game_mode::GameMode;
+use crate::input::{ControlSchemes, Handling};
+use crate::launch::LaunchOptions;
+use crate::menu::MENU_TEXT;
+use crate::settings::Settings;
+use crate::{storage, AppState, Game, StartGameEvent};
+
+const REPLAY_FILE: &str = "best_replay.ron";
+
+// All randomness that affects gameplay comes from here so replays can reproduce it.
+// Purely visual randomness like particles and screen shake doesn't need to.
+pub struct GameRng(pub StdRng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng(StdRng::seed_from_u64(random()))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct ReplayFrame {
+    delta: f32,
+    // Held controls for each player, see ControlScheme::held.
+    controls: [u8; 2],
+}
+
+#[derive(Serialize, Deserialize)]
+struct Replay {
+    // Replays from before modes existed are all classic games.
+    #[serde(default)]
+    mode: GameMode,
+    // Likewise, replays from before the handling settings were recorded with the defaults.
+    #[serde(default)]
+    handling: Handling,
+    seed: u64,
+    players: u8,
+    score: u32,
+    frames: Vec<ReplayFrame>,
+}
+
+struct Recording(Replay);
+
+// While this exists the game is showing a replay rather than being played, so anything that
+// shouldn't count it as a real game, like high scores, checks for it.
+pub struct Playback {
+    replay: Replay,
+    frame: usize,
+    started: bool,
+}
+
+impl Playback {
+    fn current(&self) -> Option<ReplayFrame> {
+        self.replay.frames.get(self.frame).copied()
+    }
+}
+
+// Shown on the main menu once there's a replay to watch.
+#[derive(Component)]
+pub struct WatchReplayButton;
+
+#[derive(Component)]
+struct ReplayBanner;
+
+pub struct Replays;
+
+impl Plugin for Replays {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameRng>()
+            .add_startup_system(setup_replay_banner)
+            .add_system(watch_replay)
+            .add_system(choose_handling.before(start_recording))
+            .add_system(start_recording)
+            .add_system(show_replay_banner)
+            .add_system_to_stage(CoreStage::PreUpdate, drive_replay_controls.after(InputSystem))
+            .add_system_to_stage(CoreStage::Last, advance_replay)
+            .add_system_set(SystemSet::on_update(AppState::InGame).with_system(record_frame))
+            .add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(save_best_replay))
+            .add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(stop_replay));
+    }
+}
+
+pub fn has_replay() -> bool {
+    storage::exists(REPLAY_FILE)
+}
+
+fn watch_replay(
+    mut commands: Commands,
+    query: Query<&Interaction, (Changed<Interaction>, With<WatchReplayButton>)>,
+    mut start_game_event: EventWriter<StartGameEvent>,
+    mut mode: ResMut<GameMode>,
+) {
+    if !query.iter().any(|interaction| *interaction == Interaction::Clicked) {
+        return;
+    }
+    match storage::load::<Replay>(REPLAY_FILE) {
+        Some(replay) => {
+            *mode = replay.mode;
+            start_game_event.send(StartGameEvent { players: replay.players });
+            commands.insert_resource(Playback { replay, frame: 0, started: false });
+        }
+        None => warn!("Replay could not be loaded"),
+    }
+}
+
+// Replays fly the way they were recorded, online games with the defaults so every peer flies the
+// ships the same, as do attract mode demos, and anything else the way the mode or the settings say.
+fn choose_handling(
+    mut game_event: EventReader<StartGameEvent>,
+    mode: Res<GameMode>,
+    playback: Option<Res<Playback>>,
+    demo: Option<Res<Demo>>,
+    settings: Res<Settings>,
+    options: Option<Res<LaunchOptions>>,
+    mut handling: ResMut<Handling>,
+) {
+    if game_event.iter().last().is_none() {
+        return;
+    }
+    let online = options.map_or(false, |options| options.online());
+    *handling = match playback.filter(|playback| !playback.started) {
+        Some(playback) => playback.replay.handling,
+        None if online || demo.is_some() => Handling::default(),
+        None => mode
+            .rules()
+            .handling
+            .unwrap_or(Handling { rotation: settings.turn_speed, thrust: settings.thrust_strength }),
+    };
+}
+
+// Every new game reseeds the RNG, either from the replay being watched or a seed that gets
+// recorded, which is fresh unless the simulation is deterministic. Duels aren't recorded, the
+// best replay is the highest score and duels don't have one. Nor are games Twitch chat can spawn
+// into, since what chat sends isn't in the replay and playing it back would go differently.
+fn start_recording(
+    mut commands: Commands,
+    mut game_event: EventReader<StartGameEvent>,
+    mut playback: Option<ResMut<Playback>>,
+    mode: Res<GameMode>,
+    options: Option<Res<LaunchOptions>>,
+    handling: Res<Handling>,
+    deterministic: Option<Res<Deterministic>>,
+    mut rng: ResMut<GameRng>,
+) {
+    let ev = match game_event.iter().last() {
+        Some(ev) => ev,
+        None => return,
+    };
+    if let Some(playback) = playback.as_mut().filter(|playback| !playback.started) {
+        playback.started = true;
+        rng.0 = StdRng::seed_from_u64(playback.replay.seed);
+        commands.remove_resource::<Recording>();
+        return;
+    }
+    // Restarting from a replay's game-over screen starts a real game.
+    commands.remove_resource::<Playback>();
+    let seed = deterministic.map(|deterministic| deterministic.seed).unwrap_or_else(random);
+    rng.0 = StdRng::seed_from_u64(seed);
+    let chat_spawns = options.map_or(false, |options| options.twitch.is_some());
+    if *mode == GameMode::Duel || chat_spawns {
+        commands.remove_resource::<Recording>();
+        return;
+    }
+    commands.insert_resource(Recording(Replay {
+        mode: *mode,
+        handling: *handling,
+        seed,
+        players: ev.players,
+        score: 0,
+        frames: Vec::new(),
+    }));
+}
+
+fn record_frame(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    schemes: Res<ControlSchemes>,
+    recording: Option<ResMut<Recording>>,
+) {
+    if let Some(mut recording) = recording {
+        let mut controls = [0; 2];
+        for (player, held) in controls.iter_mut().enumerate().take(recording.0.players as usize) {
+            *held = schemes.for_player(player as u8).held(&keyboard_input);
+        }
+        recording.0.frames.push(ReplayFrame { delta: time.delta_seconds(), controls });
+    }
+}
+
+// Keeps a replay of the highest scoring run. Demos are recorded like any other game but never kept.
+fn save_best_replay(
+    mut commands: Commands,
+    query_game: Query<&Game>,
+    demo: Option<Res<Demo>>,
+    recording: Option<ResMut<Recording>>,
+) {
+    let mut recording = match recording {
+        Some(recording) if demo.is_none() => recording,
+        _ => return,
+    };
+    recording.0.score = query_game.single().score;
+    let best = storage::load::<Replay>(REPLAY_FILE).map(|replay| replay.score);
+    if best.map(|best| recording.0.score >= best).unwrap_or(true) {
+        storage::save(REPLAY_FILE, &recording.0);
+    }
+    commands.remove_resource::<Recording>();
+}
+
+fn stop_replay(mut commands: Commands) {
+    commands.remove_resource::<Playback>();
+    commands.remove_resource::<Recording>();
+}
+
+// Runs last of the systems that change Time, replacing this frame's length with the recorded one.
+pub fn drive_replay_time(state: Res<State<AppState>>, playback: Option<Res<Playback>>, mut time: ResMut<Time>) {
+    let frame = match playback.as_ref().filter(|_| *state.current() == AppState::InGame) {
+        Some(playback) => playback.current(),
+        None => return,
+    };
+    if let (Some(frame), Some(last_update)) = (frame, time.last_update()) {
+        time.update_with_instant(last_update + Duration::from_secs_f32(frame.delta));
+    }
+}
+
+fn drive_replay_controls(
+    state: Res<State<AppState>>,
+    playback: Option<Res<Playback>>,
+    schemes: Res<ControlSchemes>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+) {
+    let frame = match playback.as_ref().filter(|_| *state.current() == AppState::InGame) {
+        Some(playback) => playback.current(),
+        None => return,
+    };
+    let frame = match frame {
+        Some(frame) => frame,
+        None => return,
+    };
+    for (player, held) in frame.controls.iter().enumerate() {
+        schemes.for_player(player as u8).press(&mut keyboard_input, *held);
+    }
+}
+
+// Replays that run out before the recorded game over went out of sync, so head back to the menu.
+fn advance_replay(playback: Option<ResMut<Playback>>, mut state: ResMut<State<AppState>>) {
+    let mut playback = match playback {
+        Some(playback) if *state.current() == AppState::InGame => playback,
+        _ => return,
+    };
+    playback.frame += 1;
+    if playback.frame >= playback.replay.frames.len() {
+        if let Err(err) = state.set(AppState::MainMenu) {
+            warn!("Could not leave replay: {:?}", err);
+        }
+    }
+}
+
+fn setup_replay_banner(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                "REPLAY",
+                TextStyle {
+                    font: asset_server.load("BungeeSpice-Regular.ttf"),
+                    font_size: 40.0,
+                    color: MENU_TEXT,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect { bottom: Val::Px(10.0), left: Val::Px(10.0), ..default() },
+                display: Display::None,
+                ..default()
+            }),
+        )
+        .insert(ReplayBanner);
+}
+
+fn show_replay_banner(playback: Option<Res<Playback>>, mut query: Query<&mut Style, With<ReplayBanner>>) {
+    let display = if playback.is_some() { Display::Flex } else { Display::None };
+    for mut style in query.iter_mut() {
+        if style.display != display {
+            style.display = display;
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::Velocity;
+use serde::{Deserialize, Serialize};
+
+use crate::attract::Demo;
+use crate::game_mode::GameMode;
+use crate::replay::Playback;
+use crate::spawning::{MeteorSpec, SpawnCommands};
+use crate::wave::WaveState;
+use crate::{
+    cleanup_game, storage, AppState, DestroyCommands, Game, GameState, Meteor, PlayerId, RunStats,
+    Spaceship, StartGameEvent,
+};
+
+const SAVE_FILE: &str = "run.ron";
+
+#[derive(Serialize, Deserialize, Clone)]
+struct SavedShip {
+    player: u8,
+    position: Vec2,
+    rotation: f32,
+    velocity: Vec2,
+    angular_velocity: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct SavedMeteor {
+    size: u8,
+    position: Vec2,
+    velocity: Vec2,
+}
+
+// Everything needed to pick a run back up where it was left.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SavedRun {
+    mode: GameMode,
+    score: u32,
+    player_scores: [u32; 2],
+    lives: u8,
+    player_lives: [u8; 2],
+    shared_lives: bool,
+    players: u8,
+    wave: u32,
+    shots_fired: u32,
+    hits: u32,
+    waves_cleared: u32,
+    ships: Vec<SavedShip>,
+    meteors: Vec<SavedMeteor>,
+}
+
+// Shown on the main menu while there's a saved run to go back to.
+#[derive(Component)]
+pub struct ContinueButton;
+
+// A run being resumed, applied once the new game has spawned its ships.
+struct ResumeRun(SavedRun);
+
+// Ship positions waiting for the ships they belong to to be spawned. Applied to whichever
+// ships exist as soon as there are any, which is the frame the new game spawns them.
+struct PendingShips(Vec<SavedShip>);
+
+pub struct SaveGame;
+
+impl Plugin for SaveGame {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(continue_saved_run)
+            .add_system(discard_saved_run)
+            .add_system(restore_ships)
+            .add_system_set(SystemSet::on_update(AppState::InGame).with_system(resume_run))
+            // Has to see the run before cleanup_game clears it away.
+            .add_system_set(SystemSet::on_exit(AppState::InGame).with_system(save_run.before(cleanup_game)));
+    }
+}
+
+pub fn has_saved_run() -> bool {
+    storage::exists(SAVE_FILE)
+}
+
+// What a run in progress looks like right now, for saving it.
+#[derive(SystemParam)]
+pub struct RunSnapshot<'w, 's> {
+    query_game: Query<'w, 's, &'static Game>,
+    mode: Res<'w, GameMode>,
+    wave: Res<'w, WaveState>,
+    run_stats: Res<'w, RunStats>,
+    query_ship: Query<'w, 's, (&'static PlayerId, &'static Transform, &'static Velocity), With<Spaceship>>,
+    query_meteor: Query<'w, 's, (&'static Meteor, &'static Transform, &'static Velocity)>,
+    playback: Option<Res<'w, Playback>>,
+    demo: Option<Res<'w, Demo>>,
+}
+
+impl<'w, 's> RunSnapshot<'w, 's> {
+    // Runs that ended in a game over have nothing to continue, and neither do replays or demos.
+    // Duels and races are too short to be worth continuing.
+    pub fn capture(&self) -> Option<SavedRun> {
+        let game = self.query_game.single();
+        let in_progress = matches!(game.gameState, GameState::InProgress);
+        let short = matches!(*self.mode, GameMode::Duel | GameMode::Race);
+        if !in_progress || self.playback.is_some() || self.demo.is_some() || short {
+            return None;
+        }
+        let ships = self
+            .query_ship
+            .iter()
+            .map(|(player, transform, velocity)| {
+                let (axis, angle) = transform.rotation.to_axis_angle();
+                SavedShip {
+                    player: player.0,
+                    position: transform.translation.truncate(),
+                    rotation: axis.z * angle,
+                    velocity: velocity.linvel,
+                    angular_velocity: velocity.angvel,
+                }
+            })
+            .collect();
+        let meteors = self
+            .query_meteor
+            .iter()
+            .map(|(meteor, transform, velocity)| SavedMeteor {
+                size: meteor.size,
+                position: transform.translation.truncate(),
+                velocity: velocity.linvel,
+            })
+            .collect();
+        Some(SavedRun {
+            mode: *self.mode,
+            score: game.score,
+            player_scores: game.player_scores,
+            lives: game.lives,
+            player_lives: game.player_lives,
+            shared_lives: game.shared_lives,
+            players: game.players,
+            wave: self.wave.number,
+            shots_fired: self.run_stats.shots_fired,
+            hits: self.run_stats.hits,
+            waves_cleared: self.run_stats.waves_cleared,
+            ships,
+            meteors,
+        })
+    }
+}
+
+pub fn write_run(run: &SavedRun) {
+    storage::save(SAVE_FILE, run);
+}
+
+// Only catches quitting out mid-run, see RunSnapshot::capture.
+fn save_run(snapshot: RunSnapshot) {
+    if let Some(run) = snapshot.capture() {
+        write_run(&run);
+    }
+}
+
+fn continue_saved_run(
+    mut commands: Commands,
+    query: Query<&Interaction, (Changed<Interaction>, With<ContinueButton>)>,
+    mut start_game_event: EventWriter<StartGameEvent>,
+    mut mode: ResMut<GameMode>,
+) {
+    if !query.iter().any(|interaction| *interaction == Interaction::Clicked) {
+        return;
+    }
+    match storage::load::<SavedRun>(SAVE_FILE) {
+        Some(run) => {
+            *mode = run.mode;
+            start_game_event.send(StartGameEvent { players: run.players });
+            commands.insert_resource(ResumeRun(run));
+        }
+        None => warn!("Saved run could not be loaded"),
+    }
+}
+
+// A save can only be continued once, any new run replaces it.
+fn discard_saved_run(mut game_event: EventReader<StartGameEvent>) {
+    if game_event.iter().last().is_some() {
+        storage::remove(SAVE_FILE);
+    }
+}
+
+// Waits for the fresh game to be in progress so its reset doesn't undo the restored values.
+fn resume_run(
+    mut commands: Commands,
+    resume: Option<Res<ResumeRun>>,
+    mut query_game: Query<&mut Game>,
+    mut wave: ResMut<WaveState>,
+    mut run_stats: ResMut<RunStats>,
+) {
+    let run = match resume {
+        Some(resume) => resume.0.clone(),
+        None => return,
+    };
+    let mut game = query_game.single_mut();
+    if !matches!(game.gameState, GameState::InProgress) {
+        return;
+    }
+    game.score = run.score;
+    game.player_scores = run.player_scores;
+    game.lives = run.lives;
+    game.player_lives = run.player_lives;
+    game.shared_lives = run.shared_lives;
+    wave.number = run.wave;
+    *run_stats = RunStats {
+        shots_fired: run.shots_fired,
+        hits: run.hits,
+        waves_cleared: run.waves_cleared,
+    };
+    for meteor in run.meteors {
+        commands.spawn_meteor(MeteorSpec::new(meteor.size).at(meteor.position).with_velocity(meteor.velocity));
+    }
+    commands.insert_resource(PendingShips(run.ships));
+    commands.remove_resource::<ResumeRun>();
+}
+
+fn restore_ships(
+    mut commands: Commands,
+    pending: Option<Res<PendingShips>>,
+    mut query_ship: Query<(Entity, &PlayerId, &mut Transform, &mut Velocity), With<Spaceship>>,
+) {
+    let pending = match pending {
+        Some(pending) => pending,
+        None => return,
+    };
+    let mut restored = false;
+    for (entity, player, mut transform, mut velocity) in query_ship.iter_mut() {
+        match pending.0.iter().find(|saved| saved.player == player.0) {
+            Some(saved) => {
+                transform.translation = saved.position.extend(transform.translation.z);
+                transform.rotation = Quat::from_rotation_z(saved.rotation);
+                velocity.linvel = saved.velocity;
+                velocity.angvel = saved.angular_velocity;
+            }
+            // That player had run out of their own lives.
+            None => commands.destroy(entity),
+        }
+        restored = true;
+    }
+    if restored {
+        commands.remove_resource::<PendingShips>();
+    }
+}
@@ -0,0 +1,112 @@
+// Whatever scores sends a ScoreEvent rather than adding to the Game itself, and score_points turns
+// them into points in one place: the double points powerup and trick shots multiply them, an extra
+// life comes every extra_life_score points in config.ron in modes that give them, a popup shows
+// what was scored where, and the run's stats are kept. It runs with the gameplay systems so online
+// games score the same on both sides. Rule scripts still add to the score directly, since they can
+// take points away as well, see scripting.rs.
+use bevy::prelude::*;
+
+use crate::config::GameConfig;
+use crate::game_mode::GameMode;
+use crate::palette::Tint;
+use crate::powerups::DoublePoints;
+use crate::{Game, Lifetime, PlayerId, RunStats, Spaceship};
+
+const POPUP_DURATION: f32 = 0.8;
+// Units a second the popups drift up by as they fade.
+const POPUP_RISE: f32 = 40.0;
+const POPUP_FONT_SIZE: f32 = 18.0;
+// Over the meteors and their explosions.
+const POPUP_Z: f32 = 5.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScoreSource {
+    // How many times the bullet had bounced off the edges counts towards trick shots.
+    MeteorShot { player: u8, ricochets: u8 },
+}
+
+impl ScoreSource {
+    fn player(&self) -> u8 {
+        match *self {
+            ScoreSource::MeteorShot { player, .. } => player,
+        }
+    }
+}
+
+pub struct ScoreEvent {
+    // Before any multipliers.
+    pub amount: u32,
+    pub source: ScoreSource,
+    pub position: Vec2,
+}
+
+#[derive(Component)]
+struct ScorePopup;
+
+pub struct ScorePopups;
+
+impl Plugin for ScorePopups {
+    fn build(&self, app: &mut App) {
+        app.add_system(float_popups);
+    }
+}
+
+pub fn score_points(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+    mode: Res<GameMode>,
+    query_doubled: Query<&PlayerId, (With<Spaceship>, With<DoublePoints>)>,
+    mut query_game: Query<&mut Game>,
+    mut run_stats: ResMut<RunStats>,
+    mut score_event: EventReader<ScoreEvent>,
+) {
+    let rules = mode.rules();
+    for ev in score_event.iter() {
+        let points = match ev.source {
+            ScoreSource::MeteorShot { player, ricochets } => {
+                run_stats.hits += 1;
+                let doubled = query_doubled.iter().any(|owner| owner.0 == player);
+                let points = if doubled { ev.amount.saturating_mul(2) } else { ev.amount };
+                if rules.trick_shots { points.saturating_mul(u32::from(ricochets)) } else { points }
+            }
+        };
+        if points == 0 {
+            continue;
+        }
+        let player = ev.source.player();
+        let mut game = query_game.single_mut();
+        let before = game.score;
+        game.score = game.score.saturating_add(points);
+        let player_score = &mut game.player_scores[player as usize];
+        *player_score = player_score.saturating_add(points);
+        let every = config.extra_life_score;
+        if rules.extra_lives && every > 0 && game.score / every > before / every {
+            game.gain_life(player);
+        }
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::from_section(
+                    format!("+{}", points),
+                    TextStyle {
+                        font: asset_server.load("BungeeSpice-Regular.ttf"),
+                        font_size: POPUP_FONT_SIZE,
+                        ..default()
+                    },
+                )
+                .with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_translation(ev.position.extend(POPUP_Z)),
+                ..default()
+            })
+            .insert(ScorePopup)
+            .insert(Tint::Accent)
+            .insert(Lifetime(Timer::from_seconds(POPUP_DURATION, false)));
+    }
+}
+
+fn float_popups(time: Res<Time>, mut query: Query<(&Lifetime, &mut Transform, &mut Text), With<ScorePopup>>) {
+    for (lifetime, mut transform, mut text) in query.iter_mut() {
+        transform.translation.y += POPUP_RISE * time.delta_seconds();
+        text.sections[0].style.color.set_a(1.0 - lifetime.0.percent());
+    }
+}
@@ -0,0 +1,264 @@
+// Rule scripts, for game variants that don't need a new build. The script named with --script is
+// loaded from assets/scripts and can define any of these hooks, which are called as the game goes:
+//
+//   on_wave_start(wave)                 as each wave starts
+//   on_meteor_destroyed(size, x, y)     whenever a meteor is shot
+//   on_tick(seconds)                    every frame of the game, with the seconds since the last
+//
+// From them it can call spawn_meteor(size, x, y, vx, vy), spawn_powerup(kind, x, y) with one of
+// "shield", "rapid_fire" or "double_points", and add_score(points), which takes points off when
+// they're negative. Positions are fractions of half the arena like in wave files, so (1.0, 1.0) is
+// the top right corner, and velocities are in pixels a second. add_score changes the score the
+// players share rather than either one's own. Scripts are sandboxed: Rhai has no way to reach files,
+// the network or the rest of the game, eval is turned off, and each call is cut off after so many
+// operations so a runaway loop can't hang the game. A script that fails is turned off until it's
+// edited, which reloads it. Scripts aren't recorded in replays, so a replay of a scripted run only
+// plays back properly with the same script, and online games leave them out since the peers
+// couldn't be sure of running the same one.
+use std::sync::{Arc, Mutex};
+
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use rhai::{Dynamic, Engine, FuncArgs, Scope, AST, FLOAT, INT};
+
+use crate::launch::LaunchOptions;
+use crate::powerups::PowerupKind;
+use crate::spawning::{MeteorSpec, PowerupSpec, SpawnCommands};
+use crate::wave::WaveStartEvent;
+use crate::{arena_size, AppState, Game, GameState, MeteorDestroyedEvent};
+
+const SCRIPTS_FOLDER: &str = "scripts";
+// Plenty for a hook doing a few spawns, and over in a blink when a script loops forever.
+const MAX_OPERATIONS: u64 = 100_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_COLLECTION_SIZE: usize = 1_000;
+const MAX_STRING_SIZE: usize = 1_000;
+
+#[derive(TypeUuid)]
+#[uuid = "c4e8a1f3-6b2d-4d97-8e5a-0f3b7c6d2a91"]
+struct ScriptSource(String);
+
+#[derive(Default)]
+struct ScriptSourceLoader;
+
+impl AssetLoader for ScriptSourceLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let source = String::from_utf8(bytes.to_vec())?;
+            load_context.set_default_asset(LoadedAsset::new(ScriptSource(source)));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["rhai"]
+    }
+}
+
+enum ScriptAction {
+    SpawnMeteor { size: u8, position: Vec2, velocity: Vec2 },
+    SpawnPowerup { kind: PowerupKind, position: Vec2 },
+    AddScore(INT),
+}
+
+// Where the script's calls are collected during a hook, to be carried out once it returns.
+#[derive(Default)]
+struct ScriptOutbox {
+    half_arena: Vec2,
+    actions: Vec<ScriptAction>,
+}
+
+struct RuleScript {
+    // The file in assets/scripts.
+    file: String,
+    engine: Engine,
+    // None until the script has loaded and compiled, and again once it fails.
+    ast: Option<AST>,
+    // Keeps the asset alive so the asset server goes on watching the file for changes.
+    handle: Handle<ScriptSource>,
+    outbox: Arc<Mutex<ScriptOutbox>>,
+}
+
+impl RuleScript {
+    // Hooks the script doesn't define are skipped.
+    fn call(&mut self, hook: &str, args: impl FuncArgs) {
+        let ast = match &self.ast {
+            Some(ast) => ast,
+            None => return,
+        };
+        if !ast.iter_functions().any(|function| function.name == hook) {
+            return;
+        }
+        if let Err(err) = self.engine.call_fn::<Dynamic>(&mut Scope::new(), ast, hook, args) {
+            warn!("Turning the script off until it's edited, {} failed: {}", hook, err);
+            self.ast = None;
+        }
+    }
+}
+
+fn powerup_kind(name: &str) -> Option<PowerupKind> {
+    match name {
+        "shield" => Some(PowerupKind::Shield),
+        "rapid_fire" => Some(PowerupKind::RapidFire),
+        "double_points" => Some(PowerupKind::DoublePoints),
+        _ => None,
+    }
+}
+
+fn sandboxed_engine(outbox: &Arc<Mutex<ScriptOutbox>>) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .disable_symbol("eval")
+        .on_print(|text| info!("Script: {}", text))
+        .on_debug(|text, _, _| info!("Script: {}", text));
+    let meteors = outbox.clone();
+    engine.register_fn("spawn_meteor", move |size: INT, x: FLOAT, y: FLOAT, vx: FLOAT, vy: FLOAT| {
+        let mut outbox = meteors.lock().unwrap();
+        let position = Vec2::new(x as f32, y as f32) * outbox.half_arena;
+        outbox.actions.push(ScriptAction::SpawnMeteor {
+            size: size.clamp(1, u8::MAX as INT) as u8,
+            position,
+            velocity: Vec2::new(vx as f32, vy as f32),
+        });
+    });
+    let powerups = outbox.clone();
+    engine.register_fn("spawn_powerup", move |kind: &str, x: FLOAT, y: FLOAT| {
+        let kind = match powerup_kind(kind) {
+            Some(kind) => kind,
+            None => {
+                warn!("Script asked for an unknown powerup {}", kind);
+                return;
+            }
+        };
+        let mut outbox = powerups.lock().unwrap();
+        let position = Vec2::new(x as f32, y as f32) * outbox.half_arena;
+        outbox.actions.push(ScriptAction::SpawnPowerup { kind, position });
+    });
+    let score = outbox.clone();
+    engine.register_fn("add_score", move |points: INT| {
+        score.lock().unwrap().actions.push(ScriptAction::AddScore(points));
+    });
+    engine
+}
+
+pub struct GameScripts;
+
+impl Plugin for GameScripts {
+    fn build(&self, app: &mut App) {
+        let options = app.world.get_resource::<LaunchOptions>().cloned().unwrap_or_default();
+        let file = match options.script {
+            Some(file) if !options.online() => file,
+            Some(_) => {
+                warn!("Scripts aren't run in online games");
+                return;
+            }
+            None => return,
+        };
+        let outbox = Arc::new(Mutex::new(ScriptOutbox::default()));
+        app
+            .add_asset::<ScriptSource>()
+            .init_asset_loader::<ScriptSourceLoader>()
+            .insert_resource(RuleScript {
+                file,
+                engine: sandboxed_engine(&outbox),
+                ast: None,
+                handle: Handle::default(),
+                outbox,
+            })
+            .add_startup_system(load_script)
+            .add_system(compile_script)
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(run_hooks)
+                    .with_system(apply_script_actions.after(run_hooks)),
+            );
+    }
+}
+
+fn load_script(asset_server: Res<AssetServer>, mut script: ResMut<RuleScript>) {
+    script.handle = asset_server.load(format!("{}/{}", SCRIPTS_FOLDER, script.file).as_str());
+}
+
+fn compile_script(
+    sources: Res<Assets<ScriptSource>>,
+    mut asset_events: EventReader<AssetEvent<ScriptSource>>,
+    mut script: ResMut<RuleScript>,
+) {
+    for ev in asset_events.iter() {
+        match ev {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } if *handle == script.handle => {
+                let source = match sources.get(handle) {
+                    Some(source) => source,
+                    None => continue,
+                };
+                match script.engine.compile(&source.0) {
+                    Ok(ast) => {
+                        info!("Loaded {}", script.file);
+                        script.ast = Some(ast);
+                    }
+                    Err(err) => {
+                        warn!("Could not compile {}: {}", script.file, err);
+                        script.ast = None;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn run_hooks(
+    time: Res<Time>,
+    windows: Res<Windows>,
+    window_descriptor: Res<WindowDescriptor>,
+    mut script: ResMut<RuleScript>,
+    query_game: Query<&Game>,
+    mut wave_event: EventReader<WaveStartEvent>,
+    mut destroyed_event: EventReader<MeteorDestroyedEvent>,
+) {
+    if !matches!(query_game.single().gameState, GameState::InProgress) {
+        return;
+    }
+    let half_arena = arena_size(&windows, &window_descriptor) / 2.0;
+    script.outbox.lock().unwrap().half_arena = half_arena;
+    for ev in wave_event.iter() {
+        script.call("on_wave_start", (ev.number as INT,));
+    }
+    for ev in destroyed_event.iter() {
+        let position = ev.position / half_arena;
+        script.call("on_meteor_destroyed", (ev.size as INT, position.x as FLOAT, position.y as FLOAT));
+    }
+    script.call("on_tick", (time.delta_seconds() as FLOAT,));
+}
+
+fn apply_script_actions(
+    mut commands: Commands,
+    script: Res<RuleScript>,
+    mut query_game: Query<&mut Game>,
+) {
+    let actions = std::mem::take(&mut script.outbox.lock().unwrap().actions);
+    for action in actions {
+        match action {
+            ScriptAction::SpawnMeteor { size, position, velocity } => {
+                commands.spawn_meteor(MeteorSpec::new(size).at(position).with_velocity(velocity))
+            }
+            ScriptAction::SpawnPowerup { kind, position } => {
+                commands.spawn_powerup(PowerupSpec::new(kind).at(position));
+            }
+            ScriptAction::AddScore(points) => {
+                let mut game = query_game.single_mut();
+                game.score = (game.score as INT + points).clamp(0, u32::MAX as INT) as u32;
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+// The game without rendering or audio, run by the asteroids-server binary. It plays game after game
+// with both ships flown by an autopilot mashing random controls, logging how each one ended, which
+// makes it a soak test for the simulation on machines with no screen or sound card. Runs are
+// always deterministic and each game is reseeded as it starts, so any game it logs can be played
+// back by passing its seed to --seed, where it's the first game played.
+use bevy::asset::AssetPlugin;
+use bevy::hierarchy::HierarchyPlugin;
+use bevy::input::{InputPlugin, InputSystem};
+use bevy::prelude::*;
+use bevy::transform::TransformPlugin;
+use rand::rngs::StdRng;
+use rand::{random, Rng, SeedableRng};
+
+use crate::animation::SpriteAnimations;
+use crate::bullets::Bullets;
+use crate::camera::CameraShakeEvent;
+use crate::config::Config;
+use crate::definitions::Definitions;
+use crate::deterministic::{Deterministic, DeterministicSimulation};
+use crate::duel::Duels;
+use crate::input::ControlScheme;
+use crate::launch::{LaunchOptions, HEADLESS_WINDOWS};
+use crate::meteor_pool::MeteorPooling;
+use crate::powerups::Powerups;
+use crate::preload::Preloading;
+use crate::race::Races;
+use crate::replay::GameRng;
+use crate::settings::Settings;
+use crate::station::StationDefense;
+use crate::stingers::MilestoneEvent;
+use crate::theme::Theme;
+use crate::time_limit::TimeLimits;
+use crate::wave::{WaveState, Waves};
+use crate::wave_script::ScriptedWaves;
+use crate::{AppState, CoreGameplay, Game, GameEvents, SetupScreen, Simulation, StartGameEvent};
+
+const SERVER_PLAYERS: u8 = 2;
+
+// Kept apart from GameRng so the autopilot doesn't change what the game itself rolls.
+pub struct Autopilot(pub StdRng);
+
+// The first game plays the seed the server was started with, every later one a seed drawn from it.
+struct GameSeeds {
+    current: u64,
+    next: u64,
+    rng: StdRng,
+}
+
+impl GameSeeds {
+    fn new(seed: u64) -> Self {
+        GameSeeds { current: seed, next: seed, rng: StdRng::seed_from_u64(seed) }
+    }
+
+    fn advance(&mut self) -> u64 {
+        self.current = self.next;
+        self.next = self.rng.gen();
+        self.current
+    }
+}
+
+pub fn run_server() {
+    let options = LaunchOptions::from_args();
+    let seed = options.seed.unwrap_or_else(random);
+    headless_app(options, seed)
+        .insert_resource(Autopilot(StdRng::seed_from_u64(seed)))
+        .insert_resource(GameSeeds::new(seed))
+        .add_system(start_next_game)
+        .add_system_to_stage(CoreStage::PreUpdate, fly_ships.after(InputSystem))
+        .add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(report_game))
+        .run();
+}
+
+// The whole simulation with nothing to draw to or play through, also what the tests in lib.rs step.
+pub(crate) fn headless_app(options: LaunchOptions, seed: u64) -> App {
+    let mut app = App::new();
+    app
+        .insert_resource(options.window_descriptor())
+        .insert_resource(options.mode)
+        .insert_resource(options)
+        .insert_resource(Deterministic { seed })
+        .add_state(AppState::MainMenu)
+        // Just what the game needs from DefaultPlugins.
+        .add_plugins(MinimalPlugins)
+        .add_plugin(TransformPlugin)
+        .add_plugin(HierarchyPlugin)
+        .add_plugin(InputPlugin)
+        .add_plugin(HEADLESS_WINDOWS)
+        .add_plugin(AssetPlugin)
+        .add_asset::<TextureAtlas>()
+        // Sent for the camera and the stingers, which the server has neither of.
+        .add_event::<CameraShakeEvent>()
+        .add_event::<MilestoneEvent>()
+        .init_resource::<Settings>()
+        .init_resource::<Theme>()
+        .init_resource::<GameRng>()
+        .add_plugin(GameEvents)
+        .add_plugin(Simulation)
+        .add_plugin(Bullets)
+        .add_plugin(MeteorPooling)
+        .add_plugin(Preloading)
+        .add_plugin(SetupScreen)
+        .add_plugin(SpriteAnimations)
+        .add_plugin(Config)
+        .add_plugin(Definitions)
+        .add_plugin(Waves)
+        .add_plugin(ScriptedWaves)
+        .add_plugin(Duels)
+        .add_plugin(Powerups)
+        .add_plugin(StationDefense)
+        .add_plugin(Races)
+        .add_plugin(TimeLimits)
+        .add_plugin(CoreGameplay)
+        .add_plugin(DeterministicSimulation);
+    app
+}
+
+fn start_next_game(
+    mut state: ResMut<State<AppState>>,
+    mut seeds: ResMut<GameSeeds>,
+    mut rng: ResMut<GameRng>,
+    mut autopilot: ResMut<Autopilot>,
+    mut start_game_event: EventWriter<StartGameEvent>,
+) {
+    if !matches!(state.current(), AppState::MainMenu | AppState::GameOver) {
+        return;
+    }
+    // Reseeded before anything hears the game is starting, just like --seed does for the first game.
+    let seed = seeds.advance();
+    rng.0 = StdRng::seed_from_u64(seed);
+    autopilot.0 = StdRng::seed_from_u64(seed);
+    start_game_event.send(StartGameEvent { players: SERVER_PLAYERS });
+    if let Err(err) = state.set(AppState::InGame) {
+        warn!("Could not start game: {:?}", err);
+    }
+}
+
+// Holds a random set of each player's controls every frame. The bench flies its ship with it too.
+pub fn fly_ships(
+    mut autopilot: ResMut<Autopilot>,
+    query_game: Query<&Game>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+) {
+    for player in 0..query_game.single().players {
+        let held = autopilot.0.gen();
+        ControlScheme::for_player(player).press(&mut keyboard_input, held);
+    }
+}
+
+fn report_game(
+    time: Res<Time>,
+    seeds: Res<GameSeeds>,
+    wave: Res<WaveState>,
+    query_game: Query<&Game>,
+    mut games: Local<u32>,
+) {
+    *games += 1;
+    let game = query_game.single();
+    info!(
+        "Game {} over on wave {} with {} points, {:.0}s into seed {}",
+        *games,
+        wave.number,
+        game.score,
+        time.seconds_since_startup(),
+        seeds.current
+    );
+}
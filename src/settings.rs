@@ -0,0 +1,540 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::hud::{HudLayout, HudPart, HUD_VISIBILITIES};
+use crate::input::{ControlPreset, CONTROL_PRESETS};
+use crate::menu::{spawn_menu_button, spawn_menu_root, spawn_menu_title, MenuButton, MENU_TEXT};
+use crate::launch::LaunchOptions;
+use crate::palette::ModPalettes;
+use crate::profile::Profile;
+use crate::theme::{LoadedThemes, DEFAULT_THEME};
+use crate::{storage, AppState};
+
+// Screen shake is a multiplier on the camera trauma, 0.0 turns it off entirely.
+const SCREEN_SHAKE_MAX: f32 = 1.0;
+const SCREEN_SHAKE_STEP: f32 = 0.25;
+
+// 0.0 means pick a scale from the window size, anything else is a manual override.
+pub const UI_SCALE_AUTO: f32 = 0.0;
+const UI_SCALE_STEPS: [f32; 6] = [UI_SCALE_AUTO, 0.75, 1.0, 1.25, 1.5, 2.0];
+// The HUD was laid out for a 720 pixel tall window.
+const UI_REFERENCE_HEIGHT: f32 = 720.0;
+
+const VOLUME_STEP: f32 = 0.1;
+
+// Multipliers on how quickly ships turn and how hard they thrust.
+const HANDLING_MIN: f32 = 0.5;
+const HANDLING_MAX: f32 = 2.0;
+const HANDLING_STEP: f32 = 0.1;
+
+// A fraction of the normal simulation speed, see game_speed.rs.
+const GAME_SPEED_MIN: f32 = 0.5;
+const GAME_SPEED_STEP: f32 = 0.1;
+
+// 0 leaves the frame rate up to vsync, or as fast as it'll go without it.
+pub const FPS_CAP_UNLIMITED: u32 = 0;
+const FPS_CAP_STEPS: [u32; 4] = [30, 60, 120, FPS_CAP_UNLIMITED];
+
+const COLOUR_PALETTES: [ColourPalette; 4] = [
+    ColourPalette::Standard,
+    ColourPalette::Deuteranopia,
+    ColourPalette::Protanopia,
+    ColourPalette::Tritanopia,
+];
+
+const SETTINGS_FILE: &str = "settings.ron";
+
+#[derive(Clone, Copy)]
+pub enum VolumeChannel {
+    Music,
+    Sfx,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RenderStyle {
+    Sprites,
+    // Glowing line outlines like the original arcade cabinet.
+    Vector,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PhysicsPreset {
+    Quality,
+    // For weaker hardware, see physics_preset.rs.
+    Performance,
+}
+
+// Named for the kind of colour blindness each is safe for, see palette.rs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColourPalette {
+    Standard,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+// Saved whenever it changes, options added since the file was written get their defaults.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub screen_shake: f32,
+    pub ui_scale: f32,
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub muted: bool,
+    pub bloom: bool,
+    pub render_style: RenderStyle,
+    // By name, see theme.rs.
+    pub theme: String,
+    pub crt: bool,
+    // No screen shake, flashes or big explosion bursts, the game itself plays the same. There's no
+    // hit-stop to turn off.
+    pub reduced_motion: bool,
+    pub palette: ColourPalette,
+    // A palette added by a mod, by name, used in place of the one above while it's there, see mods.rs.
+    pub mod_palette: Option<String>,
+    // Bright outlines on the ship, meteors and bullets over a darker background, for low vision.
+    pub high_contrast: bool,
+    pub game_speed: f32,
+    // Flashes the screen edges with a label for sounds that carry information, see visual_cues.rs.
+    pub visual_cues: bool,
+    pub turn_speed: f32,
+    pub thrust_strength: f32,
+    pub control_preset: ControlPreset,
+    pub vsync: bool,
+    pub fps_cap: u32,
+    pub physics_preset: PhysicsPreset,
+    // In two-player games, whether both ships draw from one pool of lives or each has their own.
+    pub shared_lives: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            screen_shake: SCREEN_SHAKE_MAX,
+            ui_scale: UI_SCALE_AUTO,
+            master_volume: 1.0,
+            music_volume: 0.7,
+            sfx_volume: 1.0,
+            muted: false,
+            bloom: true,
+            render_style: RenderStyle::Sprites,
+            theme: DEFAULT_THEME.to_string(),
+            crt: false,
+            reduced_motion: false,
+            palette: ColourPalette::Standard,
+            mod_palette: None,
+            high_contrast: false,
+            game_speed: 1.0,
+            visual_cues: false,
+            turn_speed: 1.0,
+            thrust_strength: 1.0,
+            control_preset: ControlPreset::Arrows,
+            vsync: true,
+            fps_cap: FPS_CAP_UNLIMITED,
+            physics_preset: PhysicsPreset::Quality,
+            shared_lives: true,
+        }
+    }
+}
+
+impl Settings {
+    // What a sound on the given channel should actually be played at.
+    pub fn volume(&self, channel: VolumeChannel) -> f32 {
+        if self.muted {
+            return 0.0;
+        }
+        let channel_volume = match channel {
+            VolumeChannel::Music => self.music_volume,
+            VolumeChannel::Sfx => self.sfx_volume,
+        };
+        self.master_volume * channel_volume
+    }
+}
+
+// Rounded to whole steps so repeated presses don't drift away from 0% and 100%.
+fn step_rounded(value: f32, step: f32, steps: i32) -> f32 {
+    ((value + step * steps as f32) / step).round() * step
+}
+
+fn step_volume(volume: f32, steps: i32) -> f32 {
+    step_rounded(volume, VOLUME_STEP, steps)
+}
+
+fn step_through<T: Copy + PartialEq>(steps: &[T], current: T, by: i32) -> T {
+    let index = steps.iter().position(|step| *step == current).unwrap_or(0) as i32;
+    steps[(index + by).clamp(0, steps.len() as i32 - 1) as usize]
+}
+
+// Options that depend on what's been loaded, by name.
+struct LoadedChoices {
+    mod_palettes: Vec<String>,
+    themes: Vec<String>,
+}
+
+// None when there's nothing loaded to pick from.
+fn step_name(names: &[String], current: &str, by: i32) -> Option<String> {
+    let last = names.len().checked_sub(1)? as i32;
+    let index = names.iter().position(|name| name == current).unwrap_or(0) as i32;
+    names.get((index + by).clamp(0, last) as usize).cloned()
+}
+
+// Through the built-in palettes and then any mods' palettes.
+fn step_palette(settings: &mut Settings, mod_palettes: &[String], by: i32) {
+    let index = match &settings.mod_palette {
+        Some(name) => mod_palettes
+            .iter()
+            .position(|mod_palette| mod_palette == name)
+            .map(|mod_index| COLOUR_PALETTES.len() + mod_index),
+        None => COLOUR_PALETTES.iter().position(|palette| *palette == settings.palette),
+    };
+    let last = (COLOUR_PALETTES.len() + mod_palettes.len()) as i32 - 1;
+    let index = (index.unwrap_or(0) as i32 + by).clamp(0, last) as usize;
+    match index.checked_sub(COLOUR_PALETTES.len()) {
+        Some(mod_index) => settings.mod_palette = Some(mod_palettes[mod_index].clone()),
+        None => {
+            settings.palette = COLOUR_PALETTES[index];
+            settings.mod_palette = None;
+        }
+    }
+}
+
+// Each adjustable option is one row on the settings screen with a value and -/+ buttons.
+#[derive(Clone, Copy)]
+enum SettingKind {
+    ScreenShake,
+    UiScale,
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    Mute,
+    Bloom,
+    RenderStyle,
+    Theme,
+    Crt,
+    ReducedMotion,
+    Palette,
+    HighContrast,
+    GameSpeed,
+    VisualCues,
+    TurnSpeed,
+    ThrustStrength,
+    Controls,
+    Hud(HudPart),
+    HudCorners,
+    Vsync,
+    FpsCap,
+    Physics,
+    SharedLives,
+}
+
+const SETTING_ROWS: [SettingKind; 26] = [
+    SettingKind::ScreenShake,
+    SettingKind::UiScale,
+    SettingKind::MasterVolume,
+    SettingKind::MusicVolume,
+    SettingKind::SfxVolume,
+    SettingKind::Mute,
+    SettingKind::Bloom,
+    SettingKind::RenderStyle,
+    SettingKind::Theme,
+    SettingKind::Crt,
+    SettingKind::ReducedMotion,
+    SettingKind::Palette,
+    SettingKind::HighContrast,
+    SettingKind::GameSpeed,
+    SettingKind::VisualCues,
+    SettingKind::TurnSpeed,
+    SettingKind::ThrustStrength,
+    SettingKind::Controls,
+    SettingKind::Hud(HudPart::Score),
+    SettingKind::Hud(HudPart::Lives),
+    SettingKind::Hud(HudPart::Radar),
+    SettingKind::HudCorners,
+    SettingKind::Vsync,
+    SettingKind::FpsCap,
+    SettingKind::Physics,
+    SettingKind::SharedLives,
+];
+
+impl SettingKind {
+    fn label(&self, settings: &Settings, hud: &HudLayout) -> String {
+        match self {
+            SettingKind::ScreenShake => match settings.screen_shake {
+                shake if shake <= 0.0 => "Screen Shake: Off".to_string(),
+                shake => format!("Screen Shake: {:.0}%", shake * 100.0),
+            },
+            SettingKind::UiScale => match settings.ui_scale {
+                scale if scale == UI_SCALE_AUTO => "UI Scale: Auto".to_string(),
+                scale => format!("UI Scale: {:.0}%", scale * 100.0),
+            },
+            SettingKind::MasterVolume => format!("Master Volume: {:.0}%", settings.master_volume * 100.0),
+            SettingKind::MusicVolume => format!("Music Volume: {:.0}%", settings.music_volume * 100.0),
+            SettingKind::SfxVolume => format!("SFX Volume: {:.0}%", settings.sfx_volume * 100.0),
+            SettingKind::Mute => format!("Mute: {}", if settings.muted { "On" } else { "Off" }),
+            SettingKind::Bloom => format!("Bloom: {}", if settings.bloom { "On" } else { "Off" }),
+            SettingKind::RenderStyle => format!("Render Style: {:?}", settings.render_style),
+            SettingKind::Theme => format!("Theme: {}", settings.theme),
+            SettingKind::Crt => format!("CRT Filter: {}", if settings.crt { "On" } else { "Off" }),
+            SettingKind::ReducedMotion => {
+                format!("Reduced Motion: {}", if settings.reduced_motion { "On" } else { "Off" })
+            }
+            SettingKind::Palette => match &settings.mod_palette {
+                Some(name) => format!("Colours: {}", name),
+                None => format!("Colours: {:?}", settings.palette),
+            },
+            SettingKind::HighContrast => {
+                format!("High Contrast: {}", if settings.high_contrast { "On" } else { "Off" })
+            }
+            SettingKind::GameSpeed => format!("Game Speed: {:.0}%", settings.game_speed * 100.0),
+            SettingKind::VisualCues => {
+                format!("Visual Cues: {}", if settings.visual_cues { "On" } else { "Off" })
+            }
+            SettingKind::TurnSpeed => format!("Turn Speed: {:.0}%", settings.turn_speed * 100.0),
+            SettingKind::ThrustStrength => format!("Thrust: {:.0}%", settings.thrust_strength * 100.0),
+            SettingKind::Controls => format!("Controls: {}", settings.control_preset.label()),
+            SettingKind::Hud(part) => {
+                let name = match part {
+                    HudPart::Score => "Score",
+                    HudPart::Lives => "Lives",
+                    HudPart::Radar => "Radar",
+                };
+                format!("HUD {}: {:?}", name, hud.visibility(*part))
+            }
+            SettingKind::HudCorners => {
+                format!("HUD Corners: {}", if hud.swap_corners { "Swapped" } else { "Normal" })
+            }
+            SettingKind::Vsync => format!("VSync: {}", if settings.vsync { "On" } else { "Off" }),
+            SettingKind::FpsCap => match settings.fps_cap {
+                FPS_CAP_UNLIMITED => "FPS Cap: Unlimited".to_string(),
+                cap => format!("FPS Cap: {}", cap),
+            },
+            SettingKind::Physics => format!("Physics: {:?}", settings.physics_preset),
+            SettingKind::SharedLives => {
+                format!("Co-op Lives: {}", if settings.shared_lives { "Shared" } else { "Separate" })
+            }
+        }
+    }
+
+    fn adjust(&self, settings: &mut Settings, loaded: &LoadedChoices, steps: i32) {
+        match self {
+            SettingKind::ScreenShake => {
+                settings.screen_shake = (settings.screen_shake + SCREEN_SHAKE_STEP * steps as f32)
+                    .clamp(0.0, SCREEN_SHAKE_MAX);
+            }
+            SettingKind::UiScale => {
+                settings.ui_scale = step_through(&UI_SCALE_STEPS, settings.ui_scale, steps);
+            }
+            SettingKind::MasterVolume => {
+                settings.master_volume = step_volume(settings.master_volume, steps).clamp(0.0, 1.0);
+            }
+            SettingKind::MusicVolume => {
+                settings.music_volume = step_volume(settings.music_volume, steps).clamp(0.0, 1.0);
+            }
+            SettingKind::SfxVolume => {
+                settings.sfx_volume = step_volume(settings.sfx_volume, steps).clamp(0.0, 1.0);
+            }
+            SettingKind::Mute => settings.muted = !settings.muted,
+            SettingKind::Bloom => settings.bloom = !settings.bloom,
+            SettingKind::RenderStyle => {
+                settings.render_style = match settings.render_style {
+                    RenderStyle::Sprites => RenderStyle::Vector,
+                    RenderStyle::Vector => RenderStyle::Sprites,
+                };
+            }
+            SettingKind::Theme => {
+                if let Some(theme) = step_name(&loaded.themes, &settings.theme, steps) {
+                    settings.theme = theme;
+                }
+            }
+            SettingKind::Crt => settings.crt = !settings.crt,
+            SettingKind::ReducedMotion => settings.reduced_motion = !settings.reduced_motion,
+            SettingKind::Palette => step_palette(settings, &loaded.mod_palettes, steps),
+            SettingKind::HighContrast => settings.high_contrast = !settings.high_contrast,
+            SettingKind::GameSpeed => {
+                settings.game_speed =
+                    step_rounded(settings.game_speed, GAME_SPEED_STEP, steps).clamp(GAME_SPEED_MIN, 1.0);
+            }
+            SettingKind::TurnSpeed => {
+                settings.turn_speed =
+                    step_rounded(settings.turn_speed, HANDLING_STEP, steps).clamp(HANDLING_MIN, HANDLING_MAX);
+            }
+            SettingKind::ThrustStrength => {
+                settings.thrust_strength =
+                    step_rounded(settings.thrust_strength, HANDLING_STEP, steps).clamp(HANDLING_MIN, HANDLING_MAX);
+            }
+            SettingKind::VisualCues => settings.visual_cues = !settings.visual_cues,
+            SettingKind::Controls => {
+                settings.control_preset = step_through(&CONTROL_PRESETS, settings.control_preset, steps);
+            }
+            // Kept in the profile, see adjust_hud.
+            SettingKind::Hud(_) | SettingKind::HudCorners => {}
+            SettingKind::Vsync => settings.vsync = !settings.vsync,
+            SettingKind::FpsCap => settings.fps_cap = step_through(&FPS_CAP_STEPS, settings.fps_cap, steps),
+            SettingKind::Physics => {
+                settings.physics_preset = match settings.physics_preset {
+                    PhysicsPreset::Quality => PhysicsPreset::Performance,
+                    PhysicsPreset::Performance => PhysicsPreset::Quality,
+                };
+            }
+            SettingKind::SharedLives => settings.shared_lives = !settings.shared_lives,
+        }
+    }
+
+    // The HUD rows change the profile rather than the settings, see hud.rs.
+    fn adjusts_profile(&self) -> bool {
+        matches!(self, SettingKind::Hud(_) | SettingKind::HudCorners)
+    }
+
+    fn adjust_hud(&self, hud: &mut HudLayout, steps: i32) {
+        match self {
+            SettingKind::Hud(part) => {
+                let visibility = hud.visibility_mut(*part);
+                *visibility = step_through(&HUD_VISIBILITIES, *visibility, steps);
+            }
+            SettingKind::HudCorners => hud.swap_corners = !hud.swap_corners,
+            _ => {}
+        }
+    }
+}
+
+#[derive(Component)]
+struct SettingAdjust {
+    kind: SettingKind,
+    steps: i32,
+}
+
+#[derive(Component)]
+struct SettingValue(SettingKind);
+
+#[derive(Component)]
+struct SettingsScreen;
+
+pub struct GameSettings;
+
+impl Plugin for GameSettings {
+    fn build(&self, app: &mut App) {
+        // Loaded here rather than in a startup system so the first frame already uses it.
+        let mut settings = storage::load::<Settings>(SETTINGS_FILE).unwrap_or_default();
+        // Same as turning Mute on from the settings menu.
+        if app.world.get_resource::<LaunchOptions>().map_or(false, |options| options.mute) {
+            settings.muted = true;
+        }
+        app
+            .insert_resource(settings)
+            .add_system(save_settings)
+            .add_system(apply_ui_scale)
+            .add_system_set(SystemSet::on_enter(AppState::Settings).with_system(setup_settings_menu))
+            .add_system_set(
+                SystemSet::on_update(AppState::Settings)
+                    .with_system(adjust_setting)
+                    .with_system(update_setting_values),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Settings).with_system(despawn_settings_menu));
+    }
+}
+
+fn setup_settings_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    profile: Res<Profile>,
+) {
+    let text_style = TextStyle {
+        font: asset_server.load("BungeeSpice-Regular.ttf"),
+        font_size: 30.0,
+        color: MENU_TEXT,
+    };
+    spawn_menu_root(&mut commands)
+        .insert(SettingsScreen)
+        .with_children(|parent| {
+            spawn_menu_title(parent, &asset_server, "Settings");
+            for kind in SETTING_ROWS {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        color: Color::NONE.into(),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        spawn_menu_button(row, &asset_server, "-").insert(SettingAdjust { kind, steps: -1 });
+                        row.spawn_bundle(
+                            TextBundle::from_section(kind.label(&settings, &profile.hud), text_style.clone())
+                                .with_style(Style {
+                                    margin: UiRect::all(Val::Px(10.0)),
+                                    ..default()
+                                }),
+                        )
+                        .insert(SettingValue(kind));
+                        spawn_menu_button(row, &asset_server, "+").insert(SettingAdjust { kind, steps: 1 });
+                    });
+            }
+            spawn_menu_button(parent, &asset_server, "Back").insert(MenuButton::Back);
+        });
+}
+
+fn adjust_setting(
+    query: Query<(&Interaction, &SettingAdjust), Changed<Interaction>>,
+    mod_palettes: Res<ModPalettes>,
+    themes: Res<LoadedThemes>,
+    mut settings: ResMut<Settings>,
+    mut profile: ResMut<Profile>,
+) {
+    for (interaction, adjust) in query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        if adjust.kind.adjusts_profile() {
+            adjust.kind.adjust_hud(&mut profile.hud, adjust.steps);
+        } else {
+            let loaded = LoadedChoices { mod_palettes: mod_palettes.names(), themes: themes.names() };
+            adjust.kind.adjust(&mut settings, &loaded, adjust.steps);
+        }
+    }
+}
+
+fn update_setting_values(
+    settings: Res<Settings>,
+    profile: Res<Profile>,
+    mut query: Query<(&mut Text, &SettingValue)>,
+) {
+    if !settings.is_changed() && !profile.is_changed() {
+        return;
+    }
+    for (mut text, value) in query.iter_mut() {
+        text.sections[0].value = value.0.label(&settings, &profile.hud);
+    }
+}
+
+fn save_settings(settings: Res<Settings>) {
+    if settings.is_changed() && !settings.is_added() {
+        storage::save(SETTINGS_FILE, &*settings);
+    }
+}
+
+// Window sizes are in logical pixels, so the display's DPI is already accounted for here.
+fn apply_ui_scale(windows: Res<Windows>, settings: Res<Settings>, mut ui_scale: ResMut<UiScale>) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        // Headless there's no window to scale the UI to.
+        None => return,
+    };
+    let scale = if settings.ui_scale == UI_SCALE_AUTO {
+        (window.height() / UI_REFERENCE_HEIGHT).clamp(0.75, 2.0)
+    } else {
+        settings.ui_scale
+    } as f64;
+    if ui_scale.scale != scale {
+        ui_scale.scale = scale;
+    }
+}
+
+fn despawn_settings_menu(mut commands: Commands, query: Query<Entity, With<SettingsScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+
+use crate::audio::{load_sound, SfxChannel};
+use crate::camera::CameraFollow;
+use crate::input::ControlScheme;
+use crate::{AppState, MeteorDestroyedEvent, ShipDestroyedEvent, Spaceship};
+
+// How far past the edge of the view a sound has to be before it drops to half volume.
+const ATTENUATION_DISTANCE: f32 = 400.0;
+
+// Thrust fades in and rises in pitch over this long while Up is held.
+const THRUST_RAMP: f32 = 1.5;
+const THRUST_MIN_VOLUME: f32 = 0.3;
+const THRUST_MIN_RATE: f32 = 0.8;
+const THRUST_MAX_RATE: f32 = 1.3;
+const THRUST_FADE_OUT: Duration = Duration::from_millis(150);
+
+struct ThrustLoop {
+    instance: Handle<AudioInstance>,
+    held: f32,
+}
+
+// Engine loops keyed by the ship playing them, so a destroyed ship's loop can be found and stopped.
+#[derive(Default)]
+struct ThrustSounds(HashMap<Entity, ThrustLoop>);
+
+// Where sounds are heard from, which is the first player's view.
+struct Listener {
+    position: Vec2,
+    half_view: Vec2,
+}
+
+impl Listener {
+    // Returns kira's panning (0.0 hard left, 1.0 hard right) and a volume for a sound at `position`.
+    fn spatialise(&self, position: Vec2) -> (f64, f64) {
+        let offset = position - self.position;
+        let panning = 0.5 + 0.5 * (offset.x / self.half_view.x).clamp(-1.0, 1.0);
+        let beyond_view = (offset.abs() - self.half_view).max(Vec2::ZERO).length();
+        let volume = 1.0 / (1.0 + beyond_view / ATTENUATION_DISTANCE);
+        (panning as f64, volume as f64)
+    }
+}
+
+pub struct UiSounds {
+    pub hover: Handle<AudioSource>,
+    pub click: Handle<AudioSource>,
+    pub confirm: Handle<AudioSource>,
+}
+
+struct SoundEffectHandles {
+    explosion: Handle<AudioSource>,
+    ship_explosion: Handle<AudioSource>,
+    thrust: Handle<AudioSource>,
+}
+
+pub struct SoundEffects;
+
+impl Plugin for SoundEffects {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ThrustSounds>()
+            .add_startup_system(load_sound_effects)
+            .add_system(play_explosions)
+            .add_system(play_thrust);
+    }
+}
+
+fn load_sound_effects(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut audio_sources: ResMut<Assets<AudioSource>>,
+) {
+    commands.insert_resource(SoundEffectHandles {
+        explosion: load_sound(&asset_server, &mut audio_sources, "sfx/explosion.wav"),
+        ship_explosion: load_sound(&asset_server, &mut audio_sources, "sfx/ship_explosion.wav"),
+        thrust: load_sound(&asset_server, &mut audio_sources, "sfx/thrust.wav"),
+    });
+    commands.insert_resource(UiSounds {
+        hover: load_sound(&asset_server, &mut audio_sources, "sfx/ui_hover.wav"),
+        click: load_sound(&asset_server, &mut audio_sources, "sfx/ui_click.wav"),
+        confirm: load_sound(&asset_server, &mut audio_sources, "sfx/ui_confirm.wav"),
+    });
+}
+
+fn play_explosions(
+    windows: Res<Windows>,
+    sfx: Res<AudioChannel<SfxChannel>>,
+    sounds: Res<SoundEffectHandles>,
+    query_camera: Query<(&Transform, &CameraFollow)>,
+    mut meteor_event: EventReader<MeteorDestroyedEvent>,
+    mut ship_event: EventReader<ShipDestroyedEvent>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        // Explosions are placed within the window, and headless there's no window or anyone to hear them.
+        None => return,
+    };
+    let listener = Listener {
+        position: query_camera
+            .iter()
+            .find(|(_, follow)| follow.0 == 0)
+            .map(|(transform, _)| transform.translation.truncate())
+            .unwrap_or(Vec2::ZERO),
+        half_view: Vec2::new(window.width(), window.height()) / 2.0,
+    };
+
+    let play_at = |sound: &Handle<AudioSource>, position: Vec2| {
+        let (panning, volume) = listener.spatialise(position);
+        sfx.play(sound.clone()).with_panning(panning).with_volume(volume);
+    };
+    for ev in meteor_event.iter() {
+        play_at(&sounds.explosion, ev.position);
+    }
+    for ev in ship_event.iter() {
+        play_at(&sounds.ship_explosion, ev.position);
+    }
+}
+
+fn play_thrust(
+    time: Res<Time>,
+    state: Res<State<AppState>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    sfx: Res<AudioChannel<SfxChannel>>,
+    sounds: Res<SoundEffectHandles>,
+    query_ship: Query<(Entity, &ControlScheme), With<Spaceship>>,
+    mut thrust_sounds: ResMut<ThrustSounds>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    let in_game = *state.current() == AppState::InGame;
+    for (ship, scheme) in query_ship.iter() {
+        if !in_game || !keyboard_input.pressed(scheme.thrust) {
+            continue;
+        }
+        let thrust = thrust_sounds.0.entry(ship).or_insert_with(|| ThrustLoop {
+            instance: sfx.play(sounds.thrust.clone()).looped().with_volume(0.0).handle(),
+            held: 0.0,
+        });
+        thrust.held += time.delta_seconds();
+        let ramp = (thrust.held / THRUST_RAMP).min(1.0);
+        if let Some(instance) = audio_instances.get_mut(&thrust.instance) {
+            let volume = THRUST_MIN_VOLUME + (1.0 - THRUST_MIN_VOLUME) * ramp;
+            let rate = THRUST_MIN_RATE + (THRUST_MAX_RATE - THRUST_MIN_RATE) * ramp;
+            instance.set_volume(volume as f64, AudioTween::default());
+            instance.set_playback_rate(rate as f64, AudioTween::default());
+        }
+    }
+
+    // Stop loops whose ship let go of thrust, left the game or was destroyed.
+    thrust_sounds.0.retain(|ship, thrust| {
+        let still_thrusting = in_game
+            && query_ship
+                .get(*ship)
+                .map(|(_, scheme)| keyboard_input.pressed(scheme.thrust))
+                .unwrap_or(false);
+        if !still_thrusting {
+            if let Some(instance) = audio_instances.get_mut(&thrust.instance) {
+                instance.stop(AudioTween::linear(THRUST_FADE_OUT));
+            }
+        }
+        still_thrusting
+    });
+}
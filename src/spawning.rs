@@ -0,0 +1,371 @@
+// How meteors, ships and powerups are put into the game, by the game's own systems and by anything
+// built on top of it like rule scripts and chat commands. Each is described by a spec and spawned
+// with the matching method on Commands, e.g.
+// `commands.spawn_meteor(MeteorSpec::large().at(position).with_velocity(velocity))`. The spawn
+// happens when the commands are applied, with whatever sprites and definitions are current by then.
+// Ships and powerups hand back their EntityCommands for anything else to go on them, meteors don't
+// since one may be taken from the pool rather than made afresh, see meteor_pool.rs. What a spec
+// decides about a ship, meteor or bullet is built as a bundle from it, ShipBundle, MeteorBundle and
+// BulletBundle, so a pooled meteor or bullet gets exactly what a new one would.
+use std::f32::consts::PI;
+
+use bevy::ecs::system::{Command, EntityCommands};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::animation::{SpriteAnimation, SpriteSheets};
+use crate::bullets::Ricochets;
+use crate::config::GameConfig;
+use crate::definitions::{BulletDefinition, EntityDefinitions, ShipDefinition};
+use crate::input::{ControlScheme, ControlSchemes};
+use crate::meteor_pool::{meteor_body, MeteorPool};
+use crate::palette::Tint;
+pub use crate::powerups::PowerupKind;
+use crate::powerups::Powerup;
+use crate::radar::RadarBlip;
+use crate::threat_indicator::Threat;
+use crate::{meteor_collider, Bullet, Lifetime, Meteor, MuzzlePoint, Parked, PlayerId, ScreenWrap, Spaceship, Weapon};
+
+pub const LARGE_METEOR: u8 = 8;
+pub const MEDIUM_METEOR: u8 = 4;
+pub const SMALL_METEOR: u8 = 2;
+
+#[derive(Clone, Copy, Debug)]
+pub struct MeteorSpec {
+    // Meteors split in half down to the smallest size in game.entities.ron.
+    pub size: u8,
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+impl MeteorSpec {
+    pub fn new(size: u8) -> Self {
+        MeteorSpec { size, position: Vec2::ZERO, velocity: Vec2::ZERO }
+    }
+
+    pub fn large() -> Self {
+        MeteorSpec::new(LARGE_METEOR)
+    }
+
+    pub fn medium() -> Self {
+        MeteorSpec::new(MEDIUM_METEOR)
+    }
+
+    pub fn small() -> Self {
+        MeteorSpec::new(SMALL_METEOR)
+    }
+
+    pub fn at(self, position: Vec2) -> Self {
+        MeteorSpec { position, ..self }
+    }
+
+    pub fn with_velocity(self, velocity: Vec2) -> Self {
+        MeteorSpec { velocity, ..self }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ShipSpec {
+    pub player: u8,
+    pub position: Vec2,
+    // Radians anticlockwise from pointing right, ships start pointing up.
+    pub orientation: f32,
+}
+
+impl ShipSpec {
+    pub fn player(player: u8) -> Self {
+        ShipSpec { player, position: Vec2::ZERO, orientation: PI / 2.0 }
+    }
+
+    pub fn at(self, position: Vec2) -> Self {
+        ShipSpec { position, ..self }
+    }
+
+    pub fn facing(self, orientation: f32) -> Self {
+        ShipSpec { orientation, ..self }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BulletSpec {
+    pub player: u8,
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+impl BulletSpec {
+    pub fn player(player: u8) -> Self {
+        BulletSpec { player, position: Vec2::ZERO, velocity: Vec2::ZERO }
+    }
+
+    pub fn at(self, position: Vec2) -> Self {
+        BulletSpec { position, ..self }
+    }
+
+    pub fn with_velocity(self, velocity: Vec2) -> Self {
+        BulletSpec { velocity, ..self }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PowerupSpec {
+    pub kind: PowerupKind,
+    pub position: Vec2,
+}
+
+impl PowerupSpec {
+    pub fn new(kind: PowerupKind) -> Self {
+        PowerupSpec { kind, position: Vec2::ZERO }
+    }
+
+    pub fn at(self, position: Vec2) -> Self {
+        PowerupSpec { position, ..self }
+    }
+}
+
+#[derive(Bundle)]
+pub struct ShipBundle {
+    #[bundle]
+    sprite: SpriteSheetBundle,
+    spaceship: Spaceship,
+    player: PlayerId,
+    blip: RadarBlip,
+    scheme: ControlScheme,
+    weapon: Weapon,
+    events: ActiveEvents,
+    body: RigidBody,
+    collider: Collider,
+    restitution: Restitution,
+    gravity: GravityScale,
+    impulse: ExternalImpulse,
+    velocity: Velocity,
+    sensor: Sensor,
+    wrap: ScreenWrap,
+}
+
+impl ShipBundle {
+    pub fn new(
+        spec: ShipSpec,
+        sprite_sheets: &SpriteSheets,
+        schemes: &ControlSchemes,
+        definition: &ShipDefinition,
+    ) -> Self {
+        ShipBundle {
+            sprite: SpriteSheetBundle {
+                texture_atlas: sprite_sheets.atlas(),
+                sprite: TextureAtlasSprite {
+                    index: sprite_sheets.ship(),
+                    custom_size: Some(definition.size),
+                    ..default()
+                },
+                transform: Transform::from_translation(spec.position.extend(0.0))
+                    .with_rotation(Quat::from_rotation_z(spec.orientation)),
+                ..default()
+            },
+            spaceship: Spaceship,
+            player: PlayerId(spec.player),
+            blip: RadarBlip { tint: Tint::Friendly },
+            scheme: schemes.for_player(spec.player),
+            weapon: Weapon::default(),
+            events: ActiveEvents::COLLISION_EVENTS,
+            body: RigidBody::Dynamic,
+            collider: definition.collider.collider(),
+            restitution: Restitution::coefficient(definition.restitution),
+            gravity: GravityScale(0.0),
+            impulse: ExternalImpulse::default(),
+            velocity: Velocity::default(),
+            sensor: Sensor,
+            wrap: ScreenWrap,
+        }
+    }
+}
+
+// Everything that makes a meteor besides its texture atlas, which SpawnMeteor gives a new one.
+#[derive(Bundle)]
+pub struct MeteorBundle {
+    sprite: TextureAtlasSprite,
+    spin: SpriteAnimation,
+    meteor: Meteor,
+    blip: RadarBlip,
+    threat: Threat,
+    body: RigidBody,
+    gravity: GravityScale,
+    collider: Collider,
+    groups: CollisionGroups,
+    #[bundle]
+    transform: TransformBundle,
+    velocity: Velocity,
+    wrap: ScreenWrap,
+}
+
+impl MeteorBundle {
+    pub fn new(
+        spec: MeteorSpec,
+        sprite_sheets: &SpriteSheets,
+        config: &GameConfig,
+        definitions: &EntityDefinitions,
+    ) -> Self {
+        let spin = sprite_sheets.meteor_spin(spec.size);
+        let (body, gravity) = meteor_body();
+        MeteorBundle {
+            sprite: TextureAtlasSprite {
+                index: spin.random_frame(),
+                custom_size: Some(Vec2::splat(config.asteroid_base) * spec.size as f32),
+                ..default()
+            },
+            spin,
+            meteor: Meteor { size: spec.size },
+            blip: RadarBlip { tint: Tint::Meteor },
+            threat: Threat,
+            body,
+            gravity,
+            collider: meteor_collider(config, definitions, spec.size),
+            groups: CollisionGroups::default(),
+            transform: TransformBundle::from(Transform::from_translation(spec.position.extend(0.0))),
+            velocity: Velocity { linvel: spec.velocity, ..default() },
+            wrap: ScreenWrap,
+        }
+    }
+}
+
+// Everything that makes a bullet play like one. fire_bullet adds how a new one looks.
+#[derive(Bundle)]
+pub struct BulletBundle {
+    bullet: Bullet,
+    player: PlayerId,
+    ricochets: Ricochets,
+    body: RigidBody,
+    sensor: Sensor,
+    collider: Collider,
+    groups: CollisionGroups,
+    #[bundle]
+    transform: TransformBundle,
+    velocity: Velocity,
+}
+
+impl BulletBundle {
+    pub fn new(spec: BulletSpec, definition: &BulletDefinition) -> Self {
+        BulletBundle {
+            bullet: Bullet,
+            player: PlayerId(spec.player),
+            ricochets: Ricochets(0),
+            body: RigidBody::KinematicVelocityBased,
+            sensor: Sensor,
+            collider: Collider::ball(definition.radius),
+            groups: CollisionGroups::default(),
+            transform: TransformBundle::from(Transform::from_translation(spec.position.extend(0.0))),
+            velocity: Velocity { linvel: spec.velocity, angvel: 0.0 },
+        }
+    }
+}
+
+pub trait SpawnCommands<'w, 's> {
+    fn spawn_meteor(&mut self, spec: MeteorSpec);
+    fn spawn_ship<'a>(&'a mut self, spec: ShipSpec) -> EntityCommands<'w, 's, 'a>;
+    fn spawn_powerup<'a>(&'a mut self, spec: PowerupSpec) -> EntityCommands<'w, 's, 'a>;
+}
+
+impl<'w, 's> SpawnCommands<'w, 's> for Commands<'w, 's> {
+    fn spawn_meteor(&mut self, spec: MeteorSpec) {
+        self.add(SpawnMeteor(spec));
+    }
+
+    fn spawn_ship<'a>(&'a mut self, spec: ShipSpec) -> EntityCommands<'w, 's, 'a> {
+        let ship = self.spawn().id();
+        self.add(SpawnShip { ship, spec });
+        self.entity(ship)
+    }
+
+    fn spawn_powerup<'a>(&'a mut self, spec: PowerupSpec) -> EntityCommands<'w, 's, 'a> {
+        let powerup = self.spawn().id();
+        self.add(SpawnPowerup { powerup, spec });
+        self.entity(powerup)
+    }
+}
+
+struct SpawnMeteor(MeteorSpec);
+
+impl Command for SpawnMeteor {
+    fn write(self, world: &mut World) {
+        let spec = self.0;
+        let _spawn = info_span!("spawn_meteor", size = spec.size).entered();
+        let meteor_bundle = MeteorBundle::new(
+            spec,
+            world.resource::<SpriteSheets>(),
+            world.resource::<GameConfig>(),
+            world.resource::<EntityDefinitions>(),
+        );
+        let atlas = world.resource::<SpriteSheets>().atlas();
+        // Everything that can differ between meteors is inserted either way, so a parked one
+        // comes back as good as new.
+        let meteor = match world.resource_mut::<MeteorPool>().take() {
+            Some(meteor) => meteor,
+            None => world.spawn().insert_bundle(SpriteSheetBundle { texture_atlas: atlas, ..default() }).id(),
+        };
+        let mut meteor = world.entity_mut(meteor);
+        meteor.remove::<Parked>();
+        meteor.insert_bundle(meteor_bundle);
+    }
+}
+
+struct SpawnShip {
+    ship: Entity,
+    spec: ShipSpec,
+}
+
+impl Command for SpawnShip {
+    fn write(self, world: &mut World) {
+        let spec = self.spec;
+        let definition = &world.resource::<EntityDefinitions>().ship;
+        let ship_bundle = ShipBundle::new(
+            spec,
+            world.resource::<SpriteSheets>(),
+            world.resource::<ControlSchemes>(),
+            definition,
+        );
+        let mounts = definition.mounts.clone();
+        let mut ship = match world.get_entity_mut(self.ship) {
+            Some(ship) => ship,
+            None => return,
+        };
+        ship.insert_bundle(ship_bundle).with_children(|parent| {
+            for mount in mounts.iter() {
+                let transform = Transform::from_translation(mount.offset.extend(0.0))
+                    .with_rotation(Quat::from_rotation_z(mount.angle));
+                parent.spawn_bundle(TransformBundle::from(transform)).insert(MuzzlePoint);
+            }
+        });
+    }
+}
+
+struct SpawnPowerup {
+    powerup: Entity,
+    spec: PowerupSpec,
+}
+
+impl Command for SpawnPowerup {
+    fn write(self, world: &mut World) {
+        let spec = self.spec;
+        let sprite_sheets = world.resource::<SpriteSheets>();
+        let (atlas, index) = (sprite_sheets.atlas(), sprite_sheets.blank());
+        let definition = world.resource::<EntityDefinitions>().powerup.clone();
+        let mut powerup = match world.get_entity_mut(self.powerup) {
+            Some(powerup) => powerup,
+            None => return,
+        };
+        powerup
+            .insert_bundle(SpriteSheetBundle {
+                texture_atlas: atlas,
+                sprite: TextureAtlasSprite {
+                    index,
+                    custom_size: Some(Vec2::splat(definition.size)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(spec.position.x, spec.position.y, 0.0),
+                ..default()
+            })
+            .insert(Powerup { kind: spec.kind })
+            .insert(Tint::Powerup(spec.kind))
+            .insert(Lifetime(Timer::from_seconds(definition.lifetime, false)));
+    }
+}
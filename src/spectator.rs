@@ -0,0 +1,143 @@
+// The view for someone watching an online game with --spectate. The spectator steers player one's
+// camera instead of the game: 1 and 2 follow either ship, F lets go of them to pan around with the
+// arrow keys or WASD. There's no split screen, the second camera stays off.
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+use crate::camera::CameraFollow;
+use crate::netplay::LocalKeys;
+use crate::palette::Tint;
+use crate::{PlayerId, Spaceship};
+
+const PAN_SPEED: f32 = 600.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SpectatorView {
+    Free,
+    Follow(u8),
+}
+
+struct SpectatorCamera {
+    view: SpectatorView,
+    // Where the camera is looking, kept up to date while following so letting go starts there.
+    position: Vec2,
+}
+
+impl Default for SpectatorCamera {
+    fn default() -> Self {
+        SpectatorCamera { view: SpectatorView::Follow(0), position: Vec2::ZERO }
+    }
+}
+
+#[derive(Component)]
+struct SpectatorHud;
+
+pub struct Spectating;
+
+impl Plugin for Spectating {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SpectatorCamera>()
+            .add_startup_system(setup_spectator_hud)
+            .add_system(choose_view)
+            .add_system(update_spectator_hud)
+            // After the game has placed its cameras, before that's turned into where they draw from.
+            .add_system_to_stage(CoreStage::PostUpdate, steer_camera.before(TransformSystem::TransformPropagate));
+    }
+}
+
+// The game's own keys are pressed by the rollback schedule, so these are read from LocalKeys.
+fn choose_view(time: Res<Time>, local_keys: Res<LocalKeys>, mut spectator: ResMut<SpectatorCamera>) {
+    let keys = &local_keys.0;
+    if keys.any_just_pressed([KeyCode::Key1, KeyCode::Numpad1]) {
+        spectator.view = SpectatorView::Follow(0);
+    } else if keys.any_just_pressed([KeyCode::Key2, KeyCode::Numpad2]) {
+        spectator.view = SpectatorView::Follow(1);
+    } else if keys.just_pressed(KeyCode::F) {
+        spectator.view = SpectatorView::Free;
+    }
+    if spectator.view != SpectatorView::Free {
+        return;
+    }
+    let axis = |negative: [KeyCode; 2], positive: [KeyCode; 2]| {
+        keys.any_pressed(positive) as i8 as f32 - keys.any_pressed(negative) as i8 as f32
+    };
+    let direction = Vec2::new(
+        axis([KeyCode::Left, KeyCode::A], [KeyCode::Right, KeyCode::D]),
+        axis([KeyCode::Down, KeyCode::S], [KeyCode::Up, KeyCode::W]),
+    );
+    spectator.position += direction.normalize_or_zero() * PAN_SPEED * time.delta_seconds();
+}
+
+fn steer_camera(
+    mut spectator: ResMut<SpectatorCamera>,
+    query_ship: Query<(&Transform, &PlayerId), (With<Spaceship>, Without<CameraFollow>)>,
+    mut query_camera: Query<(&mut Transform, &mut Camera, &CameraFollow)>,
+) {
+    if let SpectatorView::Follow(followed) = spectator.view {
+        // A ship that's just been destroyed leaves the camera where it went down.
+        if let Some((ship_transform, _)) = query_ship.iter().find(|(_, player)| player.0 == followed) {
+            spectator.position = ship_transform.translation.truncate();
+        }
+    }
+    for (mut transform, mut camera, follow) in query_camera.iter_mut() {
+        let active = follow.0 == 0;
+        if camera.is_active != active {
+            camera.is_active = active;
+        }
+        if camera.viewport.is_some() {
+            camera.viewport = None;
+        }
+        if active {
+            transform.translation.x = spectator.position.x;
+            transform.translation.y = spectator.position.y;
+        }
+    }
+}
+
+fn setup_spectator_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::FlexStart,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: asset_server.load("BungeeSpice-Regular.ttf"),
+                            font_size: 24.0,
+                            ..default()
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect::all(Val::Px(10.0)),
+                        ..default()
+                    }),
+                )
+                .insert(SpectatorHud)
+                .insert(Tint::Accent);
+        });
+}
+
+fn update_spectator_hud(spectator: Res<SpectatorCamera>, mut query_text: Query<&mut Text, With<SpectatorHud>>) {
+    if !spectator.is_changed() {
+        return;
+    }
+    let watching = match spectator.view {
+        SpectatorView::Free => "Free Camera".to_string(),
+        SpectatorView::Follow(player) => format!("Following P{}", player + 1),
+    };
+    for mut text in query_text.iter_mut() {
+        text.sections[0].value = format!("Spectating - {}   1/2: Follow a Player   F: Free Camera", watching);
+    }
+}
@@ -0,0 +1,137 @@
+use bevy::core_pipeline::clear_color::ClearColorConfig;
+use bevy::prelude::*;
+use bevy::render::camera::Viewport;
+
+use crate::camera::{CameraFollow, CameraShake};
+use crate::palette::Tint;
+use crate::Game;
+
+#[derive(Component)]
+struct PlayerHud(u8);
+
+pub struct SplitScreen;
+
+impl Plugin for SplitScreen {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(setup_split_screen)
+            .add_system(update_viewports)
+            .add_system(update_player_hud);
+    }
+}
+
+fn setup_split_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+    // The second player's camera draws over the first one's frame, so it must not clear it.
+    commands
+        .spawn_bundle(Camera2dBundle {
+            camera: Camera {
+                priority: 1,
+                is_active: false,
+                ..default()
+            },
+            camera_2d: Camera2d {
+                clear_color: ClearColorConfig::None,
+            },
+            ..default()
+        })
+        .insert(UiCameraConfig { show_ui: false })
+        .insert(CameraShake::default())
+        .insert(CameraFollow(1));
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size { width: Val::Percent(100.0), height: Val::Percent(100.0) },
+                position_type: PositionType::Absolute,
+                align_items: AlignItems::FlexEnd,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            for player in 0..2 {
+                parent
+                    .spawn_bundle(
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: asset_server.load("BungeeSpice-Regular.ttf"),
+                                font_size: 30.0,
+                                ..default()
+                            },
+                        )
+                        .with_style(Style {
+                            size: Size { width: Val::Percent(50.0), height: Val::Auto },
+                            margin: UiRect::all(Val::Px(10.0)),
+                            display: Display::None,
+                            ..default()
+                        }),
+                    )
+                    .insert(PlayerHud(player))
+                    .insert(Tint::Accent);
+            }
+        });
+}
+
+fn update_viewports(
+    windows: Res<Windows>,
+    query_game: Query<&Game>,
+    mut layout: Local<Option<(u8, u32, u32)>>,
+    mut query_camera: Query<(&mut Camera, &CameraFollow)>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        // Headless there's no window to split.
+        None => return,
+    };
+    let game = query_game.single();
+    let current = (game.players, window.physical_width(), window.physical_height());
+    if *layout == Some(current) {
+        return;
+    }
+    *layout = Some(current);
+
+    let half_width = window.physical_width() / 2;
+    for (mut camera, follow) in query_camera.iter_mut() {
+        if game.players > 1 {
+            camera.is_active = true;
+            camera.viewport = Some(Viewport {
+                physical_position: UVec2::new(half_width * follow.0 as u32, 0),
+                physical_size: UVec2::new(half_width, window.physical_height()),
+                ..default()
+            });
+        } else {
+            camera.is_active = follow.0 == 0;
+            camera.viewport = None;
+        }
+    }
+}
+
+fn update_player_hud(
+    query_game: Query<&Game, Changed<Game>>,
+    mut query_hud: Query<(&mut Text, &mut Style, &PlayerHud)>,
+) {
+    let game = match query_game.get_single() {
+        Ok(game) => game,
+        Err(_) => return,
+    };
+    for (mut text, mut style, hud) in query_hud.iter_mut() {
+        if game.players > 1 {
+            style.display = Display::Flex;
+            let player = hud.0 as usize;
+            text.sections[0].value = if game.shared_lives {
+                format!("P{} Score: {}", player + 1, game.player_scores[player])
+            } else {
+                format!(
+                    "P{} Score: {}  Lives: {}",
+                    player + 1,
+                    game.player_scores[player],
+                    game.player_lives[player]
+                )
+            };
+        } else {
+            style.display = Display::None;
+        }
+    }
+}
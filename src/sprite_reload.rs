@@ -0,0 +1,66 @@
+// Fits colliders to sprites edited while the game is running, so new art can be flown and shot at
+// straight away. When the ship or a meteor sheet is packed into the atlas again, see animation.rs,
+// its collider is worked out afresh from the opaque pixels of the first frame, and the ship is
+// drawn at the size the new image works out to at the old scale. The results go into
+// EntityDefinitions for everything spawned from then on, and onto whatever is already in the arena.
+// Colliders stay centred on their sprites, and the meteors' hitbox is shared between every size, so
+// it follows whichever sheet was edited last. Editing game.entities.ron afterwards puts back what's
+// written there. This only happens while assets are being watched for changes, in debug builds.
+use bevy::prelude::*;
+
+use crate::animation::{Sheet, SheetEditedEvent};
+use crate::config::GameConfig;
+use crate::definitions::{ColliderShape, EntityDefinitions};
+use crate::{meteor_collider, Meteor, Spaceship};
+
+pub struct SpriteReloading;
+
+impl Plugin for SpriteReloading {
+    fn build(&self, app: &mut App) {
+        app.add_system(fit_colliders);
+    }
+}
+
+fn fit_colliders(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    mut definitions: ResMut<EntityDefinitions>,
+    mut edited_event: EventReader<SheetEditedEvent>,
+    mut query_ship: Query<(Entity, &mut TextureAtlasSprite), With<Spaceship>>,
+    query_meteor: Query<(Entity, &Meteor)>,
+) {
+    for ev in edited_event.iter() {
+        let outline = match (ev.sheet, ev.opaque) {
+            // Nothing collides with the thruster.
+            (Sheet::Thruster, _) => continue,
+            (_, Some(opaque)) => opaque.max - opaque.min,
+            (_, None) => {
+                warn!("{:?} has no opaque pixels to fit a collider to", ev.sheet);
+                continue;
+            }
+        };
+        match ev.sheet {
+            Sheet::Ship => {
+                // World units to a pixel of the image.
+                let scale = definitions.ship.size.x / ev.old_frame.x;
+                let size = ev.new_frame * scale;
+                let outline = outline * scale;
+                let radius = outline.y / 2.0;
+                let collider = ColliderShape::Capsule { half_length: (outline.x / 2.0 - radius).max(0.0), radius };
+                definitions.ship.size = size;
+                definitions.ship.collider = collider;
+                for (ship, mut sprite) in query_ship.iter_mut() {
+                    sprite.custom_size = Some(size);
+                    commands.entity(ship).insert(collider.collider());
+                }
+            }
+            Sheet::Meteor(_) => {
+                definitions.meteor.hitbox = outline.max_element() / ev.new_frame.max_element();
+                for (entity, meteor) in query_meteor.iter() {
+                    commands.entity(entity).insert(meteor_collider(&config, &definitions, meteor.size));
+                }
+            }
+            Sheet::Thruster => {}
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::Velocity;
+use rand::random;
+
+use crate::settings::Settings;
+use crate::theme::Theme;
+use crate::Spaceship;
+
+const STAR_Z: f32 = -10.0;
+// High contrast dims the stars and clears to black, so they're not mistaken for anything that matters.
+const HIGH_CONTRAST_STAR_DIMMING: f32 = 0.35;
+const HIGH_CONTRAST_CLEAR_COLOUR: Color = Color::BLACK;
+
+struct StarLayer {
+    count: usize,
+    size: f32,
+    brightness: f32,
+    // How much of the ship's velocity the layer drifts by, nearer layers drift more.
+    parallax: f32,
+}
+
+const STAR_LAYERS: [StarLayer; 2] = [
+    StarLayer { count: 60, size: 1.5, brightness: 0.4, parallax: 0.02 },
+    StarLayer { count: 140, size: 2.5, brightness: 0.8, parallax: 0.08 },
+];
+
+#[derive(Component)]
+struct Star {
+    parallax: f32,
+    brightness: f32,
+}
+
+pub struct Starfield;
+
+impl Plugin for Starfield {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(setup_starfield)
+            .add_system(scroll_starfield)
+            .add_system(apply_background);
+    }
+}
+
+fn setup_starfield(mut commands: Commands, windows: Res<Windows>) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        // Headless there's no window to scatter stars across.
+        None => return,
+    };
+    for layer in STAR_LAYERS.iter() {
+        for _ in 0..layer.count {
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgb(layer.brightness, layer.brightness, layer.brightness),
+                        custom_size: Some(Vec2::splat(layer.size)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(
+                        (random::<f32>() - 0.5) * window.width(),
+                        (random::<f32>() - 0.5) * window.height(),
+                        STAR_Z,
+                    ),
+                    ..default()
+                })
+                .insert(Star { parallax: layer.parallax, brightness: layer.brightness });
+        }
+    }
+}
+
+fn scroll_starfield(
+    time: Res<Time>,
+    windows: Res<Windows>,
+    query_ship: Query<&Velocity, With<Spaceship>>,
+    mut query_stars: Query<(&mut Transform, &Star)>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        // Headless there are no stars to scroll.
+        None => return,
+    };
+    let ship_velocity = query_ship
+        .iter()
+        .next()
+        .map(|velocity| velocity.linvel)
+        .unwrap_or(Vec2::ZERO);
+
+    for (mut transform, star) in query_stars.iter_mut() {
+        let drift = -ship_velocity * star.parallax * time.delta_seconds();
+        transform.translation.x += drift.x;
+        transform.translation.y += drift.y;
+
+        let half_width = window.width() / 2.0;
+        let half_height = window.height() / 2.0;
+        if transform.translation.x > half_width {
+            transform.translation.x -= window.width();
+        } else if transform.translation.x < -half_width {
+            transform.translation.x += window.width();
+        }
+        if transform.translation.y > half_height {
+            transform.translation.y -= window.height();
+        } else if transform.translation.y < -half_height {
+            transform.translation.y += window.height();
+        }
+    }
+}
+
+// High contrast darkens the background and dims the stars, otherwise the background is the theme's.
+fn apply_background(
+    settings: Res<Settings>,
+    theme: Res<Theme>,
+    mut clear_colour: ResMut<ClearColor>,
+    mut query_stars: Query<(&mut Sprite, &Star)>,
+) {
+    if !settings.is_changed() && !theme.is_changed() {
+        return;
+    }
+    let (background, dimming) = if settings.high_contrast {
+        (HIGH_CONTRAST_CLEAR_COLOUR, HIGH_CONTRAST_STAR_DIMMING)
+    } else {
+        (theme.background, 1.0)
+    };
+    if clear_colour.0 != background {
+        clear_colour.0 = background;
+    }
+    for (mut sprite, star) in query_stars.iter_mut() {
+        let brightness = star.brightness * dimming;
+        sprite.color = Color::rgb(brightness, brightness, brightness);
+    }
+}
@@ -0,0 +1,233 @@
+// Station defense: a space station sits in the middle of the arena and one or two players keep the
+// meteors off it. Each wave is sent at the station, and meteors that reach it crash into it and take
+// a bite out of its health. The game is over once it's gone, or once the ships run out of lives as
+// usual. Destroyed meteors sometimes leave a repair pickup, which patches the station up when a ship
+// flies through it. Which modes have a station is down to their rules, see ModeRules.
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::animation::SpriteSheets;
+use crate::camera::{CameraShakeEvent, METEOR_TRAUMA_PER_SIZE};
+use crate::config::GameConfig;
+use crate::game_mode::GameMode;
+use crate::palette::Tint;
+use crate::replay::GameRng;
+use crate::{
+    AppState, DestroyCommands, Game, GameState, Lifetime, Meteor, MeteorDestroyedEvent, Parked, Spaceship,
+    StartGameEvent,
+};
+
+const STATION_SIZE: f32 = 60.0;
+// Health lost to a meteor of each size unit, so a size 8 meteor takes 40%.
+const DAMAGE_PER_SIZE: f32 = 0.05;
+const REPAIR_DROP_CHANCE: f32 = 0.08;
+const REPAIR_AMOUNT: f32 = 0.25;
+const REPAIR_SIZE: f32 = 14.0;
+const REPAIR_RADIUS: f32 = 30.0;
+const REPAIR_LIFETIME: f32 = 10.0;
+const HEALTH_BAR_WIDTH: f32 = 200.0;
+// Below this much health the bar turns to the danger colour.
+const LOW_HEALTH: f32 = 0.3;
+
+#[derive(Component)]
+pub struct Station {
+    // From 1.0 when it's undamaged down to 0.0 when it's destroyed.
+    health: f32,
+}
+
+#[derive(Component)]
+pub struct RepairPickup;
+
+#[derive(Component)]
+struct HealthBar;
+
+#[derive(Component)]
+struct HealthBarFill;
+
+pub struct StationDefense;
+
+impl Plugin for StationDefense {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(setup_health_bar)
+            .add_system(spawn_station)
+            .add_system(update_health_bar)
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(meteors_hit_station)
+                    .with_system(drop_repairs)
+                    .with_system(collect_repairs),
+            );
+    }
+}
+
+// Whatever was left of the last game's station went with the rest of its gameplay entities.
+fn spawn_station(mut commands: Commands, mode: Res<GameMode>, mut game_event: EventReader<StartGameEvent>) {
+    if game_event.iter().last().is_none() || !mode.rules().station {
+        return;
+    }
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite { custom_size: Some(Vec2::splat(STATION_SIZE)), ..default() },
+            ..default()
+        })
+        .insert(Station { health: 1.0 })
+        .insert(Tint::Friendly);
+}
+
+fn meteors_hit_station(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    mut query_station: Query<(&mut Station, &Transform)>,
+    query_meteor: Query<(Entity, &Meteor, &Transform), Without<Parked>>,
+    mut query_game: Query<&mut Game>,
+    mut shake_event: EventWriter<CameraShakeEvent>,
+) {
+    for (mut station, station_transform) in query_station.iter_mut() {
+        let centre = station_transform.translation.truncate();
+        for (entity_meteor, meteor, transform) in query_meteor.iter() {
+            let reach = STATION_SIZE / 2.0 + config.asteroid_base * meteor.size as f32 / 2.0;
+            if transform.translation.truncate().distance(centre) > reach {
+                continue;
+            }
+            commands.entity(entity_meteor).insert(Parked);
+            shake_event.send(CameraShakeEvent { trauma: METEOR_TRAUMA_PER_SIZE * meteor.size as f32 });
+            station.health = (station.health - DAMAGE_PER_SIZE * meteor.size as f32).max(0.0);
+            if station.health == 0.0 {
+                query_game.single_mut().gameState = GameState::Ended;
+            }
+        }
+    }
+}
+
+// Rolled on the gameplay RNG like powerups, so replays drop the same repairs.
+fn drop_repairs(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    mut rng: ResMut<GameRng>,
+    sprite_sheets: Res<SpriteSheets>,
+    mut destroyed_event: EventReader<MeteorDestroyedEvent>,
+) {
+    if !mode.rules().station {
+        return;
+    }
+    for ev in destroyed_event.iter() {
+        if rng.0.gen::<f32>() > REPAIR_DROP_CHANCE {
+            continue;
+        }
+        commands
+            .spawn_bundle(SpriteSheetBundle {
+                texture_atlas: sprite_sheets.atlas(),
+                sprite: TextureAtlasSprite {
+                    index: sprite_sheets.blank(),
+                    custom_size: Some(Vec2::splat(REPAIR_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(ev.position.x, ev.position.y, 0.0),
+                ..default()
+            })
+            .insert(RepairPickup)
+            .insert(Tint::Friendly)
+            .insert(Lifetime(Timer::from_seconds(REPAIR_LIFETIME, false)));
+    }
+}
+
+fn collect_repairs(
+    mut commands: Commands,
+    query_ship: Query<&Transform, With<Spaceship>>,
+    query_repair: Query<(Entity, &Transform), With<RepairPickup>>,
+    mut query_station: Query<&mut Station>,
+) {
+    for (entity_repair, repair_transform) in query_repair.iter() {
+        let position = repair_transform.translation.truncate();
+        let collected = query_ship
+            .iter()
+            .any(|ship_transform| ship_transform.translation.truncate().distance(position) < REPAIR_RADIUS);
+        if !collected {
+            continue;
+        }
+        commands.destroy(entity_repair);
+        for mut station in query_station.iter_mut() {
+            station.health = (station.health + REPAIR_AMOUNT).min(1.0);
+        }
+    }
+}
+
+fn setup_health_bar(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect { top: Val::Px(50.0), left: Val::Percent(50.0), ..default() },
+                margin: UiRect { left: Val::Px(-HEALTH_BAR_WIDTH / 2.0), ..default() },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                display: Display::None,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(HealthBar)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle::from_section(
+                "Station",
+                TextStyle {
+                    font: asset_server.load("BungeeSpice-Regular.ttf"),
+                    font_size: 20.0,
+                    ..default()
+                },
+            ));
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(HEALTH_BAR_WIDTH), Val::Px(10.0)),
+                        ..default()
+                    },
+                    color: Color::rgba(1.0, 1.0, 1.0, 0.2).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .insert(HealthBarFill)
+                        .insert(Tint::Friendly);
+                });
+        });
+}
+
+// Only touches the bar when something differs, so the layout isn't recomputed every frame.
+fn update_health_bar(
+    mut commands: Commands,
+    query_station: Query<&Station>,
+    mut query_bar: Query<&mut Style, (With<HealthBar>, Without<HealthBarFill>)>,
+    mut query_fill: Query<(Entity, &mut Style, &Tint), With<HealthBarFill>>,
+) {
+    let health = query_station.iter().next().map(|station| station.health);
+    let display = if health.is_some() { Display::Flex } else { Display::None };
+    for mut style in query_bar.iter_mut() {
+        if style.display != display {
+            style.display = display;
+        }
+    }
+    let health = match health {
+        Some(health) => health,
+        None => return,
+    };
+    for (entity, mut style, tint) in query_fill.iter_mut() {
+        let width = Val::Percent(health * 100.0);
+        if style.size.width != width {
+            style.size.width = width;
+        }
+        let wanted = if health < LOW_HEALTH { Tint::Danger } else { Tint::Friendly };
+        if *tint != wanted {
+            commands.entity(entity).insert(wanted);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+// Mirrors the profile's achievements and lifetime stats to Steam when the game was started from
+// Steam, and does nothing otherwise. The profile stays the source of truth, Steam is only told
+// about it. The names here have to match the ones set up for the app on Steamworks.
+use bevy::prelude::*;
+use steamworks::{Client, SingleClient};
+
+use crate::profile::{Achievement, Profile};
+use crate::AppState;
+
+struct Steam {
+    client: Client,
+    // Steam wants stats stored sparingly, so they're only sent when worth it.
+    unsent: bool,
+}
+
+pub struct SteamStats;
+
+impl Plugin for SteamStats {
+    fn build(&self, app: &mut App) {
+        let (client, single) = match Client::init() {
+            Ok(steam) => steam,
+            Err(err) => {
+                info!("Not running under Steam: {}", err);
+                return;
+            }
+        };
+        client.user_stats().request_current_stats();
+        app
+            .insert_resource(Steam { client, unsent: false })
+            // Steam's callbacks have to be run from the thread that started it.
+            .insert_non_send_resource(single)
+            .add_system(run_steam_callbacks)
+            .add_system(mirror_profile)
+            .add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(store_stats.after(mirror_profile)));
+    }
+}
+
+fn steam_name(achievement: Achievement) -> &'static str {
+    match achievement {
+        Achievement::FirstBlood => "FIRST_BLOOD",
+        Achievement::Centurion => "CENTURION",
+        Achievement::Survivor => "SURVIVOR",
+        Achievement::Veteran => "VETERAN",
+    }
+}
+
+fn run_steam_callbacks(single: NonSend<SingleClient>) {
+    single.run_callbacks();
+}
+
+fn mirror_profile(mut steam: ResMut<Steam>, profile: Res<Profile>) {
+    if !profile.is_changed() {
+        return;
+    }
+    let user_stats = steam.client.user_stats();
+    let stats = [
+        ("games_played", profile.games_played),
+        ("meteors_destroyed", profile.meteors_destroyed),
+        ("waves_cleared", profile.waves_cleared),
+        ("shots_fired", profile.shots_fired),
+        ("hits", profile.hits),
+        ("best_score", profile.best_score),
+    ];
+    for (name, value) in stats {
+        if user_stats.set_stat_i32(name, value.min(i32::MAX as u32) as i32).is_err() {
+            warn!("Steam doesn't know the {} stat", name);
+        }
+    }
+    // An unlock is worth showing straight away, unlike the counters going up.
+    let mut unlocked = false;
+    for (achievement, progress) in profile.achievements.iter() {
+        let steam_achievement = user_stats.achievement(steam_name(*achievement));
+        if *progress >= achievement.goal() && steam_achievement.get() == Ok(false) {
+            unlocked |= steam_achievement.set().is_ok();
+        }
+    }
+    steam.unsent = true;
+    if unlocked {
+        store_stats(steam);
+    }
+}
+
+fn store_stats(mut steam: ResMut<Steam>) {
+    if !steam.unsent {
+        return;
+    }
+    if steam.client.user_stats().store_stats().is_err() {
+        warn!("Could not store stats on Steam");
+    }
+    steam.unsent = false;
+}
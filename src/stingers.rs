@@ -0,0 +1,122 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+
+use crate::audio::{load_sound, SfxChannel};
+use crate::game_mode::GameMode;
+use crate::leaderboard::HighScores;
+use crate::{Game, StartGameEvent};
+
+#[derive(Clone, Copy)]
+pub enum Milestone {
+    ExtraLife,
+    WaveCleared,
+    NewHighScore,
+}
+
+pub struct MilestoneEvent {
+    pub milestone: Milestone,
+}
+
+struct StingerHandles {
+    extra_life: Handle<AudioSource>,
+    wave_cleared: Handle<AudioSource>,
+    new_high_score: Handle<AudioSource>,
+}
+
+impl StingerHandles {
+    fn get(&self, milestone: Milestone) -> Handle<AudioSource> {
+        match milestone {
+            Milestone::ExtraLife => self.extra_life.clone(),
+            Milestone::WaveCleared => self.wave_cleared.clone(),
+            Milestone::NewHighScore => self.new_high_score.clone(),
+        }
+    }
+}
+
+// Stingers wait for the one before them to finish instead of playing over the top of it.
+#[derive(Default)]
+struct StingerQueue {
+    waiting: VecDeque<Milestone>,
+    playing: Option<Handle<AudioInstance>>,
+}
+
+pub struct Stingers;
+
+impl Plugin for Stingers {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<StingerQueue>()
+            .add_event::<MilestoneEvent>()
+            .add_startup_system(load_stingers)
+            .add_system(detect_score_milestones)
+            .add_system(queue_stingers.after(detect_score_milestones))
+            .add_system(play_stingers.after(queue_stingers));
+    }
+}
+
+fn load_stingers(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut audio_sources: ResMut<Assets<AudioSource>>,
+) {
+    commands.insert_resource(StingerHandles {
+        extra_life: load_sound(&asset_server, &mut audio_sources, "sfx/stinger_extra_life.wav"),
+        wave_cleared: load_sound(&asset_server, &mut audio_sources, "sfx/stinger_wave_clear.wav"),
+        new_high_score: load_sound(&asset_server, &mut audio_sources, "sfx/stinger_high_score.wav"),
+    });
+}
+
+// Watches the game for lives going up and the score passing the best on the leaderboard,
+// which only gets announced once per run.
+fn detect_score_milestones(
+    high_scores: Res<HighScores>,
+    mode: Res<GameMode>,
+    query_game: Query<&Game, Changed<Game>>,
+    mut game_event: EventReader<StartGameEvent>,
+    mut last_lives: Local<Option<u8>>,
+    mut beat_high_score: Local<bool>,
+    mut milestone_event: EventWriter<MilestoneEvent>,
+) {
+    if game_event.iter().last().is_some() {
+        *last_lives = None;
+        *beat_high_score = false;
+    }
+    let game = match query_game.get_single() {
+        Ok(game) => game,
+        Err(_) => return,
+    };
+    if matches!(*last_lives, Some(lives) if game.lives > lives) {
+        milestone_event.send(MilestoneEvent { milestone: Milestone::ExtraLife });
+    }
+    *last_lives = Some(game.lives);
+
+    let best = high_scores.best(*mode);
+    if !*beat_high_score && best > 0 && game.score > best {
+        *beat_high_score = true;
+        milestone_event.send(MilestoneEvent { milestone: Milestone::NewHighScore });
+    }
+}
+
+fn queue_stingers(mut milestone_event: EventReader<MilestoneEvent>, mut queue: ResMut<StingerQueue>) {
+    for ev in milestone_event.iter() {
+        queue.waiting.push_back(ev.milestone);
+    }
+}
+
+fn play_stingers(
+    sfx: Res<AudioChannel<SfxChannel>>,
+    stingers: Res<StingerHandles>,
+    mut queue: ResMut<StingerQueue>,
+) {
+    if let Some(instance) = &queue.playing {
+        if !matches!(sfx.state(instance), PlaybackState::Stopped) {
+            return;
+        }
+    }
+    queue.playing = queue
+        .waiting
+        .pop_front()
+        .map(|milestone| sfx.play(stingers.get(milestone)).handle());
+}
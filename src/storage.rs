@@ -0,0 +1,111 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+const APP_DIR: &str = "asteroids";
+
+// Where a file called `name` lives in the platform's config directory, e.g. ~/.config/asteroids
+// on Linux or %APPDATA%\asteroids on Windows.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn config_path(name: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR).join(name))
+}
+
+// Browsers have no files to write, so whatever wants one of its own, like clips, goes without.
+#[cfg(target_arch = "wasm32")]
+pub fn config_path(_name: &str) -> Option<PathBuf> {
+    None
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(name: &str) -> Option<String> {
+    fs::read_to_string(config_path(name)?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(name: &str, contents: &str) -> Result<(), String> {
+    let path = config_path(name).ok_or("no config directory")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    fs::write(&path, contents).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn exists(name: &str) -> bool {
+    config_path(name).map(|path| path.exists()).unwrap_or(false)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove(name: &str) {
+    if let Some(path) = config_path(name) {
+        if path.exists() {
+            if let Err(err) = fs::remove_file(&path) {
+                warn!("Could not remove {}: {}", path.display(), err);
+            }
+        }
+    }
+}
+
+// In the browser everything is kept in localStorage under the same names, e.g. asteroids/settings.ron.
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn storage_key(name: &str) -> String {
+    format!("{}/{}", APP_DIR, name)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(name: &str) -> Option<String> {
+    local_storage()?.get_item(&storage_key(name)).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(name: &str, contents: &str) -> Result<(), String> {
+    local_storage()
+        .ok_or("no localStorage")?
+        .set_item(&storage_key(name), contents)
+        .map_err(|err| format!("{:?}", err))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn exists(name: &str) -> bool {
+    read(name).is_some()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn remove(name: &str) {
+    if let Some(storage) = local_storage() {
+        if let Err(err) = storage.remove_item(&storage_key(name)) {
+            warn!("Could not remove {}: {:?}", name, err);
+        }
+    }
+}
+
+// Returns None if the file doesn't exist yet or can't be read, so callers fall back to defaults.
+pub fn load<T: DeserializeOwned>(name: &str) -> Option<T> {
+    let contents = read(name)?;
+    match ron::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("Ignoring unreadable {}: {}", name, err);
+            None
+        }
+    }
+}
+
+pub fn save<T: Serialize>(name: &str, value: &T) {
+    let result = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())
+        .and_then(|contents| write(name, &contents));
+    if let Err(err) = result {
+        warn!("Could not save {}: {}", name, err);
+    }
+}
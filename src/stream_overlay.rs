@@ -0,0 +1,147 @@
+// Live stats for streamers' browser-source overlays. Setting stream_overlay_port in config.ron
+// serves a WebSocket on localhost that sends a small JSON object whenever the score, lives, wave
+// or combo change, and the latest one to anything that connects, e.g.
+// {"score":12,"lives":2,"wave":3,"combo":4,"player_scores":[8,4],"player_lives":[1,1]}
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use bevy::prelude::*;
+use tungstenite::{accept, Message, WebSocket};
+
+use crate::config::GameConfig;
+use crate::wave::WaveState;
+use crate::{Game, MeteorDestroyedEvent, StartGameEvent};
+
+// Meteors destroyed less than this far apart keep a combo going.
+const COMBO_WINDOW: f32 = 2.0;
+
+#[derive(Default)]
+struct OverlayServer {
+    port: Option<u16>,
+    sender: Option<Mutex<Sender<String>>>,
+}
+
+struct Combo {
+    count: u32,
+    timer: Timer,
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        Combo { count: 0, timer: Timer::from_seconds(COMBO_WINDOW, false) }
+    }
+}
+
+pub struct StreamOverlay;
+
+impl Plugin for StreamOverlay {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<OverlayServer>()
+            .init_resource::<Combo>()
+            .add_system(start_overlay_server)
+            .add_system(count_combo)
+            .add_system(publish_stats.after(start_overlay_server).after(count_combo));
+    }
+}
+
+// Started the first time config.ron asks for it. Changing the port needs a restart.
+fn start_overlay_server(config: Res<GameConfig>, mut server: ResMut<OverlayServer>) {
+    let port = match config.stream_overlay_port {
+        Some(port) if config.is_changed() && server.port.is_none() => port,
+        _ => return,
+    };
+    let listener = match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(err) => {
+            warn!("Could not serve the stream overlay on port {}: {}", port, err);
+            return;
+        }
+    };
+    info!("Serving stream overlay stats on ws://localhost:{}", port);
+    let (sender, receiver) = channel();
+    thread::spawn(move || serve_overlay(listener, receiver));
+    server.port = Some(port);
+    server.sender = Some(Mutex::new(sender));
+}
+
+fn serve_overlay(listener: TcpListener, receiver: Receiver<String>) {
+    let clients: Arc<Mutex<Vec<WebSocket<TcpStream>>>> = Arc::default();
+    let latest: Arc<Mutex<String>> = Arc::default();
+    let (accepted, newest) = (clients.clone(), latest.clone());
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut socket = match accept(stream) {
+                Ok(socket) => socket,
+                Err(_) => continue,
+            };
+            let stats = newest.lock().map(|stats| stats.clone()).unwrap_or_default();
+            if socket.write_message(Message::Text(stats)).is_ok() {
+                if let Ok(mut clients) = accepted.lock() {
+                    clients.push(socket);
+                }
+            }
+        }
+    });
+    // Overlays that have gone away are dropped the next time sending to them fails.
+    for stats in receiver {
+        if let Ok(mut latest) = latest.lock() {
+            *latest = stats.clone();
+        }
+        if let Ok(mut clients) = clients.lock() {
+            clients.retain_mut(|socket| socket.write_message(Message::Text(stats.clone())).is_ok());
+        }
+    }
+}
+
+fn count_combo(
+    time: Res<Time>,
+    mut combo: ResMut<Combo>,
+    mut destroyed_event: EventReader<MeteorDestroyedEvent>,
+    mut game_event: EventReader<StartGameEvent>,
+) {
+    if game_event.iter().last().is_some() {
+        *combo = Combo::default();
+    }
+    let destroyed = destroyed_event.iter().count() as u32;
+    if destroyed > 0 {
+        combo.count += destroyed;
+        combo.timer.reset();
+    } else if combo.count > 0 && combo.timer.tick(time.delta()).just_finished() {
+        combo.count = 0;
+    }
+}
+
+fn publish_stats(
+    server: Res<OverlayServer>,
+    combo: Res<Combo>,
+    wave: Res<WaveState>,
+    query_game: Query<&Game>,
+    mut published: Local<String>,
+) {
+    let sender = match &server.sender {
+        Some(sender) => sender,
+        None => return,
+    };
+    let game = query_game.single();
+    let stats = format!(
+        "{{\"score\":{},\"lives\":{},\"wave\":{},\"combo\":{},\"player_scores\":[{},{}],\"player_lives\":[{},{}]}}",
+        game.score,
+        game.lives,
+        wave.number,
+        combo.count,
+        game.player_scores[0],
+        game.player_scores[1],
+        game.player_lives[0],
+        game.player_lives[1]
+    );
+    if *published == stats {
+        return;
+    }
+    if let Ok(sender) = sender.lock() {
+        sender.send(stats.clone()).ok();
+    }
+    *published = stats;
+}
@@ -0,0 +1,124 @@
+use std::f32::consts::TAU;
+
+use bevy_kira_audio::AudioSource;
+use kira::dsp::Frame;
+use kira::sound::static_sound::{StaticSoundData, StaticSoundSettings};
+use rand::random;
+
+const SAMPLE_RATE: u32 = 22050;
+// Every music loop is one bar of this length so the gameplay stems stay in step.
+const BAR_LENGTH: f32 = 4.0;
+const BEAT_LENGTH: f32 = BAR_LENGTH / 8.0;
+
+fn note(midi: u8) -> f32 {
+    440.0 * 2f32.powf((midi as f32 - 69.0) / 12.0)
+}
+
+fn square(frequency: f32, t: f32) -> f32 {
+    if (t * frequency).fract() < 0.5 { 1.0 } else { -1.0 }
+}
+
+// Noise that only picks a new value every so often, lower rates give a deeper rumble.
+struct Noise {
+    value: f32,
+    hold: f32,
+    rate: f32,
+}
+
+impl Noise {
+    fn new(rate: f32) -> Self {
+        Noise { value: 0.0, hold: 0.0, rate }
+    }
+
+    fn next(&mut self) -> f32 {
+        self.hold -= self.rate / SAMPLE_RATE as f32;
+        if self.hold <= 0.0 {
+            self.hold += 1.0;
+            self.value = random::<f32>() * 2.0 - 1.0;
+        }
+        self.value
+    }
+}
+
+fn render(duration: f32, mut sample: impl FnMut(f32) -> f32) -> Vec<f32> {
+    (0..(duration * SAMPLE_RATE as f32) as usize)
+        .map(|i| sample(i as f32 / SAMPLE_RATE as f32))
+        .collect()
+}
+
+fn blip(frequency: f32, duration: f32, volume: f32) -> Vec<f32> {
+    render(duration, |t| square(frequency, t) * volume * (1.0 - t / duration))
+}
+
+fn explosion(duration: f32, rate: f32, volume: f32) -> Vec<f32> {
+    let mut noise = Noise::new(rate);
+    render(duration, |t| noise.next() * volume * (1.0 - t / duration).powi(2))
+}
+
+// Square wave notes played one after another, each with a short decay.
+fn arpeggio(notes: &[(u8, f32)], volume: f32) -> Vec<f32> {
+    notes
+        .iter()
+        .flat_map(|(midi, duration)| render(*duration, |t| square(note(*midi), t) * volume * (-4.0 * t).exp()))
+        .collect()
+}
+
+// A bar of music with one note per beat, a 0 leaves that beat silent.
+fn bar(beats: [u8; 8], volume: f32, decay: f32) -> Vec<f32> {
+    render(BAR_LENGTH, |t| {
+        let beat = (t / BEAT_LENGTH) as usize;
+        let since_beat = t - beat as f32 * BEAT_LENGTH;
+        match beats[beat.min(7)] {
+            0 => 0.0,
+            midi => square(note(midi), t) * volume * (-decay * since_beat).exp(),
+        }
+    })
+}
+
+fn drums() -> Vec<f32> {
+    let mut noise = Noise::new(8000.0);
+    render(BAR_LENGTH, |t| {
+        let since_beat = t % BEAT_LENGTH;
+        let kick = (TAU * 60.0 * since_beat).sin() * (-20.0 * since_beat).exp();
+        let hat = noise.next() * (-60.0 * (t % (BEAT_LENGTH / 2.0))).exp() * 0.3;
+        (kick + hat) * 0.3
+    })
+}
+
+fn samples_for(path: &str) -> Option<Vec<f32>> {
+    let samples = match path {
+        "sfx/explosion.wav" => explosion(0.5, 4000.0, 0.4),
+        "sfx/ship_explosion.wav" => explosion(1.2, 1200.0, 0.5),
+        // Looped while thrusting, so it has no fade of its own.
+        "sfx/thrust.wav" => {
+            let mut noise = Noise::new(600.0);
+            render(1.0, |_| noise.next() * 0.3)
+        }
+        "sfx/ui_hover.wav" => blip(note(81), 0.04, 0.15),
+        "sfx/ui_click.wav" => blip(note(76), 0.06, 0.2),
+        "sfx/ui_confirm.wav" => arpeggio(&[(72, 0.07), (76, 0.07), (79, 0.07), (84, 0.1)], 0.2),
+        "sfx/stinger_extra_life.wav" => arpeggio(&[(72, 0.1), (76, 0.1), (79, 0.1), (84, 0.35)], 0.25),
+        "sfx/stinger_wave_clear.wav" => arpeggio(&[(67, 0.12), (72, 0.12), (76, 0.4)], 0.25),
+        "sfx/stinger_high_score.wav" => {
+            arpeggio(&[(72, 0.1), (72, 0.1), (79, 0.1), (79, 0.1), (84, 0.5)], 0.25)
+        }
+        "music/menu.wav" => bar([57, 0, 60, 0, 64, 0, 60, 0], 0.12, 1.5),
+        "music/game.wav" => bar([33, 33, 45, 33, 36, 36, 43, 40], 0.15, 3.0),
+        "music/game_drums.wav" => drums(),
+        "music/game_lead.wav" => bar([69, 72, 76, 0, 74, 72, 69, 0], 0.1, 2.0),
+        _ => return None,
+    };
+    Some(samples)
+}
+
+// Builds an arcade-style stand-in for one of the game's sound files, or None if there isn't one.
+pub fn sound_for(path: &str) -> Option<AudioSource> {
+    let frames: Vec<Frame> = samples_for(path)?.into_iter().map(Frame::from_mono).collect();
+    Some(AudioSource {
+        sound: StaticSoundData {
+            sample_rate: SAMPLE_RATE,
+            frames: frames.into(),
+            settings: StaticSoundSettings::default(),
+        },
+    })
+}
@@ -0,0 +1,171 @@
+// Themes change how the game looks without touching how it plays. Each is a .theme.ron manifest in
+// assets/themes/ naming the images for every sprite sheet, the colours of the line outlines, the
+// background and optionally a palette to use in place of the standard one, see palette.rs. The
+// colourblind palettes are left alone. A theme with outlines draws the ship, meteors and bullets as
+// lines whatever the render style setting says, see vector.rs. The chosen theme is picked in the
+// settings and can be switched mid-game, its sheets are packed into the sprite atlas where the old
+// ones were as soon as they've loaded, see animation.rs. Colliders keep to game.entities.ron whatever
+// size a theme's images are, so every theme plays the same. Mods can add themes, see mods.rs.
+use std::collections::BTreeMap;
+
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+
+use crate::animation::Sheet;
+use crate::palette::Palette;
+use crate::settings::Settings;
+
+// The look the game had before it had themes, and what it falls back to.
+pub const DEFAULT_THEME: &str = "Cartoon";
+#[cfg(not(target_arch = "wasm32"))]
+const THEMES_FOLDER: &str = "themes";
+#[cfg(target_arch = "wasm32")]
+const WEB_THEMES: [&str; 3] = ["themes/cartoon.theme.ron", "themes/classic.theme.ron", "themes/neon.theme.ron"];
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct OutlineColours {
+    pub ship: Color,
+    pub meteor: Color,
+    pub bullet: Color,
+}
+
+// The theme in use is also a resource.
+#[derive(Deserialize, TypeUuid, Clone)]
+#[uuid = "e2a7c4d9-3f61-4b8e-9c05-8d1f6a2b7e34"]
+pub struct Theme {
+    pub name: String,
+    // Sprite sheets as paths in assets/, laid out like the ones the game comes with.
+    pub large_meteor: String,
+    pub medium_meteor: String,
+    pub small_meteor: String,
+    pub ship: String,
+    pub thruster: String,
+    // Draws line outlines instead of the sprites, for the classic arcade look.
+    #[serde(default)]
+    pub outlines: bool,
+    pub outline_colours: OutlineColours,
+    pub background: Color,
+    #[serde(default)]
+    pub palette: Option<Palette>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            name: DEFAULT_THEME.to_string(),
+            large_meteor: "meteor_8_spin.png".to_string(),
+            medium_meteor: "meteor_4_spin.png".to_string(),
+            small_meteor: "meteor_2_spin.png".to_string(),
+            ship: "spaceship.png".to_string(),
+            thruster: "thruster.png".to_string(),
+            outlines: false,
+            outline_colours: OutlineColours {
+                ship: Color::rgb(0.3, 1.0, 1.0),
+                meteor: Color::rgb(0.95, 0.95, 1.0),
+                bullet: Color::rgb(1.0, 0.4, 0.8),
+            },
+            background: ClearColor::default().0,
+            palette: None,
+        }
+    }
+}
+
+impl Theme {
+    pub fn sheet(&self, sheet: Sheet) -> &str {
+        match sheet {
+            Sheet::Meteor(8) => &self.large_meteor,
+            Sheet::Meteor(4) => &self.medium_meteor,
+            Sheet::Meteor(_) => &self.small_meteor,
+            Sheet::Ship => &self.ship,
+            Sheet::Thruster => &self.thruster,
+        }
+    }
+}
+
+#[derive(Default)]
+struct ThemeLoader;
+
+impl AssetLoader for ThemeLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let theme: Theme = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(theme));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme.ron"]
+    }
+}
+
+// Every theme there is to pick from, by name.
+#[derive(Default)]
+pub struct LoadedThemes {
+    // Keeps the files loaded so the asset server goes on watching them for changes.
+    handles: Vec<Handle<Theme>>,
+    themes: BTreeMap<String, Theme>,
+}
+
+impl LoadedThemes {
+    pub fn names(&self) -> Vec<String> {
+        self.themes.keys().cloned().collect()
+    }
+}
+
+pub struct Themes;
+
+impl Plugin for Themes {
+    fn build(&self, app: &mut App) {
+        app
+            .add_asset::<Theme>()
+            .init_asset_loader::<ThemeLoader>()
+            .init_resource::<LoadedThemes>()
+            .init_resource::<Theme>()
+            .add_startup_system(load_themes)
+            .add_system(apply_themes)
+            .add_system(select_theme.after(apply_themes));
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_themes(asset_server: Res<AssetServer>, mut themes: ResMut<LoadedThemes>) {
+    match asset_server.load_folder(THEMES_FOLDER) {
+        Ok(handles) => themes.handles = handles.into_iter().map(|handle| handle.typed()).collect(),
+        Err(err) => warn!("Could not load the {} folder: {:?}", THEMES_FOLDER, err),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_themes(asset_server: Res<AssetServer>, mut themes: ResMut<LoadedThemes>) {
+    themes.handles = WEB_THEMES.iter().map(|path| asset_server.load(*path)).collect();
+}
+
+fn apply_themes(
+    assets: Res<Assets<Theme>>,
+    mut asset_events: EventReader<AssetEvent<Theme>>,
+    mut themes: ResMut<LoadedThemes>,
+) {
+    for ev in asset_events.iter() {
+        if let AssetEvent::Created { handle } | AssetEvent::Modified { handle } = ev {
+            if let Some(theme) = assets.get(handle) {
+                info!("Loaded the {} theme", theme.name);
+                themes.themes.insert(theme.name.clone(), theme.clone());
+            }
+        }
+    }
+}
+
+// The built-in look stands in until the chosen theme has loaded, or if it's nowhere to be found.
+fn select_theme(settings: Res<Settings>, themes: Res<LoadedThemes>, mut theme: ResMut<Theme>) {
+    if !themes.is_changed() && (!settings.is_changed() || theme.name == settings.theme) {
+        return;
+    }
+    *theme = themes.themes.get(&settings.theme).cloned().unwrap_or_default();
+}
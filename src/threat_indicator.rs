@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+
+use crate::camera::CameraFollow;
+use crate::palette::Tint;
+use crate::Game;
+
+const ARROW_SIZE: Vec2 = Vec2::new(18.0, 8.0);
+const ARROW_MARGIN: f32 = 16.0;
+// Threats further than this beyond the screen edge get the smallest, faintest arrow.
+const ARROW_FADE_DISTANCE: f32 = 600.0;
+
+// Marks entities worth warning about when they are outside the view.
+#[derive(Component)]
+pub struct Threat;
+
+#[derive(Component)]
+struct ThreatArrow(Entity);
+
+pub struct ThreatIndicators;
+
+impl Plugin for ThreatIndicators {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(add_threat_arrows)
+            .add_system(update_threat_arrows);
+    }
+}
+
+fn add_threat_arrows(mut commands: Commands, query_threats: Query<Entity, Added<Threat>>) {
+    for entity in query_threats.iter() {
+        commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(ARROW_SIZE.x), Val::Px(ARROW_SIZE.y)),
+                    position_type: PositionType::Absolute,
+                    display: Display::None,
+                    ..default()
+                },
+                ..default()
+            })
+            .insert(ThreatArrow(entity))
+            .insert(Tint::Danger);
+    }
+}
+
+fn update_threat_arrows(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    query_game: Query<&Game>,
+    query_camera: Query<(&Transform, &CameraFollow), Without<ThreatArrow>>,
+    query_threats: Query<&Transform, (With<Threat>, Without<ThreatArrow>)>,
+    mut query_arrows: Query<(Entity, &mut Style, &mut Transform, &mut UiColor, &ThreatArrow)>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        // Headless there's no window edge to point from.
+        None => return,
+    };
+    let game = query_game.single();
+
+    // The UI is drawn over the first player's view, which is half the window in split-screen.
+    let mut view_size = Vec2::new(window.width(), window.height());
+    if game.players > 1 {
+        view_size.x /= 2.0;
+    }
+    let half_view = view_size / 2.0;
+    let camera_position = query_camera
+        .iter()
+        .find(|(_, follow)| follow.0 == 0)
+        .map(|(transform, _)| transform.translation.truncate())
+        .unwrap_or(Vec2::ZERO);
+
+    for (arrow, mut style, mut transform, mut color, threat) in query_arrows.iter_mut() {
+        let threat_transform = match query_threats.get(threat.0) {
+            Ok(threat_transform) => threat_transform,
+            Err(_) => {
+                commands.entity(arrow).despawn_recursive();
+                continue;
+            }
+        };
+
+        let offset = threat_transform.translation.truncate() - camera_position;
+        if offset.x.abs() <= half_view.x && offset.y.abs() <= half_view.y {
+            style.display = Display::None;
+            continue;
+        }
+
+        // Scale the direction so it touches the nearest screen edge, then pull it in a little.
+        let edge_scale = (half_view.x / offset.x.abs()).min(half_view.y / offset.y.abs());
+        let edge = offset * edge_scale;
+        let inset = edge - edge.normalize_or_zero() * ARROW_MARGIN;
+        let distance = offset.length() - edge.length();
+        let closeness = 1.0 - (distance / ARROW_FADE_DISTANCE).clamp(0.0, 1.0);
+
+        style.display = Display::Flex;
+        style.position = UiRect {
+            left: Val::Px(inset.x + half_view.x - ARROW_SIZE.x / 2.0),
+            bottom: Val::Px(inset.y + half_view.y - ARROW_SIZE.y / 2.0),
+            ..default()
+        };
+        transform.rotation = Quat::from_rotation_z(offset.y.atan2(offset.x));
+        transform.scale = Vec3::splat(0.5 + closeness * 0.5);
+        color.0.set_a(0.3 + closeness * 0.7);
+    }
+}
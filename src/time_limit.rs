@@ -0,0 +1,113 @@
+// Timed modes end the run once their clock runs out, though losing every life still ends it early.
+// The clock only runs while the game is in progress, so pausing stops it, and the time left is shown
+// at the top of the screen. Which modes are timed and for how long is down to their rules, see
+// ModeRules.
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+use crate::game_mode::GameMode;
+use crate::menu::MENU_TEXT;
+use crate::ui::set_text;
+use crate::{AppState, Game, GameState, StartGameEvent};
+
+// None when the run isn't timed.
+#[derive(Default)]
+struct TimeLeft(Option<Timer>);
+
+#[derive(Component)]
+struct TimeLeftUI;
+
+pub struct TimeLimits;
+
+impl Plugin for TimeLimits {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<TimeLeft>()
+            .add_startup_system(setup_time_left_ui)
+            .add_system(reset_time_limit)
+            .add_system(update_time_left_ui)
+            .add_system_set(SystemSet::on_update(AppState::InGame).with_system(count_down));
+    }
+}
+
+fn reset_time_limit(
+    mode: Res<GameMode>,
+    mut game_event: EventReader<StartGameEvent>,
+    mut time_left: ResMut<TimeLeft>,
+) {
+    if game_event.iter().last().is_some() {
+        time_left.0 = mode.rules().time_limit.map(|seconds| Timer::from_seconds(seconds, false));
+    }
+}
+
+fn count_down(time: Res<Time>, mut time_left: ResMut<TimeLeft>, mut query_game: Query<&mut Game>) {
+    let mut game = query_game.single_mut();
+    if !matches!(game.gameState, GameState::InProgress) {
+        return;
+    }
+    if let Some(timer) = &mut time_left.0 {
+        if timer.tick(time.delta()).just_finished() {
+            game.gameState = GameState::Ended;
+        }
+    }
+}
+
+fn setup_time_left_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                // The UI's y axis points up in this version of Bevy, so this is the top of the screen.
+                align_items: AlignItems::FlexEnd,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: asset_server.load("BungeeSpice-Regular.ttf"),
+                            font_size: 30.0,
+                            color: MENU_TEXT,
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect { top: Val::Px(50.0), ..default() },
+                        display: Display::None,
+                        ..default()
+                    }),
+                )
+                .insert(TimeLeftUI);
+        });
+}
+
+// Only touches the text when something differs, so the layout isn't recomputed every frame.
+fn update_time_left_ui(
+    time_left: Res<TimeLeft>,
+    query_game: Query<&Game>,
+    mut query_text: Query<(&mut Style, &mut Text), With<TimeLeftUI>>,
+) {
+    let in_progress = matches!(query_game.single().gameState, GameState::InProgress);
+    let value = match &time_left.0 {
+        Some(timer) if in_progress => {
+            // Rounded up, so the clock reads 0:00 only once time is up.
+            let seconds = (timer.duration() - timer.elapsed()).as_secs_f32().ceil() as u32;
+            format!("Time Left {}:{:02}", seconds / 60, seconds % 60)
+        }
+        _ => String::new(),
+    };
+    let display = if value.is_empty() { Display::None } else { Display::Flex };
+    for (mut style, mut text) in query_text.iter_mut() {
+        if style.display != display {
+            style.display = display;
+        }
+        set_text(&mut text, &value);
+    }
+}
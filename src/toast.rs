@@ -0,0 +1,96 @@
+// Short messages at the bottom of the screen for things the player should know about but that
+// don't need anything doing, like assets that didn't load, see fallback.rs. Anything can show one by
+// sending a ToastEvent. They stack up oldest at the bottom and each fades out on its own.
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+use crate::palette::Tint;
+
+const TOAST_DURATION: f32 = 6.0;
+// Only the last part of a toast's time is spent fading.
+const TOAST_FADE: f32 = 1.5;
+
+pub struct ToastEvent(pub String);
+
+#[derive(Component)]
+struct Toast(Timer);
+
+// Holds the toasts, across the whole screen and under everything else in the UI.
+#[derive(Component)]
+struct ToastStack;
+
+pub struct Toasts;
+
+impl Plugin for Toasts {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<ToastEvent>()
+            .add_startup_system(create_toast_stack)
+            .add_system(show_toasts)
+            .add_system(fade_toasts.after(show_toasts));
+    }
+}
+
+fn create_toast_stack(mut commands: Commands) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                // The UI's y axis points up in this version of Bevy, so this stacks up from the bottom.
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::FlexStart,
+                padding: UiRect { bottom: Val::Px(20.0), ..default() },
+                ..default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        })
+        .insert(ToastStack);
+}
+
+fn show_toasts(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut toast_event: EventReader<ToastEvent>,
+    query_stack: Query<Entity, With<ToastStack>>,
+) {
+    let stack = match query_stack.get_single() {
+        Ok(stack) => stack,
+        Err(_) => return,
+    };
+    for ev in toast_event.iter() {
+        let toast = commands
+            .spawn_bundle(
+                TextBundle::from_section(
+                    ev.0.clone(),
+                    TextStyle {
+                        font: asset_server.load("BungeeSpice-Regular.ttf"),
+                        font_size: 20.0,
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect { top: Val::Px(6.0), ..default() },
+                    ..default()
+                }),
+            )
+            .insert(Toast(Timer::from_seconds(TOAST_DURATION, false)))
+            .insert(Tint::Danger)
+            .id();
+        commands.entity(stack).add_child(toast);
+    }
+}
+
+fn fade_toasts(mut commands: Commands, time: Res<Time>, mut query: Query<(Entity, &mut Toast, &mut Text)>) {
+    for (entity, mut toast, mut text) in query.iter_mut() {
+        if toast.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let alpha = (toast.0.duration().as_secs_f32() - toast.0.elapsed_secs()) / TOAST_FADE;
+        text.sections[0].style.color.set_a(alpha.min(1.0));
+    }
+}
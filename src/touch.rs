@@ -0,0 +1,57 @@
+// Touchscreen controls for player one, mainly for phones and tablets in the browser build. The
+// screen is split into four columns, from the left: turn left, turn right, thrust and fire. A
+// finger on one holds that key down, so everything that reads the keyboard, replays included,
+// sees touches as ordinary key presses. Menus are tapped like they're clicked.
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+use crate::input::{ControlScheme, ControlSchemes};
+use crate::AppState;
+
+const TOUCH_COLUMNS: f32 = 4.0;
+
+pub struct TouchControls;
+
+impl Plugin for TouchControls {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::PreUpdate, press_touched_keys.after(InputSystem));
+    }
+}
+
+fn touched_key(scheme: &ControlScheme, x: f32, width: f32) -> KeyCode {
+    match (x / width * TOUCH_COLUMNS) as u32 {
+        0 => scheme.left,
+        1 => scheme.right,
+        2 => scheme.thrust,
+        _ => scheme.fire,
+    }
+}
+
+// Keys are only released here if a touch pressed them, so the keyboard can still be used alongside.
+fn press_touched_keys(
+    touches: Res<Touches>,
+    windows: Res<Windows>,
+    state: Res<State<AppState>>,
+    schemes: Res<ControlSchemes>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut held: Local<Vec<KeyCode>>,
+) {
+    let scheme = schemes.for_player(0);
+    let width = windows.get_primary().map_or(0.0, |window| window.width());
+    let mut touched = Vec::new();
+    if *state.current() == AppState::InGame && width > 0.0 {
+        for touch in touches.iter() {
+            let key = touched_key(&scheme, touch.position().x, width);
+            if !touched.contains(&key) {
+                touched.push(key);
+            }
+        }
+    }
+    for key in held.iter().filter(|key| !touched.contains(key)) {
+        keyboard_input.release(*key);
+    }
+    for key in touched.iter() {
+        keyboard_input.press(*key);
+    }
+    *held = touched;
+}
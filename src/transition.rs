@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+
+use crate::AppState;
+
+const FADE_DURATION: f32 = 0.3;
+const SLIDE_DURATION: f32 = 0.25;
+const SLIDE_DISTANCE: f32 = 40.0;
+
+// Menu roots carrying this slide down into place from slightly above.
+#[derive(Component)]
+pub struct SlideIn(Timer);
+
+impl Default for SlideIn {
+    fn default() -> Self {
+        SlideIn(Timer::from_seconds(SLIDE_DURATION, false))
+    }
+}
+
+#[derive(Component)]
+struct FadeCurtain(Timer);
+
+pub struct Transitions;
+
+impl Plugin for Transitions {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(start_fade_on_state_change)
+            .add_system(fade_curtain)
+            .add_system(slide_in);
+    }
+}
+
+fn ease_out(t: f32) -> f32 {
+    1.0 - (1.0 - t) * (1.0 - t)
+}
+
+// A black curtain is dropped over everything on each state change and fades away.
+// It is respawned rather than reused so it sits above whichever screen was just created.
+fn start_fade_on_state_change(
+    mut commands: Commands,
+    state: Res<State<AppState>>,
+    query: Query<Entity, With<FadeCurtain>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            color: Color::BLACK.into(),
+            focus_policy: bevy::ui::FocusPolicy::Pass,
+            ..default()
+        })
+        .insert(FadeCurtain(Timer::from_seconds(FADE_DURATION, false)));
+}
+
+fn fade_curtain(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut UiColor, &mut FadeCurtain)>,
+) {
+    for (entity, mut color, mut curtain) in query.iter_mut() {
+        curtain.0.tick(time.delta());
+        if curtain.0.finished() {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            color.0.set_a(1.0 - ease_out(curtain.0.percent()));
+        }
+    }
+}
+
+fn slide_in(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Style, &mut SlideIn)>,
+) {
+    for (entity, mut style, mut slide) in query.iter_mut() {
+        slide.0.tick(time.delta());
+        let offset = SLIDE_DISTANCE * (1.0 - ease_out(slide.0.percent()));
+        style.position.top = Val::Px(-offset);
+        style.position.bottom = Val::Px(offset);
+        if slide.0.finished() {
+            commands.entity(entity).remove::<SlideIn>();
+        }
+    }
+}
@@ -0,0 +1,209 @@
+// Prompts for a new player's first game, one at a time near the bottom of the screen: how to
+// thrust, how to fire, then that the edges wrap around. Each goes away once the player has done
+// what it says, which is kept in the profile along with everything else, so they only ever show on
+// a fresh profile's first game. The prompts name player one's keys, spelt out as the font has no
+// arrows. Replays and the attract mode's demo are someone else flying, so they neither show the
+// prompts nor tick them off.
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+use serde::{Deserialize, Serialize};
+
+use crate::attract::Demo;
+use crate::input::{ControlPreset, ControlScheme};
+use crate::menu::MENU_TEXT;
+use crate::profile::Profile;
+use crate::replay::Playback;
+use crate::settings::Settings;
+use crate::ui::set_text;
+use crate::wave::WaveState;
+use crate::{arena_size, AppState, PlayerId, Spaceship};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TutorialProgress {
+    thrust: bool,
+    fire: bool,
+    wrap: bool,
+}
+
+#[derive(Clone, Copy)]
+enum TutorialStep {
+    Thrust,
+    Fire,
+    Wrap,
+}
+
+impl TutorialProgress {
+    fn next_step(&self) -> Option<TutorialStep> {
+        if !self.thrust {
+            Some(TutorialStep::Thrust)
+        } else if !self.fire {
+            Some(TutorialStep::Fire)
+        } else if !self.wrap {
+            Some(TutorialStep::Wrap)
+        } else {
+            None
+        }
+    }
+}
+
+fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::LControl => "Left Ctrl".to_string(),
+        _ => format!("{:?}", key),
+    }
+}
+
+// The gamepad preset presses the arrow layout's keys, but the player is holding a gamepad.
+fn prompt(step: TutorialStep, scheme: &ControlScheme, preset: ControlPreset) -> String {
+    let gamepad = preset == ControlPreset::Gamepad;
+    match step {
+        TutorialStep::Thrust if gamepad => "RT to thrust".to_string(),
+        TutorialStep::Thrust => format!("{} to thrust", key_label(scheme.thrust)),
+        TutorialStep::Fire if gamepad => "A to fire".to_string(),
+        TutorialStep::Fire => format!("{} to fire", key_label(scheme.fire)),
+        TutorialStep::Wrap => "Edges wrap around to the other side".to_string(),
+    }
+}
+
+#[derive(Component)]
+struct TutorialPrompt;
+
+pub struct Tutorial;
+
+impl Plugin for Tutorial {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(setup_tutorial_prompt)
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(tick_off_controls)
+                    .with_system(tick_off_wrap)
+                    .with_system(show_tutorial_prompt.after(tick_off_controls).after(tick_off_wrap)),
+            )
+            .add_system_set(SystemSet::on_pause(AppState::InGame).with_system(hide_tutorial_prompt))
+            .add_system_set(SystemSet::on_exit(AppState::InGame).with_system(hide_tutorial_prompt));
+    }
+}
+
+// The prompt itself is hidden rather than its full-screen parent, which only centres it.
+fn setup_tutorial_prompt(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                // The UI's y axis points up in this version of Bevy, so this is the bottom of the screen.
+                align_items: AlignItems::FlexStart,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: asset_server.load("BungeeSpice-Regular.ttf"),
+                            font_size: 30.0,
+                            color: MENU_TEXT,
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect { bottom: Val::Px(80.0), ..default() },
+                        display: Display::None,
+                        ..default()
+                    }),
+                )
+                .insert(TutorialPrompt);
+        });
+}
+
+fn tick_off_controls(
+    playback: Option<Res<Playback>>,
+    demo: Option<Res<Demo>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    query_ship: Query<(&PlayerId, &ControlScheme), With<Spaceship>>,
+    mut profile: ResMut<Profile>,
+) {
+    if playback.is_some() || demo.is_some() || profile.games_played > 0 {
+        return;
+    }
+    for (player, scheme) in query_ship.iter() {
+        if player.0 != 0 {
+            continue;
+        }
+        if !profile.tutorial.thrust && keyboard_input.pressed(scheme.thrust) {
+            profile.tutorial.thrust = true;
+        }
+        if !profile.tutorial.fire && keyboard_input.just_pressed(scheme.fire) {
+            profile.tutorial.fire = true;
+        }
+    }
+}
+
+// The ship has wrapped if it moved further in a frame than it could by flying.
+fn tick_off_wrap(
+    playback: Option<Res<Playback>>,
+    demo: Option<Res<Demo>>,
+    windows: Res<Windows>,
+    window_descriptor: Res<WindowDescriptor>,
+    query_ship: Query<(&PlayerId, &Transform), With<Spaceship>>,
+    mut profile: ResMut<Profile>,
+    mut last_position: Local<Option<Vec2>>,
+) {
+    if playback.is_some() || demo.is_some() || profile.games_played > 0 || profile.tutorial.wrap {
+        return;
+    }
+    let position = query_ship
+        .iter()
+        .find(|(player, _)| player.0 == 0)
+        .map(|(_, transform)| transform.translation.truncate());
+    let arena = arena_size(&windows, &window_descriptor);
+    if let (Some(position), Some(last_position)) = (position, *last_position) {
+        let moved = (position - last_position).abs();
+        if moved.x > arena.x / 2.0 || moved.y > arena.y / 2.0 {
+            profile.tutorial.wrap = true;
+        }
+    }
+    *last_position = position;
+}
+
+// Prompts are only for the first wave, anyone still going after that has worked it out.
+fn show_tutorial_prompt(
+    profile: Res<Profile>,
+    settings: Res<Settings>,
+    wave: Res<WaveState>,
+    playback: Option<Res<Playback>>,
+    demo: Option<Res<Demo>>,
+    query_ship: Query<(&PlayerId, &ControlScheme), With<Spaceship>>,
+    mut query_prompt: Query<(&mut Style, &mut Text), With<TutorialPrompt>>,
+) {
+    let scheme = query_ship.iter().find(|(player, _)| player.0 == 0).map(|(_, scheme)| *scheme);
+    let step = profile.tutorial.next_step().filter(|_| {
+        profile.games_played == 0 && wave.number <= 1 && playback.is_none() && demo.is_none()
+    });
+    let message = match (step, scheme) {
+        (Some(step), Some(scheme)) => Some(prompt(step, &scheme, settings.control_preset)),
+        _ => None,
+    };
+    for (mut style, mut text) in query_prompt.iter_mut() {
+        let display = if message.is_some() { Display::Flex } else { Display::None };
+        if style.display != display {
+            style.display = display;
+        }
+        if let Some(message) = &message {
+            set_text(&mut text, message);
+        }
+    }
+}
+
+fn hide_tutorial_prompt(mut query: Query<&mut Style, With<TutorialPrompt>>) {
+    for mut style in query.iter_mut() {
+        style.display = Display::None;
+    }
+}
@@ -0,0 +1,258 @@
+// Lets the chat of the Twitch channel given with --twitch join in. Chat is read anonymously over
+// Twitch's IRC interface on a background thread. Typing !meteor or !powerup is a vote, one per
+// viewer per VOTE_WINDOW, and the winner is queued when the window closes. Channel point rewards
+// whose message is one of those commands skip the vote and are queued straight away. The queue
+// lets out one spawn every SPAWN_INTERVAL so chat can't bury the streamer.
+use std::collections::{HashSet, VecDeque};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+
+use bevy::prelude::*;
+use rand::{random, thread_rng, Rng};
+
+use crate::launch::LaunchOptions;
+use crate::powerups::POWERUP_KINDS;
+use crate::replay::Playback;
+use crate::spawning::{MeteorSpec, PowerupSpec, SpawnCommands};
+use crate::ui::set_text;
+use crate::{arena_size, AppState, Game, GameState};
+
+const TWITCH_IRC: &str = "irc.chat.twitch.tv:6667";
+const VOTE_WINDOW: f32 = 30.0;
+const SPAWN_INTERVAL: f32 = 5.0;
+const MAX_QUEUED: usize = 10;
+const CHAT_METEOR_SIZE: u8 = 4;
+const CHAT_METEOR_SPEED: f32 = 60.0;
+const CHAT_TEXT_COLOUR: Color = Color::rgb(0.6, 0.4, 1.0);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ChatCommand {
+    Meteor,
+    Powerup,
+}
+
+const CHAT_COMMANDS: [ChatCommand; 2] = [ChatCommand::Meteor, ChatCommand::Powerup];
+
+impl ChatCommand {
+    fn parse(message: &str) -> Option<ChatCommand> {
+        match message.split_whitespace().next()?.to_lowercase().as_str() {
+            "!meteor" => Some(ChatCommand::Meteor),
+            "!powerup" => Some(ChatCommand::Powerup),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ChatCommand::Meteor => "!meteor",
+            ChatCommand::Powerup => "!powerup",
+        }
+    }
+}
+
+struct ChatMessage {
+    viewer: String,
+    command: ChatCommand,
+    // Sent with a channel point reward rather than typed as a vote.
+    redeemed: bool,
+}
+
+struct ChatQueue {
+    receiver: Mutex<Receiver<ChatMessage>>,
+    votes: [u32; 2],
+    voted: HashSet<String>,
+    vote_timer: Timer,
+    queue: VecDeque<ChatCommand>,
+    spawn_timer: Timer,
+}
+
+#[derive(Component)]
+struct ChatHud;
+
+pub struct TwitchChat;
+
+impl Plugin for TwitchChat {
+    fn build(&self, app: &mut App) {
+        let options = app.world.get_resource::<LaunchOptions>();
+        let channel_name = match options.and_then(|options| options.twitch.clone()) {
+            Some(channel_name) => channel_name,
+            None => return,
+        };
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            if let Err(err) = read_chat(&channel_name, sender) {
+                warn!("Lost Twitch chat for {}: {}", channel_name, err);
+            }
+        });
+        app
+            .insert_resource(ChatQueue {
+                receiver: Mutex::new(receiver),
+                votes: [0; 2],
+                voted: HashSet::new(),
+                vote_timer: Timer::from_seconds(VOTE_WINDOW, true),
+                queue: VecDeque::new(),
+                spawn_timer: Timer::from_seconds(SPAWN_INTERVAL, true),
+            })
+            .add_startup_system(setup_chat_hud)
+            .add_system(collect_chat)
+            .add_system(update_chat_hud)
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(close_vote.after(collect_chat))
+                    .with_system(spawn_from_queue.after(close_vote)),
+            );
+    }
+}
+
+// Anonymous logins can read any channel's chat without a token.
+fn read_chat(channel_name: &str, sender: Sender<ChatMessage>) -> std::io::Result<()> {
+    let mut stream = TcpStream::connect(TWITCH_IRC)?;
+    write!(
+        stream,
+        "CAP REQ :twitch.tv/tags\r\nPASS SCHMOOPIIE\r\nNICK justinfan{}\r\nJOIN #{}\r\n",
+        random::<u16>(),
+        channel_name
+    )?;
+    info!("Reading Twitch chat for {}", channel_name);
+    let reader = BufReader::new(stream.try_clone()?);
+    for line in reader.lines() {
+        let line = line?;
+        if line.starts_with("PING") {
+            write!(stream, "{}\r\n", line.replacen("PING", "PONG", 1))?;
+        } else if let Some(message) = parse_chat_line(&line) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Lines look like `@tag=value;... :viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #channel :text`.
+fn parse_chat_line(line: &str) -> Option<ChatMessage> {
+    let (tags, rest) = match line.strip_prefix('@') {
+        Some(tagged) => tagged.split_once(' ')?,
+        None => ("", line),
+    };
+    let (prefix, rest) = rest.strip_prefix(':')?.split_once(' ')?;
+    let text = rest.strip_prefix("PRIVMSG ")?.split_once(" :")?.1;
+    Some(ChatMessage {
+        viewer: prefix.split('!').next()?.to_string(),
+        command: ChatCommand::parse(text)?,
+        redeemed: tags.split(';').any(|tag| tag.starts_with("custom-reward-id=")),
+    })
+}
+
+fn collect_chat(mut chat: ResMut<ChatQueue>) {
+    let messages: Vec<ChatMessage> = match chat.receiver.lock() {
+        Ok(receiver) => receiver.try_iter().collect(),
+        Err(_) => return,
+    };
+    for message in messages {
+        if message.redeemed {
+            if chat.queue.len() < MAX_QUEUED {
+                chat.queue.push_back(message.command);
+            }
+        } else if chat.voted.insert(message.viewer) {
+            let index = CHAT_COMMANDS.iter().position(|command| *command == message.command).unwrap_or(0);
+            chat.votes[index] += 1;
+        }
+    }
+}
+
+fn close_vote(time: Res<Time>, mut chat: ResMut<ChatQueue>) {
+    if !chat.vote_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let (winner, votes) = CHAT_COMMANDS
+        .iter()
+        .zip(chat.votes)
+        .max_by_key(|(_, votes)| *votes)
+        .map(|(command, votes)| (*command, votes))
+        .unwrap_or((ChatCommand::Meteor, 0));
+    if votes > 0 && chat.queue.len() < MAX_QUEUED {
+        chat.queue.push_back(winner);
+    }
+    chat.votes = [0; 2];
+    chat.voted.clear();
+}
+
+// Chat's spawns don't come from GameRng and aren't recorded, which is why replay.rs doesn't record
+// games played with --twitch. They'd change a replay being watched too, so chat waits for it to end.
+fn spawn_from_queue(
+    mut commands: Commands,
+    time: Res<Time>,
+    windows: Res<Windows>,
+    window_descriptor: Res<WindowDescriptor>,
+    query_game: Query<&Game>,
+    playback: Option<Res<Playback>>,
+    mut chat: ResMut<ChatQueue>,
+) {
+    if playback.is_some()
+        || !matches!(query_game.single().gameState, GameState::InProgress)
+        || !chat.spawn_timer.tick(time.delta()).just_finished()
+    {
+        return;
+    }
+    let command = match chat.queue.pop_front() {
+        Some(command) => command,
+        None => return,
+    };
+    let mut rng = thread_rng();
+    let arena = arena_size(&windows, &window_descriptor);
+    // Meteors drift in from the edge, pickups can turn up anywhere.
+    let position = Vec2::new((rng.gen::<f32>() - 0.5) * arena.x, (rng.gen::<f32>() - 0.5) * arena.y);
+    match command {
+        ChatCommand::Meteor => {
+            let edge = Vec2::new(arena.x / 2.0 * position.x.signum(), position.y);
+            let velocity = -edge.normalize_or_zero() * CHAT_METEOR_SPEED;
+            commands.spawn_meteor(MeteorSpec::new(CHAT_METEOR_SIZE).at(edge).with_velocity(velocity));
+        }
+        ChatCommand::Powerup => {
+            let kind = POWERUP_KINDS[rng.gen_range(0..POWERUP_KINDS.len())];
+            commands.spawn_powerup(PowerupSpec::new(kind).at(position));
+        }
+    }
+    info!("Chat sent a {}", command.name());
+}
+
+fn setup_chat_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("BungeeSpice-Regular.ttf"),
+                    font_size: 20.0,
+                    color: CHAT_TEXT_COLOUR,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect { left: Val::Px(10.0), bottom: Val::Px(50.0), ..default() },
+                ..default()
+            }),
+        )
+        .insert(ChatHud);
+}
+
+fn update_chat_hud(chat: Res<ChatQueue>, mut query_text: Query<&mut Text, With<ChatHud>>) {
+    let tally = CHAT_COMMANDS
+        .iter()
+        .zip(chat.votes)
+        .map(|(command, votes)| format!("{} {}", command.name(), votes))
+        .collect::<Vec<_>>()
+        .join("  ");
+    let value = format!(
+        "Chat: {}  Next vote in {:.0}s  Queued: {}",
+        tally,
+        chat.vote_timer.duration().as_secs_f32() - chat.vote_timer.elapsed_secs(),
+        chat.queue.len()
+    );
+    for mut text in query_text.iter_mut() {
+        set_text(&mut text, &value);
+    }
+}
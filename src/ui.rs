@@ -0,0 +1,9 @@
+// Helpers for the text that the HUD, overlays and menus keep up to date while they're showing.
+use bevy::prelude::*;
+
+// Only touches the text when it differs, so the layout isn't recomputed every frame.
+pub fn set_text(text: &mut Mut<Text>, value: &str) {
+    if text.sections[0].value != value {
+        text.sections[0].value = value.to_string();
+    }
+}
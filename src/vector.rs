@@ -0,0 +1,221 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
+
+use crate::config::GameConfig;
+use crate::fallback::MissingArt;
+use crate::settings::{RenderStyle, Settings};
+use crate::theme::Theme;
+use crate::{Bullet, Meteor, Spaceship};
+
+// The outlines' usual colours come from the theme, see theme.rs.
+// High contrast draws the outlines over the sprites, in colours picked to stand apart from each other.
+const SHIP_HIGH_CONTRAST_COLOUR: Color = Color::WHITE;
+const METEOR_HIGH_CONTRAST_COLOUR: Color = Color::rgb(1.0, 1.0, 0.0);
+const BULLET_HIGH_CONTRAST_COLOUR: Color = Color::rgb(0.0, 1.0, 1.0);
+// In front of the sprite it outlines.
+const OUTLINE_Z: f32 = 0.1;
+const METEOR_CORNERS: usize = 11;
+const BULLET_RADIUS: f32 = 3.0;
+
+// Line outlines drawn instead of the sprite for the classic arcade look, with the render style
+// setting or a theme that asks for them.
+#[derive(Component)]
+struct VectorOutline;
+
+struct VectorShape {
+    mesh: Mesh2dHandle,
+    material: Handle<ColorMaterial>,
+    high_contrast_colour: Color,
+}
+
+struct VectorShapes {
+    ship: VectorShape,
+    meteor: VectorShape,
+    bullet: VectorShape,
+}
+
+pub struct VectorGraphics;
+
+impl Plugin for VectorGraphics {
+    fn build(&self, app: &mut App) {
+        app
+            .add_startup_system(create_vector_shapes)
+            .add_system(add_vector_outlines)
+            .add_system(resize_meteor_outlines)
+            .add_system(apply_render_style.after(add_vector_outlines))
+            .add_system(apply_outline_colours);
+    }
+}
+
+// A closed line strip through `points`. Mesh2d wants normals and UVs even though lines don't use them.
+fn outline_mesh(points: &[Vec2]) -> Mesh {
+    let positions: Vec<[f32; 3]> = points.iter().map(|point| [point.x, point.y, 0.0]).collect();
+    let mut indices: Vec<u32> = (0..points.len() as u32).collect();
+    indices.push(0);
+    let mut mesh = Mesh::new(PrimitiveTopology::LineStrip);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; positions.len()]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+// Points on a unit circle pushed in and out by a fixed pattern so every meteor has the same
+// lumpy outline, scaled up to the meteor's size when it's attached.
+fn meteor_points() -> Vec<Vec2> {
+    const BUMPS: [f32; 4] = [1.0, 0.8, 0.95, 0.75];
+    (0..METEOR_CORNERS)
+        .map(|corner| {
+            let angle = corner as f32 / METEOR_CORNERS as f32 * TAU;
+            Vec2::from_angle(angle) * BUMPS[corner % BUMPS.len()]
+        })
+        .collect()
+}
+
+fn create_vector_shapes(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    // Coloured in by apply_outline_colours.
+    let mut shape = |points: &[Vec2], high_contrast_colour: Color| VectorShape {
+        mesh: meshes.add(outline_mesh(points)).into(),
+        material: materials.add(ColorMaterial::default()),
+        high_contrast_colour,
+    };
+    // The ship sprite points along +x and is 65 by 33.
+    let ship = shape(
+        &[
+            Vec2::new(32.5, 0.0),
+            Vec2::new(-32.5, 16.5),
+            Vec2::new(-20.0, 0.0),
+            Vec2::new(-32.5, -16.5),
+        ],
+        SHIP_HIGH_CONTRAST_COLOUR,
+    );
+    let meteor = shape(&meteor_points(), METEOR_HIGH_CONTRAST_COLOUR);
+    let bullet = shape(
+        &[
+            Vec2::new(BULLET_RADIUS, 0.0),
+            Vec2::new(0.0, BULLET_RADIUS),
+            Vec2::new(-BULLET_RADIUS, 0.0),
+            Vec2::new(0.0, -BULLET_RADIUS),
+        ],
+        BULLET_HIGH_CONTRAST_COLOUR,
+    );
+    commands.insert_resource(VectorShapes { ship, meteor, bullet });
+}
+
+fn add_vector_outlines(
+    mut commands: Commands,
+    shapes: Res<VectorShapes>,
+    config: Res<GameConfig>,
+    query_ship: Query<Entity, Added<Spaceship>>,
+    query_meteor: Query<(Entity, &Meteor, Option<&Children>), Added<Meteor>>,
+    query_bullet: Query<Entity, Added<Bullet>>,
+    query_outlines: Query<(), With<VectorOutline>>,
+) {
+    // Meteors taken back out of the pool already have theirs.
+    let outlined = |children: Option<&Children>| {
+        children.map_or(false, |children| children.iter().any(|child| query_outlines.contains(*child)))
+    };
+    let outlines = query_ship
+        .iter()
+        .map(|entity| (entity, &shapes.ship, 1.0))
+        .chain(
+            query_meteor
+                .iter()
+                .filter(|(_, _, children)| !outlined(*children))
+                .map(|(entity, meteor, _)| (entity, &shapes.meteor, meteor_outline_scale(&config, meteor))),
+        )
+        .chain(query_bullet.iter().map(|entity| (entity, &shapes.bullet, 1.0)));
+    for (entity, shape, scale) in outlines {
+        commands.entity(entity).with_children(|parent| {
+            parent
+                .spawn_bundle(MaterialMesh2dBundle {
+                    mesh: shape.mesh.clone(),
+                    material: shape.material.clone(),
+                    transform: Transform {
+                        translation: Vec3::new(0.0, 0.0, OUTLINE_Z),
+                        scale: Vec3::new(scale, scale, 1.0),
+                        ..default()
+                    },
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                })
+                .insert(VectorOutline);
+        });
+    }
+}
+
+fn meteor_outline_scale(config: &GameConfig, meteor: &Meteor) -> f32 {
+    config.asteroid_base * meteor.size as f32 / 2.0
+}
+
+// A meteor from the pool can come back a different size to the one its outline was made for.
+fn resize_meteor_outlines(
+    config: Res<GameConfig>,
+    query_meteor: Query<(&Meteor, &Children), Changed<Meteor>>,
+    mut query_outlines: Query<&mut Transform, With<VectorOutline>>,
+) {
+    for (meteor, children) in query_meteor.iter() {
+        let scale = meteor_outline_scale(&config, meteor);
+        for child in children.iter() {
+            if let Ok(mut transform) = query_outlines.get_mut(*child) {
+                transform.scale = Vec3::new(scale, scale, 1.0);
+            }
+        }
+    }
+}
+
+// Visibility isn't inherited in this version of Bevy, so the parent sprite and its outline
+// can be shown and hidden independently. High contrast shows both. Ships and meteors whose sheet
+// didn't load are outlined whatever the style, see fallback.rs.
+fn apply_render_style(
+    settings: Res<Settings>,
+    theme: Res<Theme>,
+    missing: Res<MissingArt>,
+    added_outlines: Query<(), Added<VectorOutline>>,
+    mut query_sprites: Query<
+        (&mut Visibility, Option<&Children>, Option<&Spaceship>, Option<&Meteor>),
+        (Or<(With<Spaceship>, With<Meteor>, With<Bullet>)>, Without<VectorOutline>),
+    >,
+    mut query_outlines: Query<&mut Visibility, With<VectorOutline>>,
+) {
+    if !settings.is_changed() && !theme.is_changed() && !missing.is_changed() && added_outlines.is_empty() {
+        return;
+    }
+    let vector = settings.render_style == RenderStyle::Vector || theme.outlines;
+    for (mut visibility, children, ship, meteor) in query_sprites.iter_mut() {
+        let outlined = vector || (ship.is_some() && missing.ship) || (meteor.is_some() && missing.meteors);
+        visibility.is_visible = !outlined;
+        for child in children.into_iter().flat_map(|children| children.iter()) {
+            if let Ok(mut visibility) = query_outlines.get_mut(*child) {
+                visibility.is_visible = outlined || settings.high_contrast;
+            }
+        }
+    }
+}
+
+// Every outline of a kind shares one material, so switching colours only touches the three of them.
+fn apply_outline_colours(
+    settings: Res<Settings>,
+    theme: Res<Theme>,
+    shapes: Res<VectorShapes>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if !settings.is_changed() && !theme.is_changed() {
+        return;
+    }
+    let colours = theme.outline_colours;
+    let outlines = [(&shapes.ship, colours.ship), (&shapes.meteor, colours.meteor), (&shapes.bullet, colours.bullet)];
+    for (shape, themed) in outlines {
+        let colour = if settings.high_contrast { shape.high_contrast_colour } else { themed };
+        if let Some(material) = materials.get_mut(&shape.material) {
+            material.color = colour;
+        }
+    }
+}
@@ -0,0 +1,160 @@
+// On-screen versions of the sounds that tell the player something, for deaf and hard of hearing
+// players: the screen edges flash and a label says what the sound was. They cover the stingers and
+// the music's lead coming in once the field gets dangerous. Off unless turned on in the settings.
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+use crate::music::DangerLevel;
+use crate::palette::Tint;
+use crate::settings::Settings;
+use crate::stingers::{Milestone, MilestoneEvent};
+
+const CUE_DURATION: f32 = 1.5;
+const EDGE_THICKNESS: f32 = 8.0;
+// Where the lead stem fades into the music, see music.rs.
+const DANGER_CUE_LEVEL: f32 = 0.65;
+
+#[derive(Clone, Copy)]
+enum Cue {
+    Milestone(Milestone),
+    Danger,
+}
+
+impl Cue {
+    fn label(&self) -> &'static str {
+        match self {
+            Cue::Milestone(Milestone::ExtraLife) => "+1 Life",
+            Cue::Milestone(Milestone::WaveCleared) => "Wave Cleared",
+            Cue::Milestone(Milestone::NewHighScore) => "New High Score",
+            Cue::Danger => "Danger",
+        }
+    }
+
+    fn tint(&self) -> Tint {
+        match self {
+            Cue::Milestone(Milestone::ExtraLife) => Tint::Friendly,
+            Cue::Milestone(_) => Tint::Accent,
+            Cue::Danger => Tint::Danger,
+        }
+    }
+}
+
+// Everything in a cue fades out together and is despawned with it.
+#[derive(Component)]
+struct VisualCue(Timer);
+
+pub struct VisualCues;
+
+impl Plugin for VisualCues {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system(show_cues)
+            .add_system(fade_cues.after(show_cues));
+    }
+}
+
+fn edge(position: UiRect<Val>, size: Size<Val>) -> NodeBundle {
+    NodeBundle {
+        style: Style { position_type: PositionType::Absolute, position, size, ..default() },
+        focus_policy: FocusPolicy::Pass,
+        ..default()
+    }
+}
+
+fn spawn_cue(commands: &mut Commands, asset_server: &AssetServer, cue: Cue) {
+    let thickness = Val::Px(EDGE_THICKNESS);
+    let full = Val::Percent(100.0);
+    let edges = [
+        edge(UiRect { left: Val::Px(0.0), top: Val::Px(0.0), ..default() }, Size::new(full, thickness)),
+        edge(UiRect { left: Val::Px(0.0), bottom: Val::Px(0.0), ..default() }, Size::new(full, thickness)),
+        edge(UiRect { left: Val::Px(0.0), top: Val::Px(0.0), ..default() }, Size::new(thickness, full)),
+        edge(UiRect { right: Val::Px(0.0), top: Val::Px(0.0), ..default() }, Size::new(thickness, full)),
+    ];
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(full, full),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                // The UI's y axis points up in this version of Bevy, so this is the top of the screen.
+                align_items: AlignItems::FlexEnd,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        })
+        .insert(VisualCue(Timer::from_seconds(CUE_DURATION, false)))
+        .with_children(|parent| {
+            for edge in edges {
+                parent.spawn_bundle(edge).insert(cue.tint());
+            }
+            parent
+                .spawn_bundle(
+                    TextBundle::from_section(
+                        cue.label(),
+                        TextStyle {
+                            font: asset_server.load("BungeeSpice-Regular.ttf"),
+                            font_size: 30.0,
+                            ..default()
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect { top: Val::Px(60.0), ..default() },
+                        ..default()
+                    }),
+                )
+                .insert(cue.tint());
+        });
+}
+
+// A newer cue replaces whatever is still showing rather than piling on top of it.
+fn show_cues(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    danger: Res<DangerLevel>,
+    mut milestone_event: EventReader<MilestoneEvent>,
+    query_cues: Query<Entity, With<VisualCue>>,
+    mut dangerous: Local<bool>,
+) {
+    let mut cue = milestone_event.iter().last().map(|ev| Cue::Milestone(ev.milestone));
+    let now_dangerous = danger.0 >= DANGER_CUE_LEVEL;
+    if now_dangerous && !*dangerous {
+        cue = Some(Cue::Danger);
+    }
+    *dangerous = now_dangerous;
+    let cue = match cue {
+        Some(cue) if settings.visual_cues => cue,
+        _ => return,
+    };
+    for entity in query_cues.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_cue(&mut commands, &asset_server, cue);
+}
+
+fn fade_cues(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query_cues: Query<(Entity, &mut VisualCue, &Children)>,
+    mut query_parts: Query<(Option<&mut UiColor>, Option<&mut Text>)>,
+) {
+    for (entity, mut cue, children) in query_cues.iter_mut() {
+        if cue.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let alpha = 1.0 - cue.0.percent();
+        for child in children.iter() {
+            if let Ok((colour, text)) = query_parts.get_mut(*child) {
+                if let Some(mut colour) = colour {
+                    colour.0.set_a(alpha);
+                }
+                if let Some(mut text) = text {
+                    text.sections[0].style.color.set_a(alpha);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::game_mode::GameMode;
+use crate::palette::Tint;
+use crate::replay::GameRng;
+use crate::spawning::{MeteorSpec, SpawnCommands};
+use crate::stingers::{Milestone, MilestoneEvent};
+use crate::ui::set_text;
+use crate::wave_script::WaveScripts;
+use crate::{arena_size, AppState, Game, GameState, Meteor, Parked, RunStats, StartGameEvent};
+
+const WAVE_DELAY: f32 = 2.0;
+// How long survival mode waits before sending the next wave on top of whatever is left.
+const SURVIVAL_WAVE_INTERVAL: f32 = 20.0;
+const BANNER_DURATION: f32 = 2.0;
+const METEOR_SPEED: f32 = 50.0;
+// Keep new meteors away from the middle of the screen where the ships respawn.
+const SAFE_SPAWN_RADIUS: f32 = 150.0;
+
+pub struct WaveStartEvent {
+    pub number: u32,
+}
+
+// Only changes when a new wave starts, so anything showing it can wait for it to change.
+#[derive(Default)]
+pub struct WaveState {
+    pub number: u32,
+}
+
+// Ticked every frame, kept apart from WaveState so that doesn't count as changed every frame too.
+struct WaveTimers {
+    next_wave: Timer,
+    banner: Timer,
+}
+
+impl Default for WaveTimers {
+    fn default() -> Self {
+        WaveTimers {
+            next_wave: Timer::from_seconds(WAVE_DELAY, false),
+            banner: Timer::from_seconds(BANNER_DURATION, false),
+        }
+    }
+}
+
+#[derive(Component)]
+struct WaveUI;
+
+#[derive(Component)]
+struct WaveBanner;
+
+pub struct Waves;
+
+impl Plugin for Waves {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<WaveState>()
+            .init_resource::<WaveTimers>()
+            .add_event::<WaveStartEvent>()
+            .add_startup_system(setup_wave_ui)
+            .add_system(reset_waves)
+            .add_system(update_wave_ui)
+            .add_system(update_wave_banner)
+            .add_system_set(SystemSet::on_update(AppState::InGame).with_system(advance_waves));
+    }
+}
+
+fn wave_meteor_count(number: u32, first_wave: u32) -> u32 {
+    first_wave + number - 1
+}
+
+fn random_spawn_position(rng: &mut impl Rng, arena: Vec2) -> Vec2 {
+    loop {
+        let position = Vec2::new(
+            (rng.gen::<f32>() - 0.5) * arena.x,
+            (rng.gen::<f32>() - 0.5) * arena.y,
+        );
+        if position.length() > SAFE_SPAWN_RADIUS {
+            return position;
+        }
+    }
+}
+
+fn reset_waves(
+    mut game_event: EventReader<StartGameEvent>,
+    mut wave: ResMut<WaveState>,
+    mut timers: ResMut<WaveTimers>,
+) {
+    if game_event.iter().last().is_some() {
+        *wave = WaveState::default();
+        *timers = WaveTimers::default();
+    }
+}
+
+// A wave is cleared once no meteors are left, the next one starts after a short breather.
+// In survival the next one also turns up if the current one takes too long. Scripted waves bring
+// their own meteors, see wave_script.rs.
+fn advance_waves(
+    time: Res<Time>,
+    mode: Res<GameMode>,
+    windows: Res<Windows>,
+    window_descriptor: Res<WindowDescriptor>,
+    query_game: Query<&Game>,
+    query_meteor: Query<(), With<Meteor>>,
+    scripts: Res<WaveScripts>,
+    mut wave: ResMut<WaveState>,
+    mut timers: ResMut<WaveTimers>,
+    mut run_stats: ResMut<RunStats>,
+    mut commands: Commands,
+    mut wave_event: EventWriter<WaveStartEvent>,
+    mut milestone_event: EventWriter<MilestoneEvent>,
+    mut rng: ResMut<GameRng>,
+) {
+    if !matches!(query_game.single().gameState, GameState::InProgress) || !mode.rules().waves {
+        return;
+    }
+    let cleared = query_meteor.is_empty() && !scripts.spawning();
+    let delay = match (cleared, *mode) {
+        (true, _) => WAVE_DELAY,
+        (false, GameMode::Survival) => SURVIVAL_WAVE_INTERVAL,
+        (false, _) => {
+            timers.next_wave.reset();
+            return;
+        }
+    };
+    timers.next_wave.set_duration(Duration::from_secs_f32(delay));
+    if !timers.next_wave.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    if wave.number > 0 && cleared {
+        run_stats.waves_cleared += 1;
+        milestone_event.send(MilestoneEvent { milestone: Milestone::WaveCleared });
+    }
+    wave.number += 1;
+    timers.next_wave.reset();
+    timers.banner.reset();
+    wave_event.send(WaveStartEvent { number: wave.number });
+    if scripts.scripted(wave.number) {
+        return;
+    }
+
+    let arena = arena_size(&windows, &window_descriptor);
+    let rules = mode.rules();
+    let speed = METEOR_SPEED * rules.meteor_speed;
+    for _ in 0..wave_meteor_count(wave.number, rules.first_wave) {
+        let velocity = Vec2::new(rng.0.gen::<f32>() - 0.5, rng.0.gen::<f32>() - 0.5) * 2.0 * speed;
+        let position = random_spawn_position(&mut rng.0, arena);
+        // A station gets every meteor sent its way, none of them too slowly to matter.
+        let velocity = if rules.station {
+            -position.normalize_or_zero() * velocity.length().max(speed / 2.0)
+        } else {
+            velocity
+        };
+        commands.spawn_meteor(MeteorSpec::large().at(position).with_velocity(velocity));
+    }
+}
+
+fn setup_wave_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("BungeeSpice-Regular.ttf");
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(
+                    TextBundle::from_section(
+                        "",
+                        TextStyle { font: font.clone(), font_size: 30.0, ..default() },
+                    )
+                    .with_style(Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect { top: Val::Px(10.0), ..default() },
+                        ..default()
+                    }),
+                )
+                .insert(WaveUI)
+                .insert(Tint::Accent);
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "",
+                    TextStyle { font, font_size: 80.0, ..default() },
+                ))
+                .insert(WaveBanner)
+                .insert(Tint::Accent);
+        });
+}
+
+// Only rebuilt when the wave or the number of meteors is different from what's shown. Counting
+// here rather than watching for meteors coming and going catches ones destroyed or parked after
+// this has run, and parked meteors still waiting to lose Meteor aren't counted.
+fn update_wave_ui(
+    wave: Res<WaveState>,
+    query_meteor: Query<(), (With<Meteor>, Without<Parked>)>,
+    mut shown: Local<Option<(u32, usize)>>,
+    mut query_text: Query<&mut Text, With<WaveUI>>,
+) {
+    let meteors = query_meteor.iter().count();
+    if *shown == Some((wave.number, meteors)) {
+        return;
+    }
+    *shown = Some((wave.number, meteors));
+    let value = if wave.number == 0 {
+        String::new()
+    } else {
+        format!("Wave {}  Meteors: {}", wave.number, meteors)
+    };
+    for mut text in query_text.iter_mut() {
+        set_text(&mut text, &value);
+    }
+}
+
+fn update_wave_banner(
+    time: Res<Time>,
+    wave: Res<WaveState>,
+    mut timers: ResMut<WaveTimers>,
+    mut query_banner: Query<&mut Text, With<WaveBanner>>,
+) {
+    timers.banner.tick(time.delta());
+    let fade = 1.0 - timers.banner.percent();
+    for mut text in query_banner.iter_mut() {
+        if wave.number == 0 || timers.banner.finished() {
+            if !text.sections[0].value.is_empty() {
+                text.sections[0].value.clear();
+            }
+            continue;
+        }
+        text.sections[0].value = format!("WAVE {}", wave.number);
+        text.sections[0].style.color.set_a(fade);
+    }
+}
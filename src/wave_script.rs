@@ -0,0 +1,208 @@
+// Scripted waves, authored as .wave.ron files in assets/waves/. Each file takes over one wave number
+// and lays out what spawns in it, how many seconds after the wave starts, where and how fast, in
+// place of the usual handful of random meteors. The wave isn't cleared until the whole timeline has
+// been spawned and shot down. Positions are fractions of half the arena, so (1.0, 1.0) is the top
+// right corner whatever the window size, and velocities are scaled by the mode's meteor speed like
+// any other wave's.
+use std::collections::HashMap;
+
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+
+use crate::game_mode::GameMode;
+use crate::powerups::PowerupKind;
+use crate::spawning::{MeteorSpec, PowerupSpec, SpawnCommands};
+use crate::wave::WaveStartEvent;
+use crate::{arena_size, AppState, Game, GameState, StartGameEvent};
+
+const WAVES_FOLDER: &str = "waves";
+// Browsers can't list what's in a folder, so there the wave files are asked for by name.
+#[cfg(target_arch = "wasm32")]
+const WEB_WAVES: [&str; 1] = ["waves/meteor_ring.wave.ron"];
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum Spawnable {
+    Meteor { size: u8 },
+    // Pickups stay where they're put, whatever their velocity.
+    Powerup(PowerupKind),
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct ScriptedSpawn {
+    // Seconds after the wave starts.
+    pub at: f32,
+    pub spawn: Spawnable,
+    pub position: Vec2,
+    #[serde(default)]
+    pub velocity: Vec2,
+}
+
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "5d2a9c47-81e3-4f6b-a0c5-7e4b19d3f862"]
+pub struct WaveScript {
+    // The wave this script is played for.
+    pub wave: u32,
+    pub spawns: Vec<ScriptedSpawn>,
+}
+
+#[derive(Default)]
+struct WaveScriptLoader;
+
+impl AssetLoader for WaveScriptLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let script: WaveScript = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(script));
+            Ok(())
+        })
+    }
+
+    // Plain .ron files are config.ron's, the asset server tries the longest extension first.
+    fn extensions(&self) -> &[&str] {
+        &["wave.ron"]
+    }
+}
+
+// A script being played, with its positions and velocities already worked out for this arena.
+struct RunningScript {
+    elapsed: f32,
+    // In the order they're due.
+    spawns: Vec<ScriptedSpawn>,
+    next: usize,
+}
+
+#[derive(Default)]
+pub struct WaveScripts {
+    // Keeps the files loaded so the asset server goes on watching them for changes.
+    handles: Vec<Handle<WaveScript>>,
+    by_wave: HashMap<u32, WaveScript>,
+    running: Option<RunningScript>,
+}
+
+impl WaveScripts {
+    // Replaces whatever was scripted for the same wave.
+    pub fn add(&mut self, script: WaveScript) {
+        self.by_wave.insert(script.wave, script);
+    }
+
+    pub fn scripted(&self, wave: u32) -> bool {
+        self.by_wave.contains_key(&wave)
+    }
+
+    // Whether a script is still waiting to spawn some of its wave.
+    pub fn spawning(&self) -> bool {
+        self.running.as_ref().map_or(false, |running| running.next < running.spawns.len())
+    }
+}
+
+pub struct ScriptedWaves;
+
+impl Plugin for ScriptedWaves {
+    fn build(&self, app: &mut App) {
+        app
+            .add_asset::<WaveScript>()
+            .init_asset_loader::<WaveScriptLoader>()
+            .init_resource::<WaveScripts>()
+            .add_startup_system(load_wave_scripts)
+            .add_system(apply_wave_scripts)
+            .add_system(start_wave_script)
+            .add_system_set(SystemSet::on_update(AppState::InGame).with_system(run_wave_script));
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_wave_scripts(asset_server: Res<AssetServer>, mut scripts: ResMut<WaveScripts>) {
+    match asset_server.load_folder(WAVES_FOLDER) {
+        Ok(handles) => scripts.handles = handles.into_iter().map(|handle| handle.typed()).collect(),
+        Err(err) => warn!("Could not load the {} folder: {:?}", WAVES_FOLDER, err),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_wave_scripts(asset_server: Res<AssetServer>, mut scripts: ResMut<WaveScripts>) {
+    scripts.handles = WEB_WAVES.iter().map(|path| asset_server.load(*path)).collect();
+}
+
+// A script that's edited on disk is picked up for the next time its wave comes round, one that's
+// already playing carries on as it was. Moving a script to another wave takes a restart to forget
+// the old one.
+fn apply_wave_scripts(
+    assets: Res<Assets<WaveScript>>,
+    mut asset_events: EventReader<AssetEvent<WaveScript>>,
+    mut scripts: ResMut<WaveScripts>,
+) {
+    for ev in asset_events.iter() {
+        if let AssetEvent::Created { handle } | AssetEvent::Modified { handle } = ev {
+            if let Some(script) = assets.get(handle) {
+                info!("Loaded the script for wave {}", script.wave);
+                scripts.add(script.clone());
+            }
+        }
+    }
+}
+
+fn start_wave_script(
+    mode: Res<GameMode>,
+    windows: Res<Windows>,
+    window_descriptor: Res<WindowDescriptor>,
+    mut scripts: ResMut<WaveScripts>,
+    mut game_event: EventReader<StartGameEvent>,
+    mut wave_event: EventReader<WaveStartEvent>,
+) {
+    if game_event.iter().last().is_some() {
+        scripts.running = None;
+    }
+    let script = match wave_event.iter().last().and_then(|ev| scripts.by_wave.get(&ev.number)) {
+        Some(script) => script,
+        None => return,
+    };
+    let half_arena = arena_size(&windows, &window_descriptor) / 2.0;
+    let speed = mode.rules().meteor_speed;
+    let mut spawns: Vec<ScriptedSpawn> = script
+        .spawns
+        .iter()
+        .map(|spawn| ScriptedSpawn {
+            position: spawn.position * half_arena,
+            velocity: spawn.velocity * speed,
+            ..*spawn
+        })
+        .collect();
+    spawns.sort_by(|a, b| a.at.total_cmp(&b.at));
+    scripts.running = Some(RunningScript { elapsed: 0.0, spawns, next: 0 });
+}
+
+fn run_wave_script(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut scripts: ResMut<WaveScripts>,
+    query_game: Query<&Game>,
+) {
+    if !matches!(query_game.single().gameState, GameState::InProgress) {
+        return;
+    }
+    let running = match &mut scripts.running {
+        Some(running) => running,
+        None => return,
+    };
+    running.elapsed += time.delta_seconds();
+    while let Some(spawn) = running.spawns.get(running.next).copied() {
+        if spawn.at > running.elapsed {
+            break;
+        }
+        running.next += 1;
+        match spawn.spawn {
+            Spawnable::Meteor { size } => {
+                commands.spawn_meteor(MeteorSpec::new(size).at(spawn.position).with_velocity(spawn.velocity))
+            }
+            Spawnable::Powerup(kind) => {
+                commands.spawn_powerup(PowerupSpec::new(kind).at(spawn.position));
+            }
+        }
+    }
+}